@@ -1,4 +1,6 @@
 mod engine;
+pub(crate) mod host_index;
 mod model;
 
 pub use engine::Engine;
+pub use model::{CrawlStop, PageReport, ScrapReport};
@@ -1,14 +1,129 @@
-use crate::orchestration::model::RegistryMapImplementation;
-use mangater_sdk::entity::AppConfigJson5;
-use mangater_sdk::traits::Registry;
+use crate::orchestration::model::{CrawlStop, PageReport, RegistryMapImplementation, ScrapReport};
+use crate::util::declarative_site::{self, DeclarativeSite};
+use crate::util::plugin_loader;
+#[cfg(feature = "wasm")]
+use crate::util::wasm_host;
+use futures::stream::{self, StreamExt};
+use mangater_sdk::entity::{
+    AppConfigJson5, CacheMode, ContentConfig, ContentFormat, CrawlConfig, HttpCacheConfig,
+    HttpConfig, PatternMatchResult, PatternType, PersistContext, RateLimitConfig, Registerable,
+    RobotsConfig, StorageConfig, StorageFormat,
+};
+use mangater_sdk::storage::{
+    BlobStore, Catalog, CatalogStorage, CbzStorage, DedupStorage, EpubStorage, FileSystemStorage,
+};
+use mangater_sdk::traits::{Domain, Registry, Storage, DEFAULT_PRIORITY};
+use mangater_sdk::util::fixture::Fixtures;
+use mangater_sdk::util::html_parsing::{parse_plain_text, parse_resource_links, resolve_base_url};
+use mangater_sdk::util::http::HttpFetcher;
+use mangater_sdk::util::http_cache::HttpCache;
+use mangater_sdk::util::markdown::parse_markdown;
+use mangater_sdk::util::rate_limit::RateLimiter;
+use mangater_sdk::util::resource::DEFAULT_USER_AGENT;
+use mangater_sdk::util::robots::RobotsCache;
 use mangater_sdk::SdkError;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 pub struct Engine {
     registry: RegistryMapImplementation,
 
     /// configuration (core and plugins)
     config: Option<AppConfigJson5>,
+
+    /// storage used when the resolved plugin does not provide one
+    default_storage: Option<Arc<dyn Storage + Send + Sync>>,
+
+    /// shared HTTP client for every request made by the engine
+    fetcher: HttpFetcher,
+
+    /// HTTP clients of the plugins overriding the core proxy settings, keyed by domain key
+    plugin_fetchers: HashMap<String, HttpFetcher>,
+
+    /// core politeness controls, applied to every domain key without overrides
+    rate_limit: RateLimitConfig,
+
+    /// politeness controls of the plugins overriding the core ones, keyed by domain key
+    plugin_rate_limits: HashMap<String, RateLimitConfig>,
+
+    /// rate limited HTTP clients, keyed by domain key; created on first use
+    domain_fetchers: Mutex<HashMap<String, HttpFetcher>>,
+
+    /// HTTP cache settings (`core.cache`); no cache unless configured or a mode is forced
+    cache: Option<HttpCacheConfig>,
+
+    /// on-disk HTTP cache every HTTP client goes through
+    http_cache: Option<Arc<HttpCache>>,
+
+    /// recorded responses every HTTP client records into or replays from (tests)
+    fixtures: Option<Arc<Fixtures>>,
+
+    /// core robots.txt compliance, applied to every domain key without overrides
+    robots: RobotsConfig,
+
+    /// robots.txt compliance of the plugins overriding the core one, keyed by domain key
+    plugin_robots: HashMap<String, RobotsConfig>,
+
+    /// robots.txt of every host visited
+    robots_cache: RobotsCache,
+
+    /// core pagination limits, applied to every domain key without overrides
+    crawl: CrawlConfig,
+
+    /// pagination limits of the plugins overriding the core ones, keyed by domain key
+    plugin_crawls: HashMap<String, CrawlConfig>,
+
+    /// core content extraction, applied to every domain key without overrides
+    content: ContentConfig,
+
+    /// content extraction of the plugins overriding the core one, keyed by domain key
+    plugin_contents: HashMap<String, ContentConfig>,
+
+    /// core storage settings, used by the file system storage fallback
+    storage: StorageConfig,
+
+    /// output directory overriding `core.storage.output` (e.g. from the command line)
+    output_dir: Option<String>,
+
+    /// storage used when neither the plugin nor the application provides one
+    fallback_storage: Arc<dyn Storage + Send + Sync>,
+
+    /// records every persisted item, whatever the storage (`core.storage.catalog`)
+    catalog: Option<Arc<Catalog>>,
+
+    /// deduplicates the files written, whatever the storage (`core.storage.dedup`)
+    blobs: Option<Arc<BlobStore>>,
+}
+
+/// Settings of a single `Engine::run_scrap_workflow` run, resolved for the plugin's domain key.
+struct ScrapSettings<'a> {
+    domain_key: String,
+    /// url the run started from
+    scrap_url: String,
+    check_robots: bool,
+    max_pages: usize,
+    content: &'a ContentConfig,
+}
+
+impl ScrapSettings<'_> {
+    /// Describes where the content comes from to the storage; the index is set on persist.
+    fn persist_context(&self, page_url: &str, content_type: Option<&str>) -> PersistContext {
+        PersistContext {
+            domain_key: self.domain_key.clone(),
+            scrap_url: self.scrap_url.clone(),
+            page_url: page_url.to_string(),
+            index: 0,
+            content_type: content_type.map(str::to_string),
+        }
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Engine {
@@ -16,41 +131,329 @@ impl Engine {
         Self {
             registry: RegistryMapImplementation::new(),
             config: None,
+            default_storage: None,
+            fetcher: HttpFetcher::new(&HttpConfig::default(), None)
+                .expect("default http client should always be buildable"),
+            plugin_fetchers: HashMap::new(),
+            rate_limit: RateLimitConfig::default(),
+            plugin_rate_limits: HashMap::new(),
+            domain_fetchers: Mutex::new(HashMap::new()),
+            cache: None,
+            http_cache: None,
+            fixtures: None,
+            robots: RobotsConfig::default(),
+            plugin_robots: HashMap::new(),
+            robots_cache: RobotsCache::new(DEFAULT_USER_AGENT),
+            crawl: CrawlConfig::default(),
+            plugin_crawls: HashMap::new(),
+            content: ContentConfig::default(),
+            plugin_contents: HashMap::new(),
+            storage: StorageConfig::default(),
+            output_dir: None,
+            fallback_storage: Arc::new(FileSystemStorage::new(&StorageConfig::default())),
+            catalog: None,
+            blobs: None,
         }
     }
 
+    /// set the storage to fall back to when a plugin does not provide its own storage
+    pub fn set_default_storage(&mut self, storage: Arc<dyn Storage + Send + Sync>) {
+        self.default_storage = Some(storage);
+    }
+
+    /// the catalog of the persisted items, if configured (`core.storage.catalog`)
+    pub fn catalog(&self) -> Option<Arc<Catalog>> {
+        self.catalog.clone()
+    }
+
+    /// override the output directory of the file system storage fallback (`core.storage.output`)
+    pub fn set_output_dir(&mut self, output: String) -> Result<(), SdkError> {
+        self.output_dir = Some(output);
+        self.rebuild_fallback_storage()
+    }
+
+    /// Forces the mode of the HTTP cache (e.g. `--offline` on the command line); the cache is
+    /// enabled with the default settings if `core.cache` is not configured.
+    pub fn set_cache_mode(&mut self, mode: CacheMode) -> Result<(), SdkError> {
+        let mut cache = self.cache.clone().unwrap_or_default();
+        cache.mode = mode;
+        self.cache = Some(cache);
+        self.rebuild_http_cache()
+    }
+
+    /// Records every response into, or replays them from, the given fixtures; for deterministic
+    /// tests of `Engine::run_scrap_workflow` without network (see [`Fixtures`]).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use mangater_core::orchestration::Engine;
+    /// use mangater_sdk::util::fixture::Fixtures;
+    /// use std::sync::Arc;
+    ///
+    /// # async fn run() -> Result<(), mangater_sdk::SdkError> {
+    /// let mut engine = Engine::new();
+    /// // replay, unless run with MANGATER_FIXTURES=record
+    /// engine.set_fixtures(Arc::new(Fixtures::from_env("tests/fixtures")?));
+    /// // register the plugin under test, then
+    /// let report = engine
+    ///     .run_scrap_workflow("https://en.wikipedia.org/wiki/NoSQL".to_string())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_fixtures(&mut self, fixtures: Arc<Fixtures>) {
+        self.fixtures = Some(fixtures);
+        self.reset_fetchers();
+    }
+
+    /// Opens the HTTP cache as per its settings; the HTTP clients are rebuilt to go through it.
+    fn rebuild_http_cache(&mut self) -> Result<(), SdkError> {
+        self.http_cache = match &self.cache {
+            Some(cache) => Some(Arc::new(HttpCache::open(&cache.dir, cache.mode)?)),
+            None => None,
+        };
+        self.reset_fetchers();
+        Ok(())
+    }
+
+    /// Drops the HTTP clients created on first use; for them to pick up the current cache and
+    /// fixtures.
+    fn reset_fetchers(&self) {
+        self.domain_fetchers.lock().unwrap().clear();
+    }
+
+    /// the given HTTP client going through the HTTP cache and the fixtures, if any
+    fn layered(&self, fetcher: &HttpFetcher) -> HttpFetcher {
+        let fetcher = match &self.http_cache {
+            Some(cache) => fetcher.with_cache(Arc::clone(cache)),
+            None => fetcher.clone(),
+        };
+        match &self.fixtures {
+            Some(fixtures) => fetcher.with_fixtures(Arc::clone(fixtures)),
+            None => fetcher,
+        }
+    }
+
+    /// Rebuilds the storages depending on the output directory: the fallback one and the blob store.
+    fn rebuild_fallback_storage(&mut self) -> Result<(), SdkError> {
+        let mut storage = self.storage.clone();
+        if let Some(output) = &self.output_dir {
+            storage.output = output.clone();
+        }
+        self.fallback_storage = match storage.format {
+            StorageFormat::Files => Arc::new(FileSystemStorage::new(&storage)),
+            StorageFormat::Cbz => Arc::new(CbzStorage::new(&storage)),
+            StorageFormat::Epub => Arc::new(EpubStorage::new(&storage)),
+        };
+        self.blobs = match &storage.dedup {
+            Some(dedup) => {
+                let root = dedup
+                    .root
+                    .clone()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| Path::new(&storage.output).join(".blobs"));
+                Some(Arc::new(BlobStore::open(root, dedup.link)?))
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
     /// return a read-only reference to the underlying registry implementation
     pub fn registry(&mut self) -> &mut dyn mangater_sdk::traits::Registry {
         &mut self.registry
     }
 
+    /// Registers the declarative sites: the sections of `plugins` having a `url_regex`, then the
+    /// `.json5` files of `core.site_dir` (if set); each with the priority of its config section
+    /// (`plugins.<key>.core.priority`), replacing the domain already registered under its key, if
+    /// any. See [`declarative_site::DeclarativeSite`].
+    ///
+    /// Returns the domain keys registered, in registration order.
+    pub fn load_declarative_sites(&mut self) -> Result<Vec<String>, SdkError> {
+        let Some(config) = &self.config else {
+            return Ok(Vec::new());
+        };
+
+        let mut sites = config
+            .site_definitions()?
+            .into_iter()
+            .map(DeclarativeSite::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(site_dir) = &config.core.site_dir {
+            sites.extend(declarative_site::load_sites_dir(site_dir)?);
+        }
+
+        let mut keys = Vec::new();
+        for site in sites {
+            let key = site.get_domain_key();
+            let priority = config
+                .plugin_overrides(&key)?
+                .priority
+                .unwrap_or(DEFAULT_PRIORITY);
+            self.registry.add_to_registry_with_priority(
+                Some(key.clone()),
+                Arc::new(site),
+                priority,
+            );
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    /// Loads the dynamic plugins found in `core.plugin_dir` (if set) and registers them, each
+    /// with the priority of its config section (`plugins.<key>.core.priority`); a plugin replaces
+    /// the one already registered under its key, if any. See [`plugin_loader::load_plugin`].
+    ///
+    /// Returns the domain keys registered, in load order.
+    pub fn load_dynamic_plugins(&mut self) -> Result<Vec<String>, SdkError> {
+        let Some(config) = &self.config else {
+            return Ok(Vec::new());
+        };
+        let Some(plugin_dir) = &config.core.plugin_dir else {
+            return Ok(Vec::new());
+        };
+
+        let mut keys = Vec::new();
+        for plugin in plugin_loader::load_plugins_dir(plugin_dir, &config.plugins)? {
+            let key = plugin.domain.get_domain_key();
+            let priority = config
+                .plugin_overrides(&key)?
+                .priority
+                .unwrap_or(DEFAULT_PRIORITY);
+            self.registry
+                .add_to_registry_with_priority(Some(key.clone()), plugin.domain, priority);
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    /// Loads the WebAssembly site plugins found in `core.wasm.plugin_dir` (if set) and registers
+    /// them like [`Engine::load_dynamic_plugins`] does. Their host `fetch` goes through the
+    /// plugin's HTTP client, under its rate limits; load the config first.
+    /// See [`wasm_host::WasmHost`].
+    ///
+    /// Returns the domain keys registered, in load order.
+    #[cfg(feature = "wasm")]
+    pub fn load_wasm_plugins(&mut self) -> Result<Vec<String>, SdkError> {
+        let Some(config) = &self.config else {
+            return Ok(Vec::new());
+        };
+        let Some(wasm) = &config.core.wasm else {
+            return Ok(Vec::new());
+        };
+        let Some(plugin_dir) = &wasm.plugin_dir else {
+            return Ok(Vec::new());
+        };
+
+        let host = wasm_host::WasmHost::new(wasm)?;
+        let plugins = host.load_dir(plugin_dir, &config.plugins, &|key| self.fetcher_for(key))?;
+        let mut keys = Vec::new();
+        for plugin in plugins {
+            let key = plugin.get_domain_key();
+            let priority = config
+                .plugin_overrides(&key)?
+                .priority
+                .unwrap_or(DEFAULT_PRIORITY);
+            self.registry.add_to_registry_with_priority(
+                Some(key.clone()),
+                Arc::new(plugin),
+                priority,
+            );
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
     pub fn config_load_from_json5_file(
         &mut self,
         config_file: String,
     ) -> Result<&AppConfigJson5, SdkError> {
-        let config_content = fs::read_to_string(config_file.clone()).map_err(|e| {
-            SdkError::InvalidConfig(format!("{} - {}", config_file.clone(), e.to_string()))
-        })?;
+        let config_content = fs::read_to_string(config_file.clone())
+            .map_err(|e| SdkError::InvalidConfig(format!("{} - {}", config_file.clone(), e)))?;
 
-        let config: AppConfigJson5 =
-            json5::from_str(&config_content).map_err(|e| SdkError::InvalidConfig(e.to_string()))?;
+        self.config_load_from_json5_str(&config_content)
+    }
 
-        self.config = Some(config);
+    /// Same as [`Engine::config_load_from_json5_file`] with the config content at hand
+    /// (e.g. inlined in a test).
+    pub fn config_load_from_json5_str(
+        &mut self,
+        config_content: &str,
+    ) -> Result<&AppConfigJson5, SdkError> {
+        let config: AppConfigJson5 =
+            json5::from_str(config_content).map_err(|e| SdkError::InvalidConfig(e.to_string()))?;
 
-        Ok(self.config.as_ref().unwrap())
+        self.apply_config(config)
     }
 
     pub fn config_load_from_json_file(
         &mut self,
         config_file: String,
     ) -> Result<&AppConfigJson5, SdkError> {
-        let config_content = fs::read_to_string(config_file.clone()).map_err(|e| {
-            SdkError::InvalidConfig(format!("{} - {}", config_file.clone(), e.to_string()))
-        })?;
+        let config_content = fs::read_to_string(config_file.clone())
+            .map_err(|e| SdkError::InvalidConfig(format!("{} - {}", config_file.clone(), e)))?;
 
         let config: AppConfigJson5 = serde_json::from_str(&config_content)
             .map_err(|e| SdkError::InvalidConfig(e.to_string()))?;
 
+        self.apply_config(config)
+    }
+
+    /// Keeps the loaded config and rebuilds the components depending on it (e.g. the HTTP client
+    /// and its cache). The rebuilt HTTP client is shared with the sdk's `util::resource` helpers as well.
+    ///
+    /// Plugins overriding the proxy or rate limit settings (e.g. `plugins.wikipedia.core.proxy`)
+    /// get their own HTTP client; plugins may opt out of the robots.txt checks as well
+    /// (e.g. `plugins.wikipedia.core.robots.enabled`).
+    fn apply_config(&mut self, config: AppConfigJson5) -> Result<&AppConfigJson5, SdkError> {
+        let http_config = config.core.http.clone().unwrap_or_default();
+        self.fetcher = HttpFetcher::new(&http_config, config.core.proxy.as_ref())?;
+        self.rate_limit = config.core.rate_limit.clone().unwrap_or_default();
+        self.robots = config.core.robots.clone().unwrap_or_default();
+        self.crawl = config.core.crawl.clone().unwrap_or_default();
+        self.content = config.core.content.clone().unwrap_or_default();
+        self.storage = config.core.storage.clone().unwrap_or_default();
+        self.rebuild_fallback_storage()?;
+        self.catalog = match &self.storage.catalog {
+            Some(path) => Some(Arc::new(Catalog::open(path)?)),
+            None => None,
+        };
+        self.robots_cache = RobotsCache::new(
+            http_config
+                .user_agent
+                .as_deref()
+                .unwrap_or(DEFAULT_USER_AGENT),
+        );
+
+        self.plugin_fetchers.clear();
+        self.plugin_rate_limits.clear();
+        self.plugin_robots.clear();
+        self.plugin_crawls.clear();
+        self.plugin_contents.clear();
+        for domain_key in config.plugins.keys() {
+            let overrides = config.plugin_overrides(domain_key)?;
+            if let Some(proxy) = overrides.proxy {
+                let fetcher = HttpFetcher::new(&http_config, Some(&proxy))?;
+                self.plugin_fetchers.insert(domain_key.clone(), fetcher);
+            }
+            if let Some(rate_limit) = overrides.rate_limit {
+                self.plugin_rate_limits
+                    .insert(domain_key.clone(), rate_limit);
+            }
+            if let Some(robots) = overrides.robots {
+                self.plugin_robots.insert(domain_key.clone(), robots);
+            }
+            if let Some(crawl) = overrides.crawl {
+                self.plugin_crawls.insert(domain_key.clone(), crawl);
+            }
+            if let Some(content) = overrides.content {
+                self.plugin_contents.insert(domain_key.clone(), content);
+            }
+        }
+        self.cache = config.core.cache.clone();
+        self.rebuild_http_cache()?;
+
         self.config = Some(config);
 
         Ok(self.config.as_ref().unwrap())
@@ -58,19 +461,1032 @@ impl Engine {
 }
 
 impl Engine {
-    pub async fn run_scrap_workflow(&self, url: String) -> Result<(), SdkError> {
-        let domain = self.registry.resolve_domain(url.as_str());
+    /// Runs the full scrap workflow against the given url.
+    ///
+    /// The flow is:
+    /// 1. resolve the `Domain` (plugin) supporting the url through the registry; the highest
+    ///    priority one wins if several match (see `Registry::add_to_registry_with_priority`)
+    /// 2. ask the plugin's `Matcher` for the patterns to apply
+    /// 3. fetch the page (only if any pattern needs it) and apply each pattern by its `PatternType`
+    ///    - `Resource` - collect the asset urls and download them
+    ///    - `Content` - extract the content as plain-text or Markdown (`content.format`)
+    ///    - `Pagination` - follow the "next" link and repeat step 3 on the next page; stops on the
+    ///      last page (no "next" link), a page already visited or `crawl.max_pages`
+    ///    - `ScrapedContent` - the content is already available, pass it through as-is
+    /// 4. hand the results over to the plugin's `Storage`, the application's default one, or
+    ///    else a [`FileSystemStorage`] (or [`CbzStorage`], [`EpubStorage`]) as per `core.storage`
+    /// 5. let the storage finish the run ([`Storage::finish`]), even if it failed
+    ///
+    /// Every page and resource url is checked against its host's robots.txt first (unless the
+    /// plugin opted out in config); a disallowed page aborts the run with
+    /// `SdkError::RobotsDisallowed`, a disallowed resource is counted as failed.
+    ///
+    /// Results are handed over to the storage in order (page by page, in pattern order within
+    /// a page); the returned [`ScrapReport`] aggregates them with a per page breakdown.
+    ///
+    /// Failures on individual items (e.g. a broken image link) are logged and counted in the
+    /// returned [`ScrapReport`]; failures on fetching a page or persisting an item abort the run.
+    pub async fn run_scrap_workflow(&self, url: String) -> Result<ScrapReport, SdkError> {
         // actually if no Domain found, not supported and throw an error
-        if domain.is_none() {
-            return Err(SdkError::Unsupported(url.to_string()));
+        let domain = self
+            .registry
+            .resolve_domain(url.as_str())?
+            .ok_or_else(|| SdkError::Unsupported(url.to_string()))?;
+
+        let domain_key = domain.get_domain_key();
+        let fetcher = self.fetcher_for(&domain_key);
+        let settings = self.settings_for(&domain_key, &url);
+        let registerable = domain.get_domain_registerable();
+        let patterns = registerable.matcher.match_patterns();
+        tracing::info!("patterns: {:?}", patterns);
+
+        let mut report = ScrapReport::default();
+        let scraped = self
+            .scrap(&fetcher, &settings, &registerable, &patterns, &mut report)
+            .await;
+
+        // the storage gets to finish the run either way, e.g. to bundle what was persisted so far
+        let mut context = settings.persist_context(&url, None);
+        context.index = report.items_downloaded;
+        let finished = self.storage_for(&registerable).finish(&context).await;
+        scraped?;
+        finished?;
+        Ok(report)
+    }
+
+    /// Applies the patterns of a run, starting from its first page; see [`Engine::run_scrap_workflow`].
+    async fn scrap(
+        &self,
+        fetcher: &HttpFetcher,
+        settings: &ScrapSettings<'_>,
+        registerable: &Registerable,
+        patterns: &[PatternMatchResult],
+        report: &mut ScrapReport,
+    ) -> Result<(), SdkError> {
+        let url = &settings.scrap_url;
+
+        // content already ready for storage; no need to fetch the page(s)
+        for pattern in patterns
+            .iter()
+            .filter(|p| p.pattern_type == PatternType::ScrapedContent)
+        {
+            report.items_found += 1;
+            match &pattern.resource_string {
+                Some(content) => {
+                    let content = content.as_bytes().to_vec();
+                    let context = settings.persist_context(url, Some("text/plain; charset=utf-8"));
+                    self.persist(registerable, context, pattern, content, report)
+                        .await?;
+                }
+                None => {
+                    tracing::warn!("scraped-content without content, skipped: {:?}", pattern);
+                    report.items_failed += 1;
+                }
+            }
+        }
+
+        let needs_page = patterns.iter().any(|p| {
+            matches!(
+                p.pattern_type,
+                PatternType::Resource | PatternType::Content | PatternType::Pagination
+            )
+        });
+        if !needs_page {
+            return Ok(());
+        }
+
+        let mut visited = HashSet::new();
+        let mut next_url = Some(url.clone());
+        report.stop_reason = Some(CrawlStop::LastPage);
+        while let Some(page_url) = next_url.take() {
+            if !visited.insert(page_url.clone()) {
+                tracing::debug!("page already visited, stop following: {}", page_url);
+                report.stop_reason = Some(CrawlStop::RepeatedUrl);
+                break;
+            }
+            if settings.max_pages > 0 && report.pages_visited >= settings.max_pages {
+                tracing::info!(
+                    "max pages ({}) reached, stop following: {}",
+                    settings.max_pages,
+                    page_url
+                );
+                report.stop_reason = Some(CrawlStop::MaxPages);
+                break;
+            }
+            next_url = self
+                .scrap_page(fetcher, settings, &page_url, patterns, registerable, report)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Resolves the run settings of the given plugin; its overrides win over the core config.
+    fn settings_for(&self, domain_key: &str, scrap_url: &str) -> ScrapSettings<'_> {
+        ScrapSettings {
+            domain_key: domain_key.to_string(),
+            scrap_url: scrap_url.to_string(),
+            check_robots: self
+                .plugin_robots
+                .get(domain_key)
+                .unwrap_or(&self.robots)
+                .enabled,
+            max_pages: self
+                .plugin_crawls
+                .get(domain_key)
+                .unwrap_or(&self.crawl)
+                .max_pages,
+            content: self
+                .plugin_contents
+                .get(domain_key)
+                .unwrap_or(&self.content),
+        }
+    }
+
+    /// Returns the HTTP client for requests made on behalf of the given plugin;
+    /// every domain key has its own rate limiter (token bucket and concurrency cap), all of them
+    /// share the HTTP cache and the fixtures. The plugin's overrides (`plugins.<key>.core.proxy`,
+    /// `plugins.<key>.core.rate_limit`) apply.
+    ///
+    /// Hand it over to the sdk's `util::resource` helpers for their downloads to be as polite
+    /// as the engine's own.
+    pub fn fetcher_for(&self, domain_key: &str) -> HttpFetcher {
+        let mut fetchers = self.domain_fetchers.lock().unwrap();
+        fetchers
+            .entry(domain_key.to_string())
+            .or_insert_with(|| {
+                let fetcher = self.layered(
+                    self.plugin_fetchers
+                        .get(domain_key)
+                        .unwrap_or(&self.fetcher),
+                );
+                let rate_limit = self
+                    .plugin_rate_limits
+                    .get(domain_key)
+                    .unwrap_or(&self.rate_limit);
+                fetcher.with_rate_limiter(Arc::new(RateLimiter::new(rate_limit)))
+            })
+            .clone()
+    }
+
+    /// Checks the url against the robots.txt of its host (and waits for its `Crawl-delay`);
+    /// a no-op if the plugin opted out of robots.txt compliance.
+    async fn check_robots(
+        &self,
+        fetcher: &HttpFetcher,
+        check_robots: bool,
+        url: &str,
+    ) -> Result<(), SdkError> {
+        if !check_robots {
+            return Ok(());
         }
-        if let Some(domain) = domain {
-            let patterns = domain.get_domain_registerable().matcher.match_patterns();
-            tracing::info!("patterns: {:?}", patterns);
+        self.robots_cache.check(fetcher, url).await
+    }
 
-            // next...
-            // check the patterns and check if need to scrap OR the content already ready for storage...
+    /// Fetches a single page and applies the page-level patterns on it.
+    /// Returns the resolved "next" page url if a `Pagination` pattern matched.
+    async fn scrap_page(
+        &self,
+        fetcher: &HttpFetcher,
+        settings: &ScrapSettings<'_>,
+        page_url: &str,
+        patterns: &[PatternMatchResult],
+        registerable: &Registerable,
+        report: &mut ScrapReport,
+    ) -> Result<Option<String>, SdkError> {
+        self.check_robots(fetcher, settings.check_robots, page_url)
+            .await?;
+        let html = fetcher.fetch_text(page_url, None).await?;
+        report.pages_visited += 1;
+        // relative links are resolved against the page's <base href>, if any
+        let base_url = resolve_base_url(&html, page_url)?;
+        // counters before the page, for the per page breakdown
+        let (found, downloaded, failed) = (
+            report.items_found,
+            report.items_downloaded,
+            report.items_failed,
+        );
+
+        let mut next_url = None;
+        for pattern in patterns {
+            match pattern.pattern_type {
+                PatternType::Resource => {
+                    let links = match parse_resource_links(&html, &pattern.pattern) {
+                        Ok(links) => links,
+                        Err(e) => {
+                            tracing::warn!("failed to match resources on {}: {}", page_url, e);
+                            report.items_failed += 1;
+                            continue;
+                        }
+                    };
+                    let mut resource_urls = Vec::new();
+                    for link in links {
+                        report.items_found += 1;
+                        match base_url.join(&link) {
+                            Ok(resource_url) => resource_urls.push(resource_url.to_string()),
+                            Err(e) => {
+                                tracing::warn!("invalid resource link {}: {}", link, e);
+                                report.items_failed += 1;
+                            }
+                        }
+                    }
+
+                    // download concurrently (bounded by the plugin's max concurrency),
+                    // results are still handed over to the storage in the page order
+                    let concurrency = fetcher.max_concurrency().unwrap_or(1).max(1);
+                    let mut downloads = stream::iter(resource_urls)
+                        .map(|resource_url| async move {
+                            let content = match self
+                                .check_robots(fetcher, settings.check_robots, &resource_url)
+                                .await
+                            {
+                                Ok(()) => fetcher.fetch_bytes(&resource_url, None).await,
+                                Err(e) => Err(e),
+                            };
+                            (resource_url, content)
+                        })
+                        .buffered(concurrency);
+                    while let Some((resource_url, content)) = downloads.next().await {
+                        match content {
+                            Ok(content) => {
+                                let resource = PatternMatchResult {
+                                    pattern: pattern.pattern.clone(),
+                                    pattern_type: PatternType::Resource,
+                                    resource_string: Some(resource_url),
+                                };
+                                let context = settings.persist_context(page_url, None);
+                                self.persist(registerable, context, &resource, content, report)
+                                    .await?;
+                            }
+                            Err(e) => {
+                                tracing::warn!("failed to download {}: {}", resource_url, e);
+                                report.items_failed += 1;
+                            }
+                        }
+                    }
+                }
+                PatternType::Content => {
+                    report.items_found += 1;
+                    let exclude = &settings.content.exclude;
+                    let content = match settings.content.format {
+                        ContentFormat::Text => parse_plain_text(&html, &pattern.pattern, exclude),
+                        ContentFormat::Markdown => {
+                            parse_markdown(&html, page_url, &pattern.pattern, exclude)
+                        }
+                    };
+                    match content {
+                        Ok(text) => {
+                            let resource = PatternMatchResult {
+                                pattern: pattern.pattern.clone(),
+                                pattern_type: PatternType::Content,
+                                resource_string: Some(page_url.to_string()),
+                            };
+                            let content_type = match settings.content.format {
+                                ContentFormat::Text => "text/plain; charset=utf-8",
+                                ContentFormat::Markdown => "text/markdown; charset=utf-8",
+                            };
+                            let context = settings.persist_context(page_url, Some(content_type));
+                            self.persist(
+                                registerable,
+                                context,
+                                &resource,
+                                text.into_bytes(),
+                                report,
+                            )
+                            .await?;
+                        }
+                        Err(e) => {
+                            tracing::warn!("failed to extract content on {}: {}", page_url, e);
+                            report.items_failed += 1;
+                        }
+                    }
+                }
+                PatternType::Pagination => {
+                    // only the first "next" link is followed
+                    let link = parse_resource_links(&html, &pattern.pattern)
+                        .unwrap_or_else(|e| {
+                            tracing::warn!("failed to match pagination on {}: {}", page_url, e);
+                            Vec::new()
+                        })
+                        .into_iter()
+                        .next();
+                    if let Some(link) = link {
+                        match base_url.join(&link) {
+                            Ok(link) => next_url = Some(link.to_string()),
+                            Err(e) => tracing::warn!("invalid pagination link {}: {}", link, e),
+                        }
+                    }
+                }
+                // handled once per run (not per page)
+                PatternType::ScrapedContent => {}
+                PatternType::Others => {
+                    tracing::debug!("pattern type not handled by the engine: {:?}", pattern);
+                }
+            }
         }
+
+        report.pages.push(PageReport {
+            url: page_url.to_string(),
+            items_found: report.items_found - found,
+            items_downloaded: report.items_downloaded - downloaded,
+            items_failed: report.items_failed - failed,
+        });
+        Ok(next_url)
+    }
+
+    /// The plugin's storage, or the application's default storage (see [`Engine::set_default_storage`]),
+    /// or the storage configured by `core.storage`; deduplicating the files written and recording
+    /// into the catalog if configured.
+    fn storage_for(&self, registerable: &Registerable) -> Arc<dyn Storage + Send + Sync> {
+        let mut storage = Arc::clone(
+            registerable
+                .storage
+                .as_ref()
+                .or(self.default_storage.as_ref())
+                .unwrap_or(&self.fallback_storage),
+        );
+        if let Some(blobs) = &self.blobs {
+            storage = Arc::new(DedupStorage::new(storage, Arc::clone(blobs)));
+        }
+        if let Some(catalog) = &self.catalog {
+            storage = Arc::new(CatalogStorage::new(storage, Arc::clone(catalog)));
+        }
+        storage
+    }
+
+    /// Hands the content over to the storage of the run (see [`Engine::storage_for`]).
+    async fn persist(
+        &self,
+        registerable: &Registerable,
+        mut context: PersistContext,
+        resource: &PatternMatchResult,
+        content: Vec<u8>,
+        report: &mut ScrapReport,
+    ) -> Result<(), SdkError> {
+        let size = content.len() as u64;
+        context.index = report.items_downloaded + 1;
+        self.storage_for(registerable)
+            .persist_with_context(&context, resource, content)
+            .await?;
+        report.items_downloaded += 1;
+        report.bytes_downloaded += size;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use async_trait::async_trait;
+    use mangater_sdk::entity::{DedupConfig, ProxyScheme};
+    use mangater_sdk::traits::{Domain, Matcher};
+    use mangater_sdk::util::fixture::FixtureMode;
+    use mangater_sdk::util::resource::download_resource;
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    type Persisted = Vec<(PatternMatchResult, Vec<u8>)>;
+
+    #[derive(Clone, Default)]
+    struct MemoryStorage {
+        persisted: Arc<Mutex<Persisted>>,
+    }
+
+    #[async_trait]
+    impl Storage for MemoryStorage {
+        async fn persist(
+            &self,
+            resource: &PatternMatchResult,
+            resource_content: Vec<u8>,
+        ) -> Result<(), SdkError> {
+            self.persisted
+                .lock()
+                .unwrap()
+                .push((resource.clone(), resource_content));
+            Ok(())
+        }
+    }
+
+    #[derive(Clone)]
+    struct ScrapedDomain {
+        storage: MemoryStorage,
+    }
+
+    impl Domain for ScrapedDomain {
+        fn match_domain(&self, domain: String) -> Result<bool, SdkError> {
+            Ok(domain.starts_with("https://scraped.example.com"))
+        }
+
+        fn get_domain_key(&self) -> String {
+            "scraped".to_string()
+        }
+
+        fn get_domain_registerable(&self) -> Registerable {
+            Registerable {
+                configurator: None,
+                matcher: Arc::new(self.clone()),
+                storage: Some(Arc::new(self.storage.clone())),
+            }
+        }
+    }
+
+    impl Matcher for ScrapedDomain {
+        fn match_patterns(&self) -> Vec<PatternMatchResult> {
+            vec![
+                PatternMatchResult {
+                    pattern: "summary".to_string(),
+                    pattern_type: PatternType::ScrapedContent,
+                    resource_string: Some("already scraped".to_string()),
+                },
+                PatternMatchResult {
+                    pattern: "empty".to_string(),
+                    pattern_type: PatternType::ScrapedContent,
+                    resource_string: None,
+                },
+            ]
+        }
+    }
+
+    /// a plugin crawling manga-like chapters: one image and a caption per page, plus a "next" link
+    #[derive(Clone)]
+    struct PagedDomain {
+        base_url: String,
+        storage: Option<MemoryStorage>,
+    }
+
+    impl Domain for PagedDomain {
+        fn match_domain(&self, domain: String) -> Result<bool, SdkError> {
+            Ok(domain.starts_with(&self.base_url))
+        }
+
+        fn get_domain_key(&self) -> String {
+            "paged".to_string()
+        }
+
+        fn get_domain_registerable(&self) -> Registerable {
+            Registerable {
+                configurator: None,
+                matcher: Arc::new(self.clone()),
+                storage: self
+                    .storage
+                    .clone()
+                    .map(|storage| Arc::new(storage) as Arc<dyn Storage + Send + Sync>),
+            }
+        }
+    }
+
+    impl Matcher for PagedDomain {
+        fn match_patterns(&self) -> Vec<PatternMatchResult> {
+            vec![
+                PatternMatchResult {
+                    pattern: "img.page".to_string(),
+                    pattern_type: PatternType::Resource,
+                    resource_string: None,
+                },
+                PatternMatchResult {
+                    pattern: "p.caption".to_string(),
+                    pattern_type: PatternType::Content,
+                    resource_string: None,
+                },
+                PatternMatchResult {
+                    pattern: "a.next".to_string(),
+                    pattern_type: PatternType::Pagination,
+                    resource_string: None,
+                },
+            ]
+        }
+    }
+
+    fn chapter_page(index: usize, next: Option<&str>) -> String {
+        let next = next
+            .map(|next| format!("<a class=\"next\" href=\"{}\">next</a>", next))
+            .unwrap_or_default();
+        format!(
+            "<html><body><img class=\"page\" src=\"/img/{index}.png\"><p class=\"caption\">page {index}</p>{next}</body></html>"
+        )
+    }
+
+    /// serves the given paths on a local port (404 for anything else, e.g. /robots.txt);
+    /// returns the base url of the site.
+    async fn serve_site(pages: HashMap<&'static str, String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let pages = Arc::new(pages);
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let pages = Arc::clone(&pages);
+                tokio::spawn(async move {
+                    let mut buffer = vec![0u8; 4096];
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+                    let path = request.split_whitespace().nth(1).unwrap_or("/");
+                    let (status, body) = match pages.get(path) {
+                        Some(body) => ("200 OK", body.clone()),
+                        None => ("404 Not Found", String::new()),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                    socket.shutdown().await.unwrap();
+                });
+            }
+        });
+        base_url
+    }
+
+    async fn paged_engine(pages: HashMap<&'static str, String>) -> (Engine, MemoryStorage, String) {
+        let base_url = serve_site(pages).await;
+        let storage = MemoryStorage::default();
+        let mut engine = Engine::new();
+        engine.registry().add_to_registry(
+            None,
+            Arc::new(PagedDomain {
+                base_url: base_url.clone(),
+                storage: Some(storage.clone()),
+            }),
+        );
+        (engine, storage, base_url)
+    }
+
+    fn chapter_site(last_next: Option<&str>) -> HashMap<&'static str, String> {
+        HashMap::from([
+            ("/chapter/1", chapter_page(1, Some("/chapter/2"))),
+            // relative "next" link
+            ("/chapter/2", chapter_page(2, Some("3"))),
+            ("/chapter/3", chapter_page(3, last_next)),
+            ("/img/1.png", "png-1".to_string()),
+            ("/img/2.png", "png-2".to_string()),
+            ("/img/3.png", "png-3".to_string()),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_unsupported() {
+        let engine = Engine::new();
+        match engine
+            .run_scrap_workflow("https://unknown.example.com".to_string())
+            .await
+        {
+            Err(SdkError::Unsupported(url)) => assert_eq!(url, "https://unknown.example.com"),
+            other => panic!("Expected SdkError::Unsupported, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_scraped_content() {
+        let storage = MemoryStorage::default();
+        let mut engine = Engine::new();
+        engine.registry().add_to_registry(
+            None,
+            Arc::new(ScrapedDomain {
+                storage: storage.clone(),
+            }),
+        );
+
+        let report = engine
+            .run_scrap_workflow("https://scraped.example.com/page".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            ScrapReport {
+                pages_visited: 0,
+                items_found: 2,
+                items_downloaded: 1,
+                items_failed: 1,
+                bytes_downloaded: "already scraped".len() as u64,
+                pages: Vec::new(),
+                stop_reason: None,
+            }
+        );
+
+        let persisted = storage.persisted.lock().unwrap();
+        assert_eq!(persisted.len(), 1);
+        assert_eq!(persisted[0].0.pattern, "summary");
+        assert_eq!(persisted[0].1, b"already scraped".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_default_storage() {
+        let plugin_storage = MemoryStorage::default();
+        let default_storage = MemoryStorage::default();
+        let mut engine = Engine::new();
+        engine.set_default_storage(Arc::new(default_storage.clone()));
+        engine.registry().add_to_registry(
+            None,
+            Arc::new(ScrapedDomain {
+                storage: plugin_storage.clone(),
+            }),
+        );
+
+        engine
+            .run_scrap_workflow("https://scraped.example.com/page".to_string())
+            .await
+            .unwrap();
+        // plugin storage wins over the default storage
+        assert_eq!(plugin_storage.persisted.lock().unwrap().len(), 1);
+        assert!(default_storage.persisted.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_pagination() {
+        let (engine, storage, base_url) = paged_engine(chapter_site(None)).await;
+
+        let report = engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+        assert_eq!(report.pages_visited, 3);
+        assert_eq!(report.items_found, 6);
+        assert_eq!(report.items_downloaded, 6);
+        assert_eq!(report.items_failed, 0);
+        assert_eq!(report.stop_reason, Some(CrawlStop::LastPage));
+        assert_eq!(
+            report.pages,
+            (1..=3)
+                .map(|index| PageReport {
+                    url: format!("{}/chapter/{}", base_url, index),
+                    items_found: 2,
+                    items_downloaded: 2,
+                    items_failed: 0,
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // aggregated in page order, pattern order within a page
+        let persisted = storage.persisted.lock().unwrap();
+        let contents: Vec<String> = persisted
+            .iter()
+            .map(|(_, content)| String::from_utf8_lossy(content).to_string())
+            .collect();
+        assert_eq!(
+            contents,
+            vec!["png-1", "page 1", "png-2", "page 2", "png-3", "page 3"]
+        );
+        assert_eq!(
+            persisted[0].0.resource_string,
+            Some(format!("{}/img/1.png", base_url))
+        );
+        assert_eq!(
+            persisted[1].0.resource_string,
+            Some(format!("{}/chapter/1", base_url))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_file_system_fallback() {
+        let base_url = serve_site(chapter_site(None)).await;
+        let output = std::env::temp_dir().join(format!("mangater-engine-{}", std::process::id()));
+        let mut engine = Engine::new();
+        engine
+            .set_output_dir(output.to_string_lossy().to_string())
+            .unwrap();
+        engine.registry().add_to_registry(
+            None,
+            Arc::new(PagedDomain {
+                base_url: base_url.clone(),
+                storage: None,
+            }),
+        );
+
+        engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+
+        // every page of the run lands in the folder of the page it started from
+        let folder = output.join("paged").join("1");
+        let mut files: Vec<String> = std::fs::read_dir(&folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "0001_1.png",
+                "0002_content.txt",
+                "0003_2.png",
+                "0004_content.txt",
+                "0005_3.png",
+                "0006_content.txt"
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(folder.join("0004_content.txt")).unwrap(),
+            "page 2"
+        );
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_cbz_fallback() {
+        let base_url = serve_site(chapter_site(None)).await;
+        let output =
+            std::env::temp_dir().join(format!("mangater-engine-cbz-{}", std::process::id()));
+        let mut engine = Engine::new();
+        engine.storage.format = StorageFormat::Cbz;
+        engine
+            .set_output_dir(output.to_string_lossy().to_string())
+            .unwrap();
+        engine.registry().add_to_registry(
+            None,
+            Arc::new(PagedDomain {
+                base_url: base_url.clone(),
+                storage: None,
+            }),
+        );
+
+        engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+
+        // the whole chapter is archived once the run is over
+        let archive = std::fs::read(output.join("paged").join("1.cbz")).unwrap();
+        assert!(archive.starts_with(b"PK"));
+        for name in ["001.png", "002.png", "003.png", "ComicInfo.xml"] {
+            assert!(
+                archive
+                    .windows(name.len())
+                    .any(|window| window == name.as_bytes()),
+                "{} missing",
+                name
+            );
+        }
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_dedup() {
+        // the same logo on every page
+        let mut site = chapter_site(None);
+        for image in ["/img/1.png", "/img/2.png", "/img/3.png"] {
+            site.insert(image, "logo".to_string());
+        }
+        let base_url = serve_site(site).await;
+        let output =
+            std::env::temp_dir().join(format!("mangater-engine-dedup-{}", std::process::id()));
+        let mut engine = Engine::new();
+        engine.storage.dedup = Some(DedupConfig::default());
+        engine
+            .set_output_dir(output.to_string_lossy().to_string())
+            .unwrap();
+        engine.registry().add_to_registry(
+            None,
+            Arc::new(PagedDomain {
+                base_url: base_url.clone(),
+                storage: None,
+            }),
+        );
+
+        engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+
+        let blobs = engine.blobs.clone().unwrap();
+        let manifest = blobs.manifest().await.unwrap();
+        // the images and the captions (distinct text per page)
+        assert_eq!(manifest.len(), 6);
+        let logo = &manifest
+            .iter()
+            .find(|(path, _)| path.ends_with("0001_1.png"))
+            .unwrap()
+            .1;
+        assert_eq!(blobs.refcount(logo).await.unwrap(), 3);
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_offline() {
+        let (mut engine, storage, base_url) = paged_engine(chapter_site(None)).await;
+        let cache_dir =
+            std::env::temp_dir().join(format!("mangater-engine-cache-{}", std::process::id()));
+        engine.cache = Some(HttpCacheConfig {
+            dir: cache_dir.to_string_lossy().to_string(),
+            mode: CacheMode::Normal,
+        });
+        engine.rebuild_http_cache().unwrap();
+
+        // nothing cached yet
+        engine.set_cache_mode(CacheMode::Offline).unwrap();
+        match engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+        {
+            Err(SdkError::Network(msg)) => assert!(msg.contains("offline")),
+            other => panic!("Expected SdkError::Network, got {:?}", other),
+        }
+
+        engine.set_cache_mode(CacheMode::Normal).unwrap();
+        engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+
+        // the same run, replayed from the cache only
+        engine.set_cache_mode(CacheMode::Offline).unwrap();
+        let report = engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+        assert_eq!(report.pages_visited, 3);
+        assert_eq!(report.items_downloaded, 6);
+        assert_eq!(storage.persisted.lock().unwrap().len(), 12);
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_fixtures() {
+        let (mut recording, recorded, base_url) = paged_engine(chapter_site(None)).await;
+        let root =
+            std::env::temp_dir().join(format!("mangater-engine-fixtures-{}", std::process::id()));
+        recording.set_fixtures(Arc::new(
+            Fixtures::open(&root, FixtureMode::Record).unwrap(),
+        ));
+        recording
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+
+        // the same plugin, against the recorded responses only
+        let replayed = MemoryStorage::default();
+        let mut replaying = Engine::new();
+        replaying.set_fixtures(Arc::new(
+            Fixtures::open(&root, FixtureMode::Replay).unwrap(),
+        ));
+        replaying.registry().add_to_registry(
+            None,
+            Arc::new(PagedDomain {
+                base_url: base_url.clone(),
+                storage: Some(replayed.clone()),
+            }),
+        );
+        let report = replaying
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+        assert_eq!(report.pages_visited, 3);
+        assert_eq!(report.items_downloaded, 6);
+        let contents = |storage: &MemoryStorage| -> Vec<Vec<u8>> {
+            let persisted = storage.persisted.lock().unwrap();
+            persisted
+                .iter()
+                .map(|(_, content)| content.clone())
+                .collect()
+        };
+        assert_eq!(contents(&replayed), contents(&recorded));
+
+        // never recorded
+        match replaying
+            .run_scrap_workflow(format!("{}/chapter/4", base_url))
+            .await
+        {
+            Err(SdkError::Network(msg)) => assert!(msg.contains("no fixture recorded")),
+            other => panic!("Expected SdkError::Network, got {:?}", other),
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_pagination_max_pages() {
+        let (mut engine, storage, base_url) = paged_engine(chapter_site(None)).await;
+        engine.crawl = CrawlConfig { max_pages: 2 };
+
+        let report = engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+        assert_eq!(report.pages_visited, 2);
+        assert_eq!(report.stop_reason, Some(CrawlStop::MaxPages));
+        assert_eq!(storage.persisted.lock().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_run_scrap_workflow_pagination_repeated_url() {
+        // the last page links back to the first one
+        let (engine, _, base_url) = paged_engine(chapter_site(Some("/chapter/1"))).await;
+
+        let report = engine
+            .run_scrap_workflow(format!("{}/chapter/1", base_url))
+            .await
+            .unwrap();
+        assert_eq!(report.pages_visited, 3);
+        assert_eq!(report.stop_reason, Some(CrawlStop::RepeatedUrl));
+    }
+
+    #[test]
+    fn test_plugin_proxy_overrides() {
+        let mut engine = Engine::new();
+        engine
+            .config_load_from_json5_file("testing/config-overrides-unit-test.json5".to_string())
+            .unwrap();
+
+        let config = engine.config.as_ref().unwrap();
+        let proxy = config.core.proxy.as_ref().unwrap();
+        assert_eq!(proxy.url.as_deref(), Some("proxy.local:3128"));
+        assert_eq!(proxy.no_proxy, vec!["localhost".to_string()]);
+
+        let overrides = config.plugin_overrides("manga").unwrap();
+        let manga_proxy = overrides.proxy.unwrap();
+        assert_eq!(manga_proxy.scheme, Some(ProxyScheme::Socks5));
+        // plugins without proxy overrides share the core http client
+        assert!(config
+            .plugin_overrides("wikipedia")
+            .unwrap()
+            .proxy
+            .is_none());
+        assert!(config
+            .plugin_overrides("not-configured")
+            .unwrap()
+            .proxy
+            .is_none());
+
+        assert!(engine.plugin_fetchers.contains_key("manga"));
+        assert!(!engine.plugin_fetchers.contains_key("wikipedia"));
+    }
+
+    #[test]
+    fn test_plugin_rate_limit_overrides() {
+        let mut engine = Engine::new();
+        engine
+            .config_load_from_json5_file("testing/config-overrides-unit-test.json5".to_string())
+            .unwrap();
+
+        assert_eq!(engine.rate_limit.requests_per_second, Some(5.0));
+        assert_eq!(engine.fetcher_for("wikipedia").max_concurrency(), Some(2));
+        assert_eq!(engine.fetcher_for("manga").max_concurrency(), Some(1));
+        // every domain key has its own rate limiter, reused across runs
+        let domain_fetchers = engine.domain_fetchers.lock().unwrap();
+        assert_eq!(domain_fetchers.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resource_helpers_rate_limited_per_plugin() {
+        let base_url = serve_site(HashMap::from([("/page", "<html></html>".to_string())])).await;
+        let url = format!("{}/page", base_url);
+        let mut engine = Engine::new();
+        engine
+            .config_load_from_json5_str(
+                "{ core: {}, plugins: { slow: { core: { rate_limit: { requests_per_second: 10.0 } } } } }",
+            )
+            .unwrap();
+
+        // 10 requests per second with a burst of 1: the 2nd and 3rd downloads wait their turn
+        let slow = engine.fetcher_for("slow");
+        let started = Instant::now();
+        for _ in 0..3 {
+            download_resource(&slow, url.clone(), None).await.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(180));
+
+        // another plugin is not held back by it (no core rate limit)
+        let fast = engine.fetcher_for("fast");
+        let started = Instant::now();
+        for _ in 0..3 {
+            download_resource(&fast, url.clone(), None).await.unwrap();
+        }
+        assert!(started.elapsed() < Duration::from_millis(180));
+    }
+
+    #[test]
+    fn test_plugin_robots_overrides() {
+        let mut engine = Engine::new();
+        assert!(engine.robots.enabled);
+
+        engine
+            .config_load_from_json5_file("testing/config-overrides-unit-test.json5".to_string())
+            .unwrap();
+        assert!(engine.robots.enabled);
+        assert!(!engine.plugin_robots["manga"].enabled);
+        assert!(!engine.plugin_robots.contains_key("wikipedia"));
+    }
+
+    #[test]
+    fn test_plugin_content_overrides() {
+        let mut engine = Engine::new();
+        engine
+            .config_load_from_json5_file("testing/config-overrides-unit-test.json5".to_string())
+            .unwrap();
+
+        let settings = engine.settings_for("wikipedia", "https://example.com");
+        assert_eq!(settings.content.format, ContentFormat::Text);
+        assert_eq!(
+            settings.content.exclude,
+            vec![".infobox", ".navbox", ".mw-editsection"]
+        );
+        assert!(settings.check_robots);
+        let settings = engine.settings_for("manga", "https://example.com");
+        assert_eq!(settings.content.format, ContentFormat::Markdown);
+        assert!(settings.content.exclude.is_empty());
+        assert!(!settings.check_robots);
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// Suffix trie of host patterns (see `Domain::host_patterns`), keyed by the labels of the host
+/// from right to left; e.g. `*.wikipedia.org` lives under `org` → `wikipedia`.
+#[derive(Debug, Default)]
+pub(crate) struct HostIndex {
+    root: HostNode,
+}
+
+#[derive(Debug, Default)]
+struct HostNode {
+    children: HashMap<String, HostNode>,
+    /// entries with an exact host pattern ending at this node
+    exact: Vec<usize>,
+    /// entries with a wildcard pattern ending at this node; they match any deeper host only
+    wildcard: Vec<usize>,
+}
+
+impl HostIndex {
+    /// Indexes the pattern for the given entry; returns `false` (not indexed) if the pattern
+    /// is not a host or a `*.` suffix wildcard of one.
+    pub(crate) fn insert(&mut self, pattern: &str, entry: usize) -> bool {
+        let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+        let (wildcard, host) = match pattern.strip_prefix("*.") {
+            Some(host) => (true, host),
+            None => (false, pattern.as_str()),
+        };
+        let labels: Vec<&str> = host.split('.').collect();
+        if labels.iter().any(|label| !is_label(label)) {
+            return false;
+        }
+
+        let mut node = &mut self.root;
+        for label in labels.into_iter().rev() {
+            node = node.children.entry(label.to_string()).or_default();
+        }
+        let entries = if wildcard {
+            &mut node.wildcard
+        } else {
+            &mut node.exact
+        };
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+        true
+    }
+
+    /// entries whose patterns match the (lowercase) host
+    pub(crate) fn lookup(&self, host: &str) -> BTreeSet<usize> {
+        let host = host.trim_end_matches('.');
+        let mut entries = BTreeSet::new();
+        let mut node = &self.root;
+        let mut labels = host.rsplit('.').peekable();
+        while let Some(label) = labels.next() {
+            let Some(child) = node.children.get(label) else {
+                break;
+            };
+            node = child;
+            match labels.peek() {
+                // labels left: the wildcards of this suffix match
+                Some(_) => entries.extend(&node.wildcard),
+                None => entries.extend(&node.exact),
+            }
+        }
+        entries
+    }
+}
+
+fn is_label(label: &str) -> bool {
+    !label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_index_lookup() {
+        let mut index = HostIndex::default();
+        assert!(index.insert("wikipedia.org", 0));
+        assert!(index.insert("*.wikipedia.org", 0));
+        assert!(index.insert("EN.Wikipedia.org.", 1));
+        assert!(index.insert("*.org", 2));
+        assert!(!index.insert("*", 3));
+        assert!(!index.insert("en.*.org", 3));
+        assert!(!index.insert("https://example.com", 3));
+
+        assert_eq!(index.lookup("wikipedia.org"), BTreeSet::from([0, 2]));
+        assert_eq!(index.lookup("en.wikipedia.org"), BTreeSet::from([0, 1, 2]));
+        assert_eq!(index.lookup("jp.m.wikipedia.org"), BTreeSet::from([0, 2]));
+        assert_eq!(index.lookup("en.wikipedia.org."), BTreeSet::from([0, 1, 2]));
+        assert_eq!(index.lookup("org"), BTreeSet::new());
+        assert_eq!(index.lookup("wikipedia.com"), BTreeSet::new());
+    }
+}
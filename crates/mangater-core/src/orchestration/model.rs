@@ -1,45 +1,217 @@
+use crate::orchestration::host_index::HostIndex;
+
+use mangater_sdk::entity::DomainCandidate;
 use mangater_sdk::traits::Domain;
 use mangater_sdk::traits::Registry;
-use std::collections::HashMap;
+use mangater_sdk::SdkError;
+use std::collections::BTreeSet;
 use std::sync::Arc;
+use url::Url;
+
+/// A domain registered in a [`RegistryMapImplementation`].
+pub struct RegistryEntry {
+    pub key: String,
+    pub priority: i32,
+    pub domain: Arc<dyn Domain>,
+    /// whether any of its host patterns is indexed; otherwise every url is a candidate
+    indexed: bool,
+}
 
+/// Registry keyed by domain key; resolution goes by priority (highest first), then
+/// registration order, so the same registrations always resolve a url the same way.
+///
+/// Domains declaring host patterns are indexed by host: resolving a url only visits the ones
+/// whose patterns match its host (plus the ones declaring none), never the whole registry;
+/// `match_domain` still has the last word on each of them.
 pub struct RegistryMapImplementation {
-    pub registry: HashMap<String, Arc<dyn Domain>>,
+    /// entries in registration order
+    registry: Vec<RegistryEntry>,
+    /// positions in `registry`, in resolution order
+    order: Vec<usize>,
+    /// host patterns of the entries, by rank (position in `order`)
+    hosts: HostIndex,
+    /// ranks of the entries without any host pattern indexed
+    unindexed: BTreeSet<usize>,
 }
 
 impl RegistryMapImplementation {
     pub fn new() -> Self {
         Self {
-            registry: HashMap::new(),
+            registry: Vec::new(),
+            order: Vec::new(),
+            hosts: HostIndex::default(),
+            unindexed: BTreeSet::new(),
+        }
+    }
+
+    /// Rebuilds the resolution order and the host index; registrations are rare, lookups are not.
+    fn reindex(&mut self) {
+        self.order = (0..self.registry.len()).collect();
+        // stable sort; ties keep the registration order
+        self.order
+            .sort_by_key(|&position| std::cmp::Reverse(self.registry[position].priority));
+
+        self.hosts = HostIndex::default();
+        self.unindexed = BTreeSet::new();
+        for (rank, &position) in self.order.iter().enumerate() {
+            let entry = &mut self.registry[position];
+            entry.indexed = false;
+            for pattern in entry.domain.host_patterns() {
+                if self.hosts.insert(&pattern, rank) {
+                    entry.indexed = true;
+                } else {
+                    tracing::warn!(
+                        "domain {} - ignoring invalid host pattern {:?}",
+                        entry.key,
+                        pattern
+                    );
+                }
+            }
+            if !entry.indexed {
+                self.unindexed.insert(rank);
+            }
         }
     }
+
+    /// ranks of the entries whose host patterns match the host of the url
+    fn host_hits(&self, url: &str) -> BTreeSet<usize> {
+        Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| self.hosts.lookup(host)))
+            .unwrap_or_default()
+    }
+
+    /// Entries in resolution order, each with whether the host of the url matched its
+    /// patterns; `None` if it declares none.
+    fn host_matches(&self, url: &str) -> impl Iterator<Item = (&RegistryEntry, Option<bool>)> {
+        let hits = self.host_hits(url);
+        self.order.iter().enumerate().map(move |(rank, &position)| {
+            let entry = &self.registry[position];
+            (entry, entry.indexed.then(|| hits.contains(&rank)))
+        })
+    }
 }
 
 impl Registry for RegistryMapImplementation {
-    fn add_to_registry(&mut self, key: Option<String>, domain: Arc<dyn Domain>) {
+    fn add_to_registry_with_priority(
+        &mut self,
+        key: Option<String>,
+        domain: Arc<dyn Domain>,
+        priority: i32,
+    ) {
         let new_key = match key {
             Some(k) => k,
             None => domain.get_domain_key(),
         };
         // this step should be done by the domain implementation itself (and not here probably)
         //domain.register_domain(new_key.clone(), domain.get_domain_registerable());
-        self.registry.insert(new_key, domain);
+        let entry = RegistryEntry {
+            key: new_key,
+            priority,
+            domain,
+            indexed: false,
+        };
+        match self.registry.iter_mut().find(|e| e.key == entry.key) {
+            Some(existing) => *existing = entry,
+            None => self.registry.push(entry),
+        }
+        self.reindex();
     }
 
-    fn resolve_domain(&self, url: &str) -> Option<Arc<dyn Domain>> {
-        for domain in self.registry.values() {
-            if let Ok(true) = domain.match_domain(url.to_string()) {
-                return Some(Arc::clone(domain));
+    fn resolve_domain(&self, url: &str) -> Result<Option<Arc<dyn Domain>>, SdkError> {
+        // the entries matching the host and the ones without patterns, in resolution order
+        let mut candidates = self.host_hits(url);
+        candidates.extend(&self.unindexed);
+
+        let mut failure = None;
+        for entry in candidates
+            .iter()
+            .map(|&rank| &self.registry[self.order[rank]])
+        {
+            match entry.domain.match_domain(url.to_string()) {
+                Ok(true) => return Ok(Some(Arc::clone(&entry.domain))),
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!("domain {} failed to match {} - {}", entry.key, url, e);
+                    failure.get_or_insert(e);
+                }
             }
         }
-        None
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    fn resolve_candidates(&self, url: &str) -> Vec<DomainCandidate> {
+        self.host_matches(url)
+            .map(|(entry, host_match)| DomainCandidate {
+                key: entry.key.clone(),
+                priority: entry.priority,
+                domain: Arc::clone(&entry.domain),
+                host_match,
+                matched: match host_match {
+                    Some(false) => Ok(false),
+                    _ => entry.domain.match_domain(url.to_string()),
+                },
+            })
+            .collect()
     }
 
     fn list_registered_domains(&self) -> Vec<String> {
-        self.registry.keys().cloned().collect()
+        self.order
+            .iter()
+            .map(|&position| self.registry[position].key.clone())
+            .collect()
     }
 }
 
+/// Summary of a single `Engine::run_scrap_workflow` run.
+///
+/// Items are the individual units the matcher patterns resolved to (e.g. every `<img>`
+/// found by a `Resource` pattern, the extracted text of a `Content` pattern or a
+/// `ScrapedContent` pass-through).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScrapReport {
+    /// number of pages fetched (including the ones reached through pagination)
+    pub pages_visited: usize,
+    /// number of items the patterns resolved to
+    pub items_found: usize,
+    /// number of items successfully fetched / extracted and handed to the storage
+    pub items_downloaded: usize,
+    /// number of items that could not be fetched or extracted
+    pub items_failed: usize,
+    /// total size (in bytes) of the items handed to the storage
+    pub bytes_downloaded: u64,
+    /// per page breakdown, in the order the pages were visited
+    pub pages: Vec<PageReport>,
+    /// why the pagination stopped; `None` if no page was fetched
+    pub stop_reason: Option<CrawlStop>,
+}
+
+/// Summary of a single page visited by `Engine::run_scrap_workflow`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageReport {
+    pub url: String,
+    /// number of items the patterns resolved to on this page
+    pub items_found: usize,
+    /// number of items of this page handed to the storage
+    pub items_downloaded: usize,
+    /// number of items of this page that could not be fetched or extracted
+    pub items_failed: usize,
+}
+
+/// Reason for the pagination to stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrawlStop {
+    /// the last page had no "next" link (or no `Pagination` pattern is defined)
+    LastPage,
+    /// the "next" link pointed to a page already visited
+    RepeatedUrl,
+    /// the `crawl.max_pages` limit was reached
+    MaxPages,
+}
+
 // flow on resolving a domain from a url
 
 // URL
@@ -65,3 +237,295 @@ impl Registry for RegistryMapImplementation {
 //         println!("Matched domain: {}", domain.get_domain_key());
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::declarative_site::DeclarativeSite;
+    use mangater_sdk::entity::{PatternMatchResult, Registerable, SiteDefinition};
+    use mangater_sdk::traits::Matcher;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// matches the urls starting with its prefix; fails on the ones containing "broken-{key}"
+    #[derive(Clone)]
+    struct PrefixDomain {
+        key: String,
+        prefix: String,
+    }
+
+    fn prefix_domain(key: &str, prefix: &str) -> Arc<dyn Domain> {
+        Arc::new(PrefixDomain {
+            key: key.to_string(),
+            prefix: prefix.to_string(),
+        })
+    }
+
+    impl Domain for PrefixDomain {
+        fn match_domain(&self, domain: String) -> Result<bool, SdkError> {
+            if domain.contains(&format!("broken-{}", self.key)) {
+                return Err(SdkError::InvalidConfig(format!("{} is broken", self.key)));
+            }
+            Ok(domain.starts_with(&self.prefix))
+        }
+
+        fn get_domain_key(&self) -> String {
+            self.key.clone()
+        }
+
+        fn get_domain_registerable(&self) -> Registerable {
+            Registerable {
+                configurator: None,
+                matcher: Arc::new(self.clone()),
+                storage: None,
+            }
+        }
+    }
+
+    impl Matcher for PrefixDomain {
+        fn match_patterns(&self) -> Vec<PatternMatchResult> {
+            Vec::new()
+        }
+    }
+
+    /// a domain declaring host patterns; counts the calls to `match_domain`
+    #[derive(Clone)]
+    struct HostDomain {
+        key: String,
+        hosts: Vec<String>,
+        prefix: String,
+        calls: Arc<AtomicUsize>,
+    }
+
+    fn host_domain(key: &str, hosts: &[&str], prefix: &str) -> HostDomain {
+        HostDomain {
+            key: key.to_string(),
+            hosts: hosts.iter().map(|host| host.to_string()).collect(),
+            prefix: prefix.to_string(),
+            calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    impl Domain for HostDomain {
+        fn match_domain(&self, domain: String) -> Result<bool, SdkError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(domain.starts_with(&self.prefix))
+        }
+
+        fn host_patterns(&self) -> Vec<String> {
+            self.hosts.clone()
+        }
+
+        fn get_domain_key(&self) -> String {
+            self.key.clone()
+        }
+
+        fn get_domain_registerable(&self) -> Registerable {
+            Registerable {
+                configurator: None,
+                matcher: Arc::new(PrefixDomain {
+                    key: self.key.clone(),
+                    prefix: self.prefix.clone(),
+                }),
+                storage: None,
+            }
+        }
+    }
+
+    fn resolved_key(registry: &RegistryMapImplementation, url: &str) -> Option<String> {
+        registry
+            .resolve_domain(url)
+            .unwrap()
+            .map(|domain| domain.get_domain_key())
+    }
+
+    #[test]
+    fn test_registry_resolution_order() {
+        let mut registry = RegistryMapImplementation::new();
+        registry.add_to_registry(None, prefix_domain("generic", "https://"));
+        registry.add_to_registry(None, prefix_domain("wiki", "https://en.wikipedia.org"));
+        registry.add_to_registry_with_priority(
+            None,
+            prefix_domain("manga", "https://manga.example.com"),
+            10,
+        );
+
+        // same priority: the earliest registered one wins
+        assert_eq!(
+            resolved_key(&registry, "https://en.wikipedia.org/wiki/Manga").as_deref(),
+            Some("generic")
+        );
+        assert_eq!(
+            resolved_key(&registry, "https://manga.example.com/1").as_deref(),
+            Some("manga")
+        );
+        assert_eq!(resolved_key(&registry, "http://example.com"), None);
+        assert_eq!(
+            registry.list_registered_domains(),
+            vec!["manga", "generic", "wiki"]
+        );
+
+        // re-registering keeps the position, with the new priority
+        registry.add_to_registry_with_priority(
+            None,
+            prefix_domain("wiki", "https://en.wikipedia.org"),
+            5,
+        );
+        assert_eq!(
+            resolved_key(&registry, "https://en.wikipedia.org/wiki/Manga").as_deref(),
+            Some("wiki")
+        );
+        let candidates = registry.resolve_candidates("https://en.wikipedia.org/wiki/Manga");
+        let matched: Vec<(&str, i32, bool)> = candidates
+            .iter()
+            .map(|c| (c.key.as_str(), c.priority, c.is_match()))
+            .collect();
+        assert_eq!(
+            matched,
+            vec![
+                ("manga", 10, false),
+                ("wiki", 5, true),
+                ("generic", 0, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_registry_match_errors() {
+        let mut registry = RegistryMapImplementation::new();
+        registry.add_to_registry_with_priority(None, prefix_domain("high", "https://high"), 1);
+        registry.add_to_registry(None, prefix_domain("low", "https://low"));
+
+        // a failing domain is skipped if another one matches
+        let mut fallback = RegistryMapImplementation::new();
+        fallback.add_to_registry_with_priority(None, prefix_domain("high", "https://high"), 1);
+        fallback.add_to_registry(None, prefix_domain("any", "https://"));
+        assert_eq!(
+            resolved_key(&fallback, "https://broken-high.example.com").as_deref(),
+            Some("any")
+        );
+
+        // ... and surfaced otherwise
+        match registry.resolve_domain("https://broken-high.example.com") {
+            Err(SdkError::InvalidConfig(msg)) => assert_eq!(msg, "high is broken"),
+            other => panic!(
+                "Expected SdkError::InvalidConfig, got {:?}",
+                other.map(|d| d.map(|d| d.get_domain_key()))
+            ),
+        }
+        let candidates = registry.resolve_candidates("https://broken-high.example.com");
+        assert!(candidates[0].matched.is_err());
+        assert!(matches!(candidates[1].matched, Ok(false)));
+    }
+
+    #[test]
+    fn test_registry_host_index() {
+        let wiki = host_domain("wiki", &["wikipedia.org", "*.wikipedia.org"], "https://");
+        let wiki_en = host_domain(
+            "wiki-en",
+            &["en.wikipedia.org"],
+            "https://en.wikipedia.org/wiki/",
+        );
+        let manga = host_domain("manga", &["*.manga.example.com"], "https://");
+        let mut registry = RegistryMapImplementation::new();
+        registry.add_to_registry(None, Arc::new(wiki.clone()));
+        registry.add_to_registry(None, Arc::new(manga.clone()));
+
+        // the host patterns skip the other domains, match_domain confirms
+        assert_eq!(
+            resolved_key(&registry, "https://JP.Wikipedia.org/wiki/Manga").as_deref(),
+            Some("wiki")
+        );
+        assert_eq!(
+            resolved_key(&registry, "https://cdn.manga.example.com/1").as_deref(),
+            Some("manga")
+        );
+        assert_eq!(wiki.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(manga.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(resolved_key(&registry, "https://manga.example.com/1"), None);
+        assert_eq!(resolved_key(&registry, "not a url"), None);
+        assert_eq!(wiki.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(manga.calls.load(Ordering::SeqCst), 1);
+
+        // the only domain matching the host still has to match the url
+        assert_eq!(
+            resolved_key(&registry, "http://en.wikipedia.org/wiki/Manga"),
+            None
+        );
+        assert_eq!(wiki.calls.load(Ordering::SeqCst), 2);
+        let candidates = registry.resolve_candidates("http://en.wikipedia.org/wiki/Manga");
+        let matched: Vec<(&str, Option<bool>, bool)> = candidates
+            .iter()
+            .map(|c| (c.key.as_str(), c.host_match, c.is_match()))
+            .collect();
+        assert_eq!(
+            matched,
+            vec![("wiki", Some(true), false), ("manga", Some(false), false)]
+        );
+
+        // several candidates: the priority order breaks the tie among the matching ones
+        registry.add_to_registry_with_priority(None, Arc::new(wiki_en.clone()), 1);
+        assert_eq!(
+            resolved_key(&registry, "https://en.wikipedia.org/wiki/Manga").as_deref(),
+            Some("wiki-en")
+        );
+        assert_eq!(
+            resolved_key(&registry, "https://en.wikipedia.org/w/index.php").as_deref(),
+            Some("wiki")
+        );
+        assert_eq!(
+            resolved_key(&registry, "https://jp.wikipedia.org/wiki/Manga").as_deref(),
+            Some("wiki")
+        );
+        assert_eq!(wiki_en.calls.load(Ordering::SeqCst), 2);
+
+        // domains without patterns are candidates for every url
+        registry.add_to_registry_with_priority(None, prefix_domain("generic", "https://jp."), 2);
+        assert_eq!(
+            resolved_key(&registry, "https://jp.wikipedia.org/wiki/Manga").as_deref(),
+            Some("generic")
+        );
+        assert_eq!(manga.calls.load(Ordering::SeqCst), 1);
+
+        let candidates = registry.resolve_candidates("https://jp.wikipedia.org/wiki/Manga");
+        let matched: Vec<(&str, Option<bool>, bool)> = candidates
+            .iter()
+            .map(|c| (c.key.as_str(), c.host_match, c.is_match()))
+            .collect();
+        assert_eq!(
+            matched,
+            vec![
+                ("generic", None, true),
+                ("wiki-en", Some(false), false),
+                ("wiki", Some(true), true),
+                ("manga", Some(false), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_registry_declarative_site() {
+        let site = DeclarativeSite::new(SiteDefinition {
+            domain_key: "mangadex".to_string(),
+            url_regex: "^https://mangadex\\.org/chapter/".to_string(),
+            host_patterns: vec!["mangadex.org".to_string()],
+            patterns: Vec::new(),
+        })
+        .unwrap();
+        let mut registry = RegistryMapImplementation::new();
+        registry.add_to_registry(None, Arc::new(site));
+
+        assert_eq!(
+            resolved_key(&registry, "https://mangadex.org/chapter/42").as_deref(),
+            Some("mangadex")
+        );
+        // the host matches, the url regex does not
+        assert_eq!(
+            resolved_key(&registry, "https://mangadex.org/title/42"),
+            None
+        );
+        let candidates = registry.resolve_candidates("https://mangadex.org/title/42");
+        assert_eq!(candidates[0].host_match, Some(true));
+        assert!(!candidates[0].is_match());
+    }
+}
@@ -1 +1,7 @@
 pub mod config;
+pub mod declarative_site;
+pub mod plugin_loader;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "wasm")]
+pub mod wasm_host;
@@ -167,7 +167,7 @@ mod tests {
         assert!(config_content.contains("\"message\": \"happy plugin dev\""));
         assert!(config_content.contains("\"ip\": \"192.168.1.100\""));
         // non existing content
-        assert_eq!(config_content.contains("\"production-test\": {"), false);
+        assert!(!config_content.contains("\"production-test\": {"));
 
         // [note] the fn would extract raw content as-is;
         // and further transformation into a struct is done by serde-json
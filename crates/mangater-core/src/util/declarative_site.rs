@@ -0,0 +1,247 @@
+use mangater_sdk::entity::{PatternMatchResult, Registerable, SiteDefinition};
+use mangater_sdk::traits::{Domain, Matcher};
+use mangater_sdk::SdkError;
+
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A `Domain` (and `Matcher`) built from a [`SiteDefinition`]: the urls matching its regex are
+/// supported, its patterns are handed over to the engine as-is.
+#[derive(Debug, Clone)]
+pub struct DeclarativeSite {
+    definition: SiteDefinition,
+    url_regex: Regex,
+}
+
+impl DeclarativeSite {
+    /// # Errors
+    ///
+    /// Returns `SdkError::InvalidConfig` if the domain key is missing or the url regex is invalid.
+    pub fn new(definition: SiteDefinition) -> Result<Self, SdkError> {
+        if definition.domain_key.trim().is_empty() {
+            return Err(SdkError::InvalidConfig(
+                "site definition - missing domain_key".to_string(),
+            ));
+        }
+        let url_regex = Regex::new(&definition.url_regex).map_err(|e| {
+            SdkError::InvalidConfig(format!("{} - url_regex - {}", definition.domain_key, e))
+        })?;
+        Ok(Self {
+            definition,
+            url_regex,
+        })
+    }
+
+    pub fn definition(&self) -> &SiteDefinition {
+        &self.definition
+    }
+}
+
+impl Domain for DeclarativeSite {
+    fn match_domain(&self, domain: String) -> Result<bool, SdkError> {
+        Ok(self.url_regex.is_match(&domain))
+    }
+
+    fn host_patterns(&self) -> Vec<String> {
+        self.definition.host_patterns.clone()
+    }
+
+    fn get_domain_key(&self) -> String {
+        self.definition.domain_key.clone()
+    }
+
+    fn get_domain_registerable(&self) -> Registerable {
+        Registerable {
+            configurator: None,
+            matcher: Arc::new(self.clone()),
+            storage: None,
+        }
+    }
+}
+
+impl Matcher for DeclarativeSite {
+    fn match_patterns(&self) -> Vec<PatternMatchResult> {
+        self.definition
+            .patterns
+            .iter()
+            .map(|pattern| PatternMatchResult {
+                pattern: pattern.pattern.clone(),
+                pattern_type: pattern.pattern_type.clone(),
+                resource_string: None,
+            })
+            .collect()
+    }
+}
+
+/// Loads the site definition of a standalone `.json5` file.
+///
+/// # Errors
+///
+/// Returns `SdkError::InvalidConfig` if the file cannot be read or is not a valid definition.
+pub fn load_site_file(path: &Path) -> Result<DeclarativeSite, SdkError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| SdkError::InvalidConfig(format!("{} - {}", path.display(), e)))?;
+    let definition: SiteDefinition = json5::from_str(&content)
+        .map_err(|e| SdkError::InvalidConfig(format!("{} - {}", path.display(), e)))?;
+    DeclarativeSite::new(definition).map_err(|e| match e {
+        SdkError::InvalidConfig(msg) => {
+            SdkError::InvalidConfig(format!("{} - {}", path.display(), msg))
+        }
+        other => other,
+    })
+}
+
+/// Loads every site definition (`.json5` file) in the folder, in file name order.
+///
+/// # Errors
+///
+/// Returns `SdkError::InvalidConfig` if the folder cannot be read; the first file failing to
+/// load fails the lot, see [`load_site_file`].
+pub fn load_sites_dir(dir: impl AsRef<Path>) -> Result<Vec<DeclarativeSite>, SdkError> {
+    let dir = dir.as_ref();
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| SdkError::InvalidConfig(format!("{} - {}", dir.display(), e)))?;
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("json5")
+        })
+        .collect();
+    paths.sort();
+
+    paths.iter().map(|path| load_site_file(path)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::test_util;
+    use mangater_sdk::entity::{AppConfigJson5, PatternType};
+
+    #[test]
+    fn test_site_definitions_from_plugins() {
+        let config: AppConfigJson5 = json5::from_str(
+            r#"{
+                core: {},
+                plugins: {
+                    wikipedia: { need_content: true },
+                    mangadex: {
+                        url_regex: "^https://mangadex\\.org/chapter/",
+                        host_patterns: ["mangadex.org"],
+                        patterns: [
+                            { pattern: "img.page", pattern_type: "resource" },
+                            { pattern: "a.next", pattern_type: "pagination" },
+                        ],
+                        core: { priority: 2 },
+                    },
+                },
+            }"#,
+        )
+        .unwrap();
+
+        let definitions = config.site_definitions().unwrap();
+        assert_eq!(definitions.len(), 1);
+        let site = DeclarativeSite::new(definitions[0].clone()).unwrap();
+        assert_eq!(site.get_domain_key(), "mangadex");
+        assert_eq!(site.host_patterns(), vec!["mangadex.org"]);
+        assert!(site
+            .match_domain("https://mangadex.org/chapter/42".to_string())
+            .unwrap());
+        assert!(!site
+            .match_domain("https://mangadex.org/title/42".to_string())
+            .unwrap());
+
+        let patterns = site.get_domain_registerable().matcher.match_patterns();
+        let patterns: Vec<(String, PatternType)> = patterns
+            .into_iter()
+            .map(|p| (p.pattern, p.pattern_type))
+            .collect();
+        assert_eq!(
+            patterns,
+            vec![
+                ("img.page".to_string(), PatternType::Resource),
+                ("a.next".to_string(), PatternType::Pagination),
+            ]
+        );
+        // the section keeps working as the site's core overrides
+        assert_eq!(
+            config.plugin_overrides("mangadex").unwrap().priority,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_site_definitions_errors() {
+        let config: AppConfigJson5 = json5::from_str(
+            r#"{ core: {}, plugins: { mangadex: { domain_key: "other", url_regex: ".*", patterns: [] } } }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.site_definitions(),
+            Err(SdkError::InvalidConfig(msg)) if msg.starts_with("plugins.mangadex")
+        ));
+
+        let config: AppConfigJson5 = json5::from_str(
+            r#"{ core: {}, plugins: { mangadex: { url_regex: ".*", patterns: [{ pattern: "img", pattern_type: "images" }] } } }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.site_definitions(),
+            Err(SdkError::InvalidConfig(_))
+        ));
+
+        let invalid_regex = SiteDefinition {
+            domain_key: "mangadex".to_string(),
+            url_regex: "^https://(".to_string(),
+            host_patterns: Vec::new(),
+            patterns: Vec::new(),
+        };
+        match DeclarativeSite::new(invalid_regex) {
+            Err(SdkError::InvalidConfig(msg)) => {
+                assert!(msg.starts_with("mangadex - url_regex"))
+            }
+            other => panic!("Expected SdkError::InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_load_sites_dir() {
+        let dir = test_util::temp_dir("sites", "dir");
+        std::fs::write(
+            dir.join("b-comics.json5"),
+            r#"{
+                // a site file is a single definition
+                domain_key: "comics",
+                url_regex: "^https://comics\\.example\\.com/",
+                patterns: [{ pattern: ".strip img", pattern_type: "resource" }],
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("a-manga.json5"),
+            r#"{ domain_key: "manga", url_regex: "^https://manga\\.example\\.com/", patterns: [] }"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("README.md"), "not a site").unwrap();
+
+        let keys: Vec<String> = load_sites_dir(&dir)
+            .unwrap()
+            .iter()
+            .map(|site| site.get_domain_key())
+            .collect();
+        assert_eq!(keys, vec!["manga", "comics"]);
+
+        std::fs::write(dir.join("c-broken.json5"), "{ domain_key: \"broken\" }").unwrap();
+        match load_sites_dir(&dir) {
+            Err(SdkError::InvalidConfig(msg)) => assert!(msg.contains("c-broken.json5")),
+            other => panic!(
+                "Expected SdkError::InvalidConfig, got {:?}",
+                other.map(|sites| sites.len())
+            ),
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
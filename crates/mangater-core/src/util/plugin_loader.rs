@@ -0,0 +1,139 @@
+use mangater_sdk::plugin::{
+    check_handshake, AbiVersionFn, BuildFn, CreateFn, PluginConfigValues, ABI_VERSION_SYMBOL,
+    BUILD_SYMBOL, CREATE_SYMBOL,
+};
+use mangater_sdk::traits::Domain;
+use mangater_sdk::SdkError;
+
+use libloading::Library;
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A `Domain` loaded from a dynamic plugin.
+pub struct LoadedPlugin {
+    /// the shared library the plugin was loaded from
+    pub path: PathBuf,
+    pub domain: Arc<dyn Domain>,
+}
+
+/// Loads the dynamic plugin (shared library) at the given path; see [`mangater_sdk::plugin`].
+///
+/// The ABI version and build of the plugin are checked before its `Domain` is created out of
+/// the `plugins` section of the config. A loaded library is never unloaded: the `Domain`, and
+/// everything it hands over (e.g. its `Matcher`), runs code living in it.
+///
+/// # Errors
+///
+/// Returns `SdkError::Plugin` if the library cannot be loaded, is not a plugin or does not
+/// match the application's ABI; the plugin's own error if it fails to create its `Domain`.
+pub fn load_plugin(path: &Path, plugins: &PluginConfigValues) -> Result<LoadedPlugin, SdkError> {
+    let name = path.display().to_string();
+    // SAFETY: loading a library runs its initializers; plugins are trusted as much as the
+    // application itself (same as a compiled-in site crate).
+    let library = unsafe { Library::new(path) }
+        .map_err(|e| SdkError::Plugin(format!("{} - failed to load - {}", name, e)))?;
+
+    // SAFETY: the handshake symbols are plain C functions without arguments, whatever the
+    // plugin's build; the Rust ABI `create` is only looked up once the handshake passed.
+    let domain = unsafe {
+        let abi_version = library
+            .get::<AbiVersionFn>(ABI_VERSION_SYMBOL)
+            .map_err(|e| {
+                SdkError::Plugin(format!(
+                    "{} - not a mangater plugin (see mangater_sdk::export_plugin!) - {}",
+                    name, e
+                ))
+            })?;
+        let build = library
+            .get::<BuildFn>(BUILD_SYMBOL)
+            .map_err(|e| SdkError::Plugin(format!("{} - missing build info - {}", name, e)))?;
+        check_handshake(&name, abi_version(), CStr::from_ptr(build()))?;
+
+        let create = library
+            .get::<CreateFn>(CREATE_SYMBOL)
+            .map_err(|e| SdkError::Plugin(format!("{} - missing entry point - {}", name, e)))?;
+        create(plugins)?
+    };
+    std::mem::forget(library);
+
+    tracing::info!(
+        "loaded plugin {} from {}",
+        domain.get_domain_key(),
+        path.display()
+    );
+    Ok(LoadedPlugin {
+        path: path.to_path_buf(),
+        domain,
+    })
+}
+
+/// Loads every dynamic plugin in the folder (files with the platform's shared library
+/// extension, e.g. `.so`), in file name order.
+///
+/// # Errors
+///
+/// Returns `SdkError::Plugin` if the folder cannot be read; the first plugin failing to load
+/// fails the lot, see [`load_plugin`].
+pub fn load_plugins_dir(
+    dir: impl AsRef<Path>,
+    plugins: &PluginConfigValues,
+) -> Result<Vec<LoadedPlugin>, SdkError> {
+    let dir = dir.as_ref();
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| SdkError::Plugin(format!("{} - {}", dir.display(), e)))?;
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path.extension().and_then(|ext| ext.to_str())
+                    == Some(std::env::consts::DLL_EXTENSION)
+        })
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| load_plugin(path, plugins))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::test_util;
+
+    #[test]
+    fn test_load_plugins_dir_errors() {
+        let plugins = PluginConfigValues::new();
+        let dir = test_util::temp_dir("plugins", "errors");
+
+        // no shared library: nothing loaded, other files are ignored
+        std::fs::write(dir.join("README.md"), "not a plugin").unwrap();
+        assert!(load_plugins_dir(&dir, &plugins).unwrap().is_empty());
+
+        let broken = dir.join(format!("broken.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&broken, "not a library").unwrap();
+        match load_plugins_dir(&dir, &plugins) {
+            Err(SdkError::Plugin(msg)) => {
+                assert!(msg.starts_with(&broken.display().to_string()));
+                assert!(msg.contains("failed to load"));
+            }
+            other => panic!(
+                "Expected SdkError::Plugin, got {:?}",
+                other.map(|loaded| loaded.len())
+            ),
+        }
+
+        match load_plugins_dir(dir.join("missing"), &plugins) {
+            Err(SdkError::Plugin(_)) => {}
+            other => panic!(
+                "Expected SdkError::Plugin, got {:?}",
+                other.map(|loaded| loaded.len())
+            ),
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,21 @@
+//! Helpers shared by the tests of the `util` modules.
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// a fresh folder under the system's temp dir, e.g. `mangater-sites-dir-<pid>-<nanos>`
+pub(crate) fn temp_dir(kind: &str, name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!(
+        "mangater-{}-{}-{}-{}",
+        kind,
+        name,
+        std::process::id(),
+        nanos
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
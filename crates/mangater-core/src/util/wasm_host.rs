@@ -0,0 +1,678 @@
+use crate::orchestration::host_index::HostIndex;
+
+use mangater_sdk::entity::{PatternMatchResult, PatternType, Registerable, WasmConfig};
+use mangater_sdk::plugin::PluginConfigValues;
+use mangater_sdk::traits::{Config, Domain, Matcher};
+use mangater_sdk::util::html_parsing::{parse_plain_text, parse_resource_links};
+use mangater_sdk::util::http::HttpFetcher;
+use mangater_sdk::SdkError;
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use url::Url;
+use wasmtime::component::{Component, HasSelf, Linker};
+use wasmtime::{Store, StoreLimits, StoreLimitsBuilder};
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "site-plugin",
+    });
+}
+
+use bindings::mangater::plugin::host;
+use bindings::{SitePlugin, SitePluginPre};
+
+/// Loads the WebAssembly site plugins: components of the `site-plugin` world of
+/// `wit/site-plugin.wit` (e.g. built for wasm32-wasip2 with `cargo component build`).
+///
+/// The plugins run sandboxed. The `mangater:plugin/host` functions are all they can reach:
+/// any other import (e.g. WASI, pulled in by the Rust standard library) is linked to a stub
+/// which traps when called. Each call into a plugin runs on a budget of fuel and its memory is
+/// capped, see [`WasmConfig`]. The host `fetch` only reaches the http(s) urls of the hosts the
+/// operator allowed the plugin in `WasmConfig::allowed_hosts`; the plugin's own host patterns
+/// grant nothing, an untrusted plugin does not get to pick what it may reach.
+pub struct WasmHost {
+    engine: wasmtime::Engine,
+    config: WasmConfig,
+}
+
+impl WasmHost {
+    pub fn new(config: &WasmConfig) -> Result<Self, SdkError> {
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(true);
+        let engine = wasmtime::Engine::new(&engine_config)
+            .map_err(|e| SdkError::Plugin(format!("failed to set up the wasm engine - {}", e)))?;
+
+        Ok(Self {
+            engine,
+            config: config.clone(),
+        })
+    }
+
+    /// Loads the plugin (`.wasm` component, or its text format) at the given path and loads its
+    /// section of the config (`plugins.<domain-key>`).
+    ///
+    /// `fetcher_for` returns the HTTP client of the plugin's domain key: the host `fetch`
+    /// goes through it, hence through the rate limits (and proxy, cache) of the plugin.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SdkError::Plugin` if the file is not a component of the `site-plugin` world or
+    /// the plugin traps; the plugin's own error (`SdkError::InvalidConfig`) if it rejects its
+    /// config.
+    pub fn load(
+        &self,
+        path: &Path,
+        plugins: &PluginConfigValues,
+        fetcher_for: &dyn Fn(&str) -> HttpFetcher,
+    ) -> Result<WasmPlugin, SdkError> {
+        let name = path.display().to_string();
+        let component = Component::from_file(&self.engine, path)
+            .map_err(|e| SdkError::Plugin(format!("{} - failed to load - {:#}", name, e)))?;
+
+        // the host functions shadow the trapping stubs of their own interface
+        let mut linker = Linker::new(&self.engine);
+        linker.allow_shadowing(true);
+        linker
+            .define_unknown_imports_as_traps(&component)
+            .and_then(|()| SitePlugin::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state))
+            .map_err(|e| SdkError::Plugin(format!("{} - failed to link - {:#}", name, e)))?;
+        let pre = linker
+            .instantiate_pre(&component)
+            .and_then(SitePluginPre::new)
+            .map_err(|e| {
+                SdkError::Plugin(format!("{} - not a mangater site plugin - {:#}", name, e))
+            })?;
+
+        let limits = Limits {
+            fuel: self.config.fuel,
+            max_memory: usize::try_from(self.config.max_memory_mb.saturating_mul(1024 * 1024))
+                .unwrap_or(usize::MAX),
+        };
+        let mut instance = PluginInstance::new(&self.engine, &pre, &limits, None)
+            .map_err(|e| SdkError::Plugin(format!("{} - failed to instantiate - {:#}", name, e)))?;
+        let (key, host_patterns) = instance
+            .describe(&limits)
+            .map_err(|e| SdkError::Plugin(format!("{} - {:#}", name, e)))?;
+        let fetcher = fetcher_for(&key);
+        let fetch_hosts = Arc::new(self.fetch_hosts(&key));
+        instance
+            .store
+            .data_mut()
+            .set_plugin(&key, &fetcher, &fetch_hosts);
+
+        let mut plugin = WasmPlugin {
+            inner: Arc::new(WasmPluginInner {
+                path: path.to_path_buf(),
+                key,
+                host_patterns,
+                engine: self.engine.clone(),
+                pre,
+                limits,
+                fetcher,
+                fetch_hosts,
+                instance: Mutex::new(instance),
+            }),
+        };
+        plugin.load(plugins.clone())?;
+
+        tracing::info!(
+            "loaded wasm plugin {} from {}",
+            plugin.inner.key,
+            path.display()
+        );
+        Ok(plugin)
+    }
+
+    /// the hosts the plugin may fetch from, as allowed by the operator
+    fn fetch_hosts(&self, key: &str) -> HostIndex {
+        let mut hosts = HostIndex::default();
+        for pattern in self.config.allowed_hosts.get(key).into_iter().flatten() {
+            if !hosts.insert(pattern, 0) {
+                tracing::warn!(
+                    "core.wasm.allowed_hosts.{} - ignoring invalid host pattern {:?}",
+                    key,
+                    pattern
+                );
+            }
+        }
+        hosts
+    }
+
+    /// Loads every plugin (`.wasm` file) in the folder, in file name order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SdkError::Plugin` if the folder cannot be read; the first plugin failing to load
+    /// fails the lot, see [`WasmHost::load`].
+    pub fn load_dir(
+        &self,
+        dir: impl AsRef<Path>,
+        plugins: &PluginConfigValues,
+        fetcher_for: &dyn Fn(&str) -> HttpFetcher,
+    ) -> Result<Vec<WasmPlugin>, SdkError> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| SdkError::Plugin(format!("{} - {}", dir.display(), e)))?;
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("wasm")
+            })
+            .collect();
+        paths.sort();
+
+        paths
+            .iter()
+            .map(|path| self.load(path, plugins, fetcher_for))
+            .collect()
+    }
+}
+
+/// A site plugin loaded by the [`WasmHost`]; implements `Domain`, `Matcher` and `Config` by
+/// calling into the plugin. Clones share the plugin instance, calls are serialized.
+///
+/// A call which traps (e.g. out of fuel) fails with `SdkError::Plugin`; the instance is then
+/// replaced by a fresh one, loaded with the last config.
+#[derive(Clone)]
+pub struct WasmPlugin {
+    inner: Arc<WasmPluginInner>,
+}
+
+struct WasmPluginInner {
+    path: PathBuf,
+    /// `domain-key` and `host-patterns` never change, they are asked once
+    key: String,
+    host_patterns: Vec<String>,
+    engine: wasmtime::Engine,
+    pre: SitePluginPre<PluginState>,
+    limits: Limits,
+    fetcher: HttpFetcher,
+    /// hosts the plugin may fetch from
+    fetch_hosts: Arc<HostIndex>,
+    instance: Mutex<PluginInstance>,
+}
+
+struct Limits {
+    fuel: u64,
+    max_memory: usize,
+}
+
+struct PluginInstance {
+    store: Store<PluginState>,
+    bindings: SitePlugin,
+    /// the config last loaded, as handed over to `load-config`
+    config: Option<String>,
+}
+
+impl PluginInstance {
+    fn new(
+        engine: &wasmtime::Engine,
+        pre: &SitePluginPre<PluginState>,
+        limits: &Limits,
+        config: Option<String>,
+    ) -> wasmtime::Result<Self> {
+        let state = PluginState {
+            domain_key: String::new(),
+            fetcher: None,
+            fetch_hosts: Arc::new(HostIndex::default()),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory)
+                .build(),
+        };
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(limits.fuel)?;
+        let bindings = pre.instantiate(&mut store)?;
+        Ok(Self {
+            store,
+            bindings,
+            config,
+        })
+    }
+
+    /// the plugin's domain key and host patterns
+    fn describe(&mut self, limits: &Limits) -> wasmtime::Result<(String, Vec<String>)> {
+        self.store.set_fuel(limits.fuel)?;
+        let key = self.bindings.call_domain_key(&mut self.store)?;
+        self.store.set_fuel(limits.fuel)?;
+        let host_patterns = self.bindings.call_host_patterns(&mut self.store)?;
+        Ok((key, host_patterns))
+    }
+}
+
+impl WasmPlugin {
+    /// the component the plugin was loaded from
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    /// Calls into the plugin with a full tank of fuel.
+    fn call<R>(
+        &self,
+        f: impl FnOnce(&SitePlugin, &mut Store<PluginState>) -> wasmtime::Result<R>,
+    ) -> Result<R, SdkError> {
+        let inner = &self.inner;
+        let mut instance = inner.instance.lock().unwrap();
+        let PluginInstance {
+            store, bindings, ..
+        } = &mut *instance;
+        let trapped = match store.set_fuel(inner.limits.fuel) {
+            Ok(()) => match f(bindings, store) {
+                Ok(result) => return Ok(result),
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+
+        // a trapped instance cannot be entered again: start over from a fresh one
+        let config = instance.config.take();
+        match inner.reinstantiate(config) {
+            Ok(fresh) => *instance = fresh,
+            Err(e) => tracing::warn!("wasm plugin {} - failed to restart - {:#}", inner.key, e),
+        }
+        Err(SdkError::Plugin(format!("{} - {:#}", inner.key, trapped)))
+    }
+}
+
+impl WasmPluginInner {
+    fn reinstantiate(&self, config: Option<String>) -> wasmtime::Result<PluginInstance> {
+        let mut instance = PluginInstance::new(&self.engine, &self.pre, &self.limits, config)?;
+        instance
+            .store
+            .data_mut()
+            .set_plugin(&self.key, &self.fetcher, &self.fetch_hosts);
+        if let Some(config) = instance.config.clone() {
+            instance.store.set_fuel(self.limits.fuel)?;
+            if let Err(e) = instance
+                .bindings
+                .call_load_config(&mut instance.store, Some(&config))?
+            {
+                return Err(wasmtime::Error::msg(format!(
+                    "the last config is rejected - {}",
+                    e
+                )));
+            }
+        }
+        Ok(instance)
+    }
+}
+
+impl Domain for WasmPlugin {
+    fn match_domain(&self, domain: String) -> Result<bool, SdkError> {
+        self.call(|bindings, store| bindings.call_match_domain(store, &domain))?
+            .map_err(|e| SdkError::Site(format!("{} - {}", self.inner.key, e)))
+    }
+
+    fn host_patterns(&self) -> Vec<String> {
+        self.inner.host_patterns.clone()
+    }
+
+    fn get_domain_key(&self) -> String {
+        self.inner.key.clone()
+    }
+
+    fn get_domain_registerable(&self) -> Registerable {
+        Registerable {
+            configurator: None,
+            matcher: Arc::new(self.clone()),
+            storage: None,
+        }
+    }
+}
+
+impl Matcher for WasmPlugin {
+    /// the plugin's patterns; none if the call fails (logged)
+    fn match_patterns(&self) -> Vec<PatternMatchResult> {
+        match self.call(|bindings, store| bindings.call_match_patterns(store)) {
+            Ok(results) => results
+                .into_iter()
+                .map(|result| PatternMatchResult {
+                    pattern: result.pattern,
+                    pattern_type: match result.pattern_type {
+                        bindings::PatternType::Resource => PatternType::Resource,
+                        bindings::PatternType::Pagination => PatternType::Pagination,
+                        bindings::PatternType::Content => PatternType::Content,
+                        bindings::PatternType::ScrapedContent => PatternType::ScrapedContent,
+                        bindings::PatternType::Others => PatternType::Others,
+                    },
+                    resource_string: result.resource_string,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!("{}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+impl Config for WasmPlugin {
+    /// Hands the plugin's section of the config (`plugins.<domain-key>`) over as JSON.
+    fn load(&mut self, raw_config_values: HashMap<String, Value>) -> Result<(), SdkError> {
+        let config = raw_config_values.get(&self.inner.key).map(Value::to_string);
+        self.call(|bindings, store| bindings.call_load_config(store, config.as_deref()))?
+            .map_err(|e| SdkError::InvalidConfig(format!("plugins.{} - {}", self.inner.key, e)))?;
+        self.inner.instance.lock().unwrap().config = config;
+        Ok(())
+    }
+}
+
+/// Host side of a plugin instance.
+struct PluginState {
+    domain_key: String,
+    /// the HTTP client of the plugin's domain key; none until the key is known
+    fetcher: Option<HttpFetcher>,
+    /// hosts `fetch` may reach; none until the key is known
+    fetch_hosts: Arc<HostIndex>,
+    limits: StoreLimits,
+}
+
+impl PluginState {
+    fn set_plugin(
+        &mut self,
+        domain_key: &str,
+        fetcher: &HttpFetcher,
+        fetch_hosts: &Arc<HostIndex>,
+    ) {
+        self.domain_key = domain_key.to_string();
+        self.fetcher = Some(fetcher.clone());
+        self.fetch_hosts = Arc::clone(fetch_hosts);
+    }
+
+    /// only the http(s) urls of the plugin's hosts may be fetched
+    fn may_fetch(&self, url: &str) -> bool {
+        Url::parse(url).is_ok_and(|url| {
+            matches!(url.scheme(), "http" | "https")
+                && url
+                    .host_str()
+                    .is_some_and(|host| !self.fetch_hosts.lookup(host).is_empty())
+        })
+    }
+}
+
+impl host::Host for PluginState {
+    fn fetch(&mut self, url: String) -> Result<Vec<u8>, String> {
+        let Some(fetcher) = self.fetcher.clone() else {
+            return Err("fetch is not available while the plugin is loading".to_string());
+        };
+        if !self.may_fetch(&url) {
+            tracing::warn!("wasm plugin {} - refused to fetch {}", self.domain_key, url);
+            return Err(format!(
+                "{} - not allowed, only the http(s) urls of core.wasm.allowed_hosts.{} may be \
+                 fetched",
+                url, self.domain_key
+            ));
+        }
+        tracing::debug!("wasm plugin {} - fetching {}", self.domain_key, url);
+        block_on(async move { fetcher.fetch_bytes(&url, None).await }).map_err(|e| e.to_string())
+    }
+
+    fn parse_links(&mut self, html: String, selector: String) -> Result<Vec<String>, String> {
+        parse_resource_links(&html, &selector).map_err(|e| e.to_string())
+    }
+
+    fn parse_text(&mut self, html: String, selector: String) -> Result<String, String> {
+        parse_plain_text(&html, &selector, &[]).map_err(|e| e.to_string())
+    }
+
+    fn log(&mut self, message: String) {
+        tracing::info!("wasm plugin {} - {}", self.domain_key, message);
+    }
+}
+
+/// Runs the future to completion from the synchronous host functions, which are called from
+/// within the engine's runtime (e.g. `Matcher::match_patterns` during a scrap) or outside of any.
+fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    if let Ok(handle) = Handle::try_current() {
+        if handle.runtime_flavor() == RuntimeFlavor::MultiThread {
+            return tokio::task::block_in_place(|| handle.block_on(future));
+        }
+    }
+    // no runtime, or a single-threaded one which cannot be blocked: on a runtime of its own
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("failed to build a runtime for the wasm host")
+                    .block_on(future)
+            })
+            .join()
+            .expect("the wasm host runtime panicked")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mangater_sdk::entity::{HttpConfig, RateLimitConfig};
+    use mangater_sdk::util::rate_limit::RateLimiter;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// hand-written component (text format), see the file for what it does
+    fn echo_plugin() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testing/wasm/echo-plugin.wat")
+    }
+
+    fn unlimited_fetcher() -> HttpFetcher {
+        HttpFetcher::new(&HttpConfig::default(), None).unwrap()
+    }
+
+    #[test]
+    fn test_wasm_plugin_contracts() {
+        let host = WasmHost::new(&WasmConfig {
+            fuel: 1_000_000,
+            ..WasmConfig::default()
+        })
+        .unwrap();
+        let plugin = host
+            .load(&echo_plugin(), &PluginConfigValues::new(), &|_| {
+                unlimited_fetcher()
+            })
+            .unwrap();
+
+        assert_eq!(plugin.get_domain_key(), "wasm-echo");
+        assert_eq!(
+            plugin.host_patterns(),
+            vec!["echo.example.com", "*.echo.example.com"]
+        );
+        assert!(plugin
+            .match_domain("https://echo.example.com/chapter/1".to_string())
+            .unwrap());
+        assert!(!plugin
+            .match_domain("https://en.wikipedia.org/wiki/Manga".to_string())
+            .unwrap());
+        match plugin.match_domain(String::new()) {
+            Err(SdkError::Site(msg)) => assert_eq!(msg, "wasm-echo - empty url"),
+            other => panic!("Expected SdkError::Site, got {:?}", other),
+        }
+        // no url configured: no fetch, no patterns
+        assert!(plugin.match_patterns().is_empty());
+
+        // looping forever: stopped once out of fuel, the plugin keeps working afterwards
+        match plugin.match_domain("!loop".to_string()) {
+            Err(SdkError::Plugin(msg)) => {
+                assert!(msg.starts_with("wasm-echo - "));
+                assert!(msg.contains("fuel"), "{}", msg);
+            }
+            other => panic!("Expected SdkError::Plugin, got {:?}", other),
+        }
+        assert!(plugin
+            .match_domain("https://echo.example.com/".to_string())
+            .unwrap());
+
+        // not a component
+        match host.load(Path::new("Cargo.toml"), &PluginConfigValues::new(), &|_| {
+            unlimited_fetcher()
+        }) {
+            Err(SdkError::Plugin(msg)) => assert!(msg.contains("failed to load")),
+            other => panic!(
+                "Expected SdkError::Plugin, got {:?}",
+                other.map(|plugin| plugin.get_domain_key())
+            ),
+        }
+    }
+
+    #[test]
+    fn test_wasm_plugin_sandboxed() {
+        let host = WasmHost::new(&WasmConfig::default()).unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testing/wasm/clock-plugin.wat");
+        // links, but reaching for WASI traps
+        match host.load(&path, &PluginConfigValues::new(), &|_| unlimited_fetcher()) {
+            Err(SdkError::Plugin(msg)) => {
+                assert!(msg.contains("wasi:clocks/monotonic-clock@0.2.0"), "{}", msg)
+            }
+            other => panic!(
+                "Expected SdkError::Plugin, got {:?}",
+                other.map(|plugin| plugin.get_domain_key())
+            ),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wasm_plugin_fetch_rate_limited() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let served = Arc::clone(&requests);
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                served.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buffer = vec![0u8; 4096];
+                    let _ = socket.read(&mut buffer).await.unwrap();
+                    let body = "{\"title\":\"echo\"}";
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                    socket.shutdown().await.unwrap();
+                });
+            }
+        });
+
+        let rate_limit = RateLimitConfig {
+            requests_per_second: Some(10.0),
+            ..RateLimitConfig::default()
+        };
+        let fetcher =
+            unlimited_fetcher().with_rate_limiter(Arc::new(RateLimiter::new(&rate_limit)));
+        let host = WasmHost::new(&WasmConfig {
+            allowed_hosts: HashMap::from([(
+                "wasm-echo".to_string(),
+                vec!["127.0.0.1".to_string()],
+            )]),
+            ..WasmConfig::default()
+        })
+        .unwrap();
+        let plugins = PluginConfigValues::from([("wasm-echo".to_string(), Value::from(url))]);
+        let plugin = host
+            .load(&echo_plugin(), &plugins, &|key| {
+                assert_eq!(key, "wasm-echo");
+                fetcher.clone()
+            })
+            .unwrap();
+
+        let started = Instant::now();
+        for _ in 0..3 {
+            let results = plugin.match_patterns();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].pattern, "echo");
+            assert_eq!(results[0].pattern_type, PatternType::ScrapedContent);
+            assert_eq!(
+                results[0].resource_string.as_deref(),
+                Some("{\"title\":\"echo\"}")
+            );
+        }
+        // 10 requests per second with a burst of 1: the 2nd and 3rd fetches waited their turn
+        assert!(started.elapsed() >= Duration::from_millis(180));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wasm_plugin_fetch_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let served = Arc::clone(&requests);
+        tokio::spawn(async move {
+            loop {
+                let _ = listener.accept().await.unwrap();
+                served.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        // allowed hosts of another plugin do not count, nor do the plugin's own host patterns
+        let host = WasmHost::new(&WasmConfig {
+            allowed_hosts: HashMap::from([
+                ("other".to_string(), vec!["127.0.0.1".to_string()]),
+                (
+                    "wasm-echo".to_string(),
+                    vec!["echo.example.com".to_string()],
+                ),
+            ]),
+            ..WasmConfig::default()
+        })
+        .unwrap();
+        let grab_plugin =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("testing/wasm/grab-plugin.wat");
+        let cases = [
+            (
+                echo_plugin(),
+                vec![
+                    url.as_str(),
+                    "file:///etc/passwd",
+                    "ftp://echo.example.com/api",
+                    "https://cdn.echo.example.com/api",
+                ],
+            ),
+            // declares `*.com` and `127.0.0.1`
+            (grab_plugin, vec![url.as_str(), "https://example.com/"]),
+        ];
+
+        for (path, refused_urls) in cases {
+            let mut plugin = host
+                .load(&path, &PluginConfigValues::new(), &|_| unlimited_fetcher())
+                .unwrap();
+            let key = plugin.get_domain_key();
+            for refused in refused_urls {
+                plugin
+                    .load(PluginConfigValues::from([(
+                        key.clone(),
+                        Value::from(refused),
+                    )]))
+                    .unwrap();
+                let results = plugin.match_patterns();
+                assert_eq!(results.len(), 1);
+                assert_eq!(results[0].pattern, "error");
+                assert_eq!(results[0].pattern_type, PatternType::Others);
+                let error = results[0].resource_string.as_deref().unwrap();
+                assert!(
+                    error.starts_with(&format!("{} - not allowed", refused)),
+                    "{}",
+                    error
+                );
+            }
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+}
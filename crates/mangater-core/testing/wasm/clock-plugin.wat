@@ -0,0 +1,50 @@
+;; A site plugin for the wasm host tests reaching for WASI: `domain-key` reads the monotonic
+;; clock, which the sandboxed host does not provide (the call traps).
+(component
+  (import "wasi:clocks/monotonic-clock@0.2.0" (instance $clock
+    (export "now" (func (result u64)))
+  ))
+
+  (core func $now (canon lower (func $clock "now")))
+
+  (core module $Main
+    (import "wasi" "now" (func $now (result i64)))
+    (memory (export "memory") 1)
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (i32.const 1024))
+    (func (export "domain-key") (result i32)
+      (drop (call $now))
+      (i32.const 0))
+    (func (export "host-patterns") (result i32)
+      (i32.const 0))
+    (func (export "match-domain") (param i32 i32) (result i32)
+      (i32.const 0))
+    (func (export "match-patterns") (result i32)
+      (i32.const 0))
+    (func (export "load-config") (param i32 i32 i32) (result i32)
+      (i32.const 0))
+  )
+  (core instance $main (instantiate $Main
+    (with "wasi" (instance (export "now" (func $now))))
+  ))
+
+  (type $pattern-type' (enum "resource" "pagination" "content" "scraped-content" "others"))
+  (export $pattern-type "pattern-type" (type $pattern-type'))
+  (type $pattern-match-result' (record
+    (field "pattern" string)
+    (field "pattern-type" $pattern-type)
+    (field "resource-string" (option string))
+  ))
+  (export $pattern-match-result "pattern-match-result" (type $pattern-match-result'))
+
+  (func (export "domain-key") (result string)
+    (canon lift (core func $main "domain-key") (memory $main "memory") (realloc (func $main "realloc"))))
+  (func (export "host-patterns") (result (list string))
+    (canon lift (core func $main "host-patterns") (memory $main "memory") (realloc (func $main "realloc"))))
+  (func (export "match-domain") (param "url" string) (result (result bool (error string)))
+    (canon lift (core func $main "match-domain") (memory $main "memory") (realloc (func $main "realloc"))))
+  (func (export "match-patterns") (result (list $pattern-match-result))
+    (canon lift (core func $main "match-patterns") (memory $main "memory") (realloc (func $main "realloc"))))
+  (func (export "load-config") (param "config" (option string)) (result (result (error string)))
+    (canon lift (core func $main "load-config") (memory $main "memory") (realloc (func $main "realloc"))))
+)
@@ -0,0 +1,162 @@
+;; A site plugin for the wasm host tests, the echo plugin (see echo-plugin.wat) under another
+;; domain key and host patterns: `wasm-grab` claims `*.com` and `127.0.0.1`, which must not let
+;; it fetch from there.
+(component
+  (import "mangater:plugin/host@0.1.0" (instance $host
+    (export "fetch" (func (param "url" string) (result (result (list u8) (error string)))))
+    (export "log" (func (param "message" string)))
+  ))
+
+  ;; memory and bump allocator, instantiated first so the host imports can be lowered
+  (core module $Libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param $old i32) (param $old_size i32) (param $align i32) (param $size i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get $align))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get $size)))
+      (block $fits
+        (br_if $fits (i32.le_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536))))
+        (drop (memory.grow
+          (i32.add
+            (i32.div_u
+              (i32.sub (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+              (i32.const 65536))
+            (i32.const 1)))))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $Libc))
+
+  (core func $fetch (canon lower (func $host "fetch") (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (core func $log (canon lower (func $host "log") (memory $libc "memory")))
+
+  (core module $Main
+    (import "libc" "memory" (memory 1))
+    (import "host" "fetch" (func $fetch (param i32 i32 i32)))
+    (import "host" "log" (func $log (param i32 i32)))
+
+    (global $url_ptr (mut i32) (i32.const 0))
+    (global $url_len (mut i32) (i32.const 0))
+
+    (data (i32.const 16) "wasm-grab")
+    (data (i32.const 32) "*.com")
+    (data (i32.const 48) "127.0.0.1")
+    (data (i32.const 80) "https://echo.example.com/")
+    (data (i32.const 112) "empty url")
+    (data (i32.const 128) "echo")
+    (data (i32.const 136) "error")
+    (data (i32.const 144) "fetching")
+
+    ;; return area of the exports (256..) and of the fetch import (320..)
+    (func (export "domain-key") (result i32)
+      (i32.store (i32.const 256) (i32.const 16))
+      (i32.store (i32.const 260) (i32.const 9))
+      (i32.const 256))
+
+    (func (export "host-patterns") (result i32)
+      (i32.store (i32.const 272) (i32.const 32))
+      (i32.store (i32.const 276) (i32.const 5))
+      (i32.store (i32.const 280) (i32.const 48))
+      (i32.store (i32.const 284) (i32.const 9))
+      (i32.store (i32.const 256) (i32.const 272))
+      (i32.store (i32.const 260) (i32.const 2))
+      (i32.const 256))
+
+    (func (export "match-domain") (param $ptr i32) (param $len i32) (result i32)
+      (local $i i32)
+      (local $matched i32)
+      ;; empty url: err("empty url")
+      (if (i32.eqz (local.get $len))
+        (then
+          (i32.store8 (i32.const 256) (i32.const 1))
+          (i32.store (i32.const 260) (i32.const 112))
+          (i32.store (i32.const 264) (i32.const 9))
+          (return (i32.const 256))))
+      ;; "!": spin forever
+      (if (i32.eq (i32.load8_u (local.get $ptr)) (i32.const 33))
+        (then (loop $spin (br $spin))))
+      ;; prefix check against "https://echo.example.com/" (25 bytes)
+      (local.set $matched (i32.ge_u (local.get $len) (i32.const 25)))
+      (block $done
+        (loop $next
+          (br_if $done (i32.eqz (local.get $matched)))
+          (br_if $done (i32.eq (local.get $i) (i32.const 25)))
+          (if (i32.ne
+                (i32.load8_u (i32.add (local.get $ptr) (local.get $i)))
+                (i32.load8_u (i32.add (i32.const 80) (local.get $i))))
+            (then (local.set $matched (i32.const 0))))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $next)))
+      (i32.store8 (i32.const 256) (i32.const 0))
+      (i32.store8 (i32.const 260) (local.get $matched))
+      (i32.const 256))
+
+    (func (export "match-patterns") (result i32)
+      ;; no url configured: empty list
+      (if (i32.eqz (global.get $url_len))
+        (then
+          (i32.store (i32.const 256) (i32.const 0))
+          (i32.store (i32.const 260) (i32.const 0))
+          (return (i32.const 256))))
+      (call $log (i32.const 144) (i32.const 8))
+      (call $fetch (global.get $url_ptr) (global.get $url_len) (i32.const 320))
+
+      ;; one record (24 bytes) at 384: pattern, pattern-type, resource-string
+      (if (i32.eqz (i32.load8_u (i32.const 320)))
+        (then
+          (i32.store (i32.const 384) (i32.const 128))
+          (i32.store (i32.const 388) (i32.const 4))
+          (i32.store8 (i32.const 392) (i32.const 3)))
+        (else
+          (i32.store (i32.const 384) (i32.const 136))
+          (i32.store (i32.const 388) (i32.const 5))
+          (i32.store8 (i32.const 392) (i32.const 4))))
+      ;; some(body or error message), as handed over by the host
+      (i32.store8 (i32.const 396) (i32.const 1))
+      (i32.store (i32.const 400) (i32.load (i32.const 324)))
+      (i32.store (i32.const 404) (i32.load (i32.const 328)))
+
+      (i32.store (i32.const 256) (i32.const 384))
+      (i32.store (i32.const 260) (i32.const 1))
+      (i32.const 256))
+
+    (func (export "load-config") (param $is_some i32) (param $ptr i32) (param $len i32) (result i32)
+      ;; a JSON string: the url without its quotes
+      (if (i32.and (local.get $is_some) (i32.ge_u (local.get $len) (i32.const 2)))
+        (then
+          (global.set $url_ptr (i32.add (local.get $ptr) (i32.const 1)))
+          (global.set $url_len (i32.sub (local.get $len) (i32.const 2)))))
+      (i32.store8 (i32.const 256) (i32.const 0))
+      (i32.const 256))
+  )
+  (core instance $main (instantiate $Main
+    (with "libc" (instance $libc))
+    (with "host" (instance
+      (export "fetch" (func $fetch))
+      (export "log" (func $log))
+    ))
+  ))
+
+  (type $pattern-type' (enum "resource" "pagination" "content" "scraped-content" "others"))
+  (export $pattern-type "pattern-type" (type $pattern-type'))
+  (type $pattern-match-result' (record
+    (field "pattern" string)
+    (field "pattern-type" $pattern-type)
+    (field "resource-string" (option string))
+  ))
+  (export $pattern-match-result "pattern-match-result" (type $pattern-match-result'))
+
+  (func (export "domain-key") (result string)
+    (canon lift (core func $main "domain-key") (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (func (export "host-patterns") (result (list string))
+    (canon lift (core func $main "host-patterns") (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (func (export "match-domain") (param "url" string) (result (result bool (error string)))
+    (canon lift (core func $main "match-domain") (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (func (export "match-patterns") (result (list $pattern-match-result))
+    (canon lift (core func $main "match-patterns") (memory $libc "memory") (realloc (func $libc "realloc"))))
+  (func (export "load-config") (param "config" (option string)) (result (result (error string)))
+    (canon lift (core func $main "load-config") (memory $libc "memory") (realloc (func $libc "realloc"))))
+)
@@ -0,0 +1,16 @@
+use std::process::Command;
+
+/// Records the compiler version for the dynamic plugin handshake (`plugin::PLUGIN_BUILD`);
+/// Rust types only cross a library boundary safely when both sides share the compiler.
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=MANGATER_RUSTC_VERSION={}", version);
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
@@ -28,6 +28,10 @@
 mod model;
 
 pub use model::{
-    AppConfigJson5, CoreConfig, HtmlImage, HtmlPlainTextAndImages, PatternAndType,
-    PatternMatchResult, PatternType, ProxyConfig, Registerable,
+    AppConfigJson5, CacheMode, CollisionPolicy, ContentConfig, ContentFormat, CoreConfig,
+    CrawlConfig, DedupConfig, DomainCandidate, DownloadedResource, HtmlImage,
+    HtmlPlainTextAndImages, HttpCacheConfig, HttpConfig, LinkMode, PatternAndType,
+    PatternMatchResult, PatternType, PersistContext, PluginOverrideConfig, ProxyConfig,
+    ProxyScheme, RateLimitConfig, Registerable, ResourceMetadata, RetryConfig, RobotsConfig,
+    SiteDefinition, StorageConfig, StorageFormat, WasmConfig, PLUGIN_OVERRIDES_KEY,
 };
@@ -31,7 +31,8 @@
 //! *See also:* Matcher trait definition in [`../traits/matcher.rs`]
 //!
 
-use crate::traits::{Config, Matcher, Storage};
+use crate::errors::SdkError;
+use crate::traits::{Config, Domain, Matcher, Storage};
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -44,6 +45,7 @@ use serde_json::Value;
 /// `PatternAndType` combines a string-based pattern (typically a regular expression or substring)
 /// with its corresponding [`PatternType`], allowing consumers to distinguish
 /// between patterns for resources and pagination/navigation links.
+#[derive(Debug, Deserialize, Clone)]
 pub struct PatternAndType {
     /// The pattern string (e.g., a regular expression or URL pattern) to match on the web page.
     pub pattern: String,
@@ -55,7 +57,8 @@ pub struct PatternAndType {
 ///
 /// `PatternType` helps specify whether the pattern is intended for a resource (such as image, PDF, video, audio, document, etc)
 /// or for pagination/navigation purposes (such as the "next" page link).
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
 pub enum PatternType {
     /// Used to match resource links on the page (e.g., images, pdfs, videos, audios, documents, etc).
     Resource,
@@ -76,6 +79,21 @@ pub struct PatternMatchResult {
     pub resource_string: Option<String>,
 }
 
+/// Where a persisted item comes from; handed over to `Storage::persist_with_context` by the engine.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PersistContext {
+    /// domain key of the plugin the item was scraped for, e.g. `wikipedia`
+    pub domain_key: String,
+    /// url the run started from; the pages reached through pagination share it
+    pub scrap_url: String,
+    /// url of the page the item was found on
+    pub page_url: String,
+    /// 1-based position of the item within the run (across pages)
+    pub index: usize,
+    /// media type of the content if known, e.g. `text/markdown; charset=utf-8`
+    pub content_type: Option<String>,
+}
+
 /// Aggregates components required for registration within the Mangater SDK.
 ///
 /// The `Registerable` struct acts as a container for the entities involved in the
@@ -113,9 +131,67 @@ pub struct Registerable {
 }
 // Box<dyn Domain>
 
+/// A registered domain (plugin) along with its answer for a url; see `Registry::resolve_candidates`.
+pub struct DomainCandidate {
+    /// key the domain was registered under, e.g. `wikipedia`
+    pub key: String,
+    pub priority: i32,
+    pub domain: Arc<dyn Domain>,
+    /// whether the host of the url matched its `Domain::host_patterns`; `None` if it declares none
+    pub host_match: Option<bool>,
+    /// whether the domain supports the url: `Ok(false)` if the host patterns rule it out, else
+    /// what `Domain::match_domain` answered
+    pub matched: Result<bool, SdkError>,
+}
+
+impl DomainCandidate {
+    pub fn is_match(&self) -> bool {
+        matches!(self.matched, Ok(true))
+    }
+}
+
+impl std::fmt::Debug for DomainCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DomainCandidate")
+            .field("key", &self.key)
+            .field("priority", &self.priority)
+            .field("host_match", &self.host_match)
+            .field("matched", &self.matched)
+            .finish()
+    }
+}
+
+/// Raw bytes of a downloaded resource along with its response metadata.
+#[derive(Debug, Clone)]
+pub struct DownloadedResource {
+    /// the response body as-is (no text decoding involved; safe for images, pdfs etc)
+    pub bytes: Vec<u8>,
+    pub metadata: ResourceMetadata,
+}
+
+/// Response metadata of a downloaded resource.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceMetadata {
+    /// value of the `Content-Type` header (e.g. `image/png`, `text/html; charset=utf-8`)
+    pub content_type: Option<String>,
+    /// value of the `Content-Length` header, if provided by the remote server
+    pub content_length: Option<u64>,
+    /// url of the resource after following redirects
+    pub final_url: String,
+    /// value of the `ETag` header
+    pub etag: Option<String>,
+    /// value of the `Last-Modified` header
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct HtmlImage {
+    /// absolute url of the image; the highest resolution candidate if a `srcset` is provided
     pub src: String,
     pub inner_html: String,
+    pub alt: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
 }
 
 pub struct HtmlPlainTextAndImages {
@@ -135,10 +211,605 @@ pub struct AppConfigJson5 {
 pub struct CoreConfig {
     #[serde(default)]
     pub proxy: Option<ProxyConfig>,
+
+    #[serde(default)]
+    pub http: Option<HttpConfig>,
+
+    /// politeness controls applied per domain key (plugin)
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+
+    /// on-disk HTTP cache; responses are not cached unless configured
+    #[serde(default)]
+    pub cache: Option<HttpCacheConfig>,
+
+    /// robots.txt compliance; enabled by default
+    #[serde(default)]
+    pub robots: Option<RobotsConfig>,
+
+    /// pagination limits
+    #[serde(default)]
+    pub crawl: Option<CrawlConfig>,
+
+    /// content extraction applied by `PatternType::Content` patterns
+    #[serde(default)]
+    pub content: Option<ContentConfig>,
+
+    /// storage used when neither the plugin nor the application provides one
+    #[serde(default)]
+    pub storage: Option<StorageConfig>,
+
+    /// folder of the dynamic plugins (shared libraries, e.g. `.so`) loaded at startup;
+    /// none are loaded unless set. The plugins must be built with the same mangater-sdk and
+    /// rustc as the application, the plugin ABI is not stable (see `plugin`)
+    #[serde(default)]
+    pub plugin_dir: Option<String>,
+
+    /// folder of the declarative site definitions (`.json5` files, see [`SiteDefinition`])
+    /// loaded at startup; none are loaded unless set
+    #[serde(default)]
+    pub site_dir: Option<String>,
+
+    /// WebAssembly site plugins (needs the `wasm` feature of mangater-core); none are loaded
+    /// unless `wasm.plugin_dir` is set
+    #[serde(default)]
+    pub wasm: Option<WasmConfig>,
 }
 
-#[derive(Debug, Deserialize)]
+impl AppConfigJson5 {
+    /// Returns the declarative sites of the `plugins` section (the sections having a
+    /// `url_regex`), in key order; see [`SiteDefinition`].
+    pub fn site_definitions(&self) -> Result<Vec<SiteDefinition>, SdkError> {
+        let mut keys: Vec<&String> = self
+            .plugins
+            .iter()
+            .filter(|(_, value)| value.get("url_regex").is_some())
+            .map(|(key, _)| key)
+            .collect();
+        keys.sort();
+
+        keys.into_iter()
+            .map(|key| {
+                let mut site: SiteDefinition = serde_json::from_value(self.plugins[key].clone())
+                    .map_err(|e| SdkError::InvalidConfig(format!("plugins.{} - {}", key, e)))?;
+                if site.domain_key.is_empty() {
+                    site.domain_key = key.clone();
+                } else if &site.domain_key != key {
+                    return Err(SdkError::InvalidConfig(format!(
+                        "plugins.{} - domain_key {} does not match the section",
+                        key, site.domain_key
+                    )));
+                }
+                Ok(site)
+            })
+            .collect()
+    }
+
+    /// Returns the core config overrides of the plugin registered under `domain_key`, read from
+    /// the reserved `core` key of its section (e.g. `plugins.wikipedia.core.proxy`). Plugins
+    /// without one have no overrides.
+    pub fn plugin_overrides(&self, domain_key: &str) -> Result<PluginOverrideConfig, SdkError> {
+        match self
+            .plugins
+            .get(domain_key)
+            .and_then(|section| section.get(PLUGIN_OVERRIDES_KEY))
+        {
+            Some(value) => serde_json::from_value(value.clone()).map_err(|e| {
+                SdkError::InvalidConfig(format!(
+                    "plugins.{}.{} - {}",
+                    domain_key, PLUGIN_OVERRIDES_KEY, e
+                ))
+            }),
+            None => Ok(PluginOverrideConfig::default()),
+        }
+    }
+}
+
+/// A site scraped without writing a plugin: the urls it supports and the patterns applied on
+/// its pages (e.g. the images and the "next" page link); see `util::declarative_site` of
+/// mangater-core.
+///
+/// Either a section of `AppConfigJson5.plugins` having a `url_regex` (the domain key is the
+/// section's name, its core overrides go under `core` as for any plugin), or a standalone
+/// `.json5` file of `CoreConfig::site_dir`:
+/// ```json5
+/// {
+///     domain_key: "mangadex",
+///     url_regex: "^https://mangadex\\.org/chapter/",
+///     host_patterns: ["mangadex.org"],
+///     patterns: [
+///         { pattern: "img.page", pattern_type: "resource" },
+///         { pattern: "a.next", pattern_type: "pagination" },
+///     ],
+/// }
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct SiteDefinition {
+    /// key the site registers under; the section's name for a site of `plugins`
+    #[serde(default)]
+    pub domain_key: String,
+    /// regular expression the urls of the site match (the whole url, e.g. `^https://...`)
+    pub url_regex: String,
+    /// see `Domain::host_patterns`; none by default
+    #[serde(default)]
+    pub host_patterns: Vec<String>,
+    /// patterns applied on every page of the site, in order
+    pub patterns: Vec<PatternAndType>,
+}
+
+/// key of a plugin section (`plugins.<key>.core`) holding its [`PluginOverrideConfig`]
+pub const PLUGIN_OVERRIDES_KEY: &str = "core";
+
+/// Core settings a plugin may override, under the reserved [`PLUGIN_OVERRIDES_KEY`] of its own
+/// section of `AppConfigJson5.plugins`:
+/// ```json5
+/// { plugins: { manga: { core: { rate_limit: { requests_per_second: 0.5 } } } } }
+/// ```
+///
+/// Any other field in the plugin section is left to the plugin's own `Config` implementation,
+/// whatever its name and shape.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct PluginOverrideConfig {
+    /// replaces `core.proxy` for every request made on behalf of the plugin
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+
+    /// replaces `core.rate_limit` for every request made on behalf of the plugin
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+
+    /// replaces `core.robots`; e.g. to crawl a site we have permission for regardless of its robots.txt
+    #[serde(default)]
+    pub robots: Option<RobotsConfig>,
+
+    /// replaces `core.crawl`; e.g. a higher `max_pages` for long manga chapters
+    #[serde(default)]
+    pub crawl: Option<CrawlConfig>,
+
+    /// replaces `core.content`; e.g. the site specific infobox / navigation selectors to leave out
+    #[serde(default)]
+    pub content: Option<ContentConfig>,
+
+    /// registry priority of the plugin, default `0`; the highest one wins when several plugins
+    /// match the same url
+    #[serde(default)]
+    pub priority: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct ProxyConfig {
+    /// proxy address, e.g. `proxy.local:3128` or `socks5://proxy.local:1080`;
+    /// no proxy is applied if missing
+    #[serde(default)]
+    pub url: Option<String>,
+    /// scheme used when `url` has none, default `http`
+    #[serde(default)]
+    pub scheme: Option<ProxyScheme>,
     pub username: Option<String>,
     pub password: Option<String>,
+    /// hosts / domains / IP ranges that bypass the proxy (e.g. `localhost`, `.internal.com`, `10.0.0.0/8`)
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+/// Supported proxy protocols.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyScheme {
+    Http,
+    Https,
+    Socks5,
+}
+
+impl ProxyScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyScheme::Http => "http",
+            ProxyScheme::Https => "https",
+            ProxyScheme::Socks5 => "socks5",
+        }
+    }
+}
+
+/// Settings of the shared HTTP client (see `util::http::HttpFetcher`).
+///
+/// Every field is optional in the config file; missing fields fall back to the defaults below.
+#[derive(Debug, Deserialize, Clone)]
+pub struct HttpConfig {
+    /// user agent sent on every request; defaults to `util::resource::DEFAULT_USER_AGENT`
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// total timeout of a request (connect + response body), default 30 seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// timeout of the connect phase only, default 10 seconds
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// headers added to every request (e.g. `Accept-Language`)
+    #[serde(default)]
+    pub default_headers: HashMap<String, String>,
+    /// keep cookies set by the remote server(s) across requests
+    #[serde(default)]
+    pub cookie_store: bool,
+    /// accept gzip compressed responses
+    #[serde(default = "default_true")]
+    pub gzip: bool,
+    /// accept brotli compressed responses
+    #[serde(default = "default_true")]
+    pub brotli: bool,
+    /// maximum number of redirects to follow, `0` disables redirects; default 10
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+    /// maximum size (in bytes) of a response body, unlimited if not set
+    #[serde(default)]
+    pub max_body_size: Option<u64>,
+    /// retry policy for transient failures
+    #[serde(default)]
+    pub retry: RetryConfig,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: None,
+            timeout_secs: default_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            default_headers: HashMap::new(),
+            cookie_store: false,
+            gzip: true,
+            brotli: true,
+            max_redirects: default_max_redirects(),
+            max_body_size: None,
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+/// Politeness controls (see `util::rate_limit::RateLimiter`); every domain key (plugin) gets its
+/// own token bucket and concurrency cap.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitConfig {
+    /// sustained number of requests per second; unlimited if not set
+    #[serde(default)]
+    pub requests_per_second: Option<f64>,
+    /// number of requests allowed back-to-back before the rate kicks in, default 1
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    /// maximum number of in-flight requests, `0` means unlimited; default 4
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: None,
+            burst: default_burst(),
+            max_concurrency: default_max_concurrency(),
+        }
+    }
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+fn default_max_concurrency() -> usize {
+    4
+}
+
+/// On-disk HTTP cache (see `util::http_cache::HttpCache`).
+#[derive(Debug, Deserialize, Clone)]
+pub struct HttpCacheConfig {
+    /// folder of the cached responses, default `.cache/http`
+    #[serde(default = "default_cache_dir")]
+    pub dir: String,
+    /// how the cache is used, default `normal`
+    #[serde(default)]
+    pub mode: CacheMode,
+}
+
+impl Default for HttpCacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_cache_dir(),
+            mode: CacheMode::default(),
+        }
+    }
+}
+
+fn default_cache_dir() -> String {
+    ".cache/http".to_string()
+}
+
+/// WebAssembly site plugins, run sandboxed: they only reach the host functions of the
+/// `mangater:plugin` WIT interface, within the memory and fuel (instructions) limits below.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WasmConfig {
+    /// folder of the plugins (`.wasm` components) loaded at startup
+    #[serde(default)]
+    pub plugin_dir: Option<String>,
+    /// linear memory a plugin may grow to, default 64 MiB
+    #[serde(default = "default_wasm_max_memory_mb")]
+    pub max_memory_mb: u64,
+    /// fuel given to each call into a plugin, default 1 billion; a plugin running out of it
+    /// (e.g. looping forever) is stopped and the call fails
+    #[serde(default = "default_wasm_fuel")]
+    pub fuel: u64,
+    /// hosts a plugin may fetch from, by domain key; none unless listed here (the plugin's own
+    /// `host-patterns` grant nothing). Same syntax as `Domain::host_patterns`, e.g.
+    /// `{ "manga": ["manga.example.com", "*.cdn.example.com"] }`
+    #[serde(default)]
+    pub allowed_hosts: HashMap<String, Vec<String>>,
+}
+
+impl Default for WasmConfig {
+    fn default() -> Self {
+        Self {
+            plugin_dir: None,
+            max_memory_mb: default_wasm_max_memory_mb(),
+            fuel: default_wasm_fuel(),
+            allowed_hosts: HashMap::new(),
+        }
+    }
+}
+
+fn default_wasm_max_memory_mb() -> u64 {
+    64
+}
+
+fn default_wasm_fuel() -> u64 {
+    1_000_000_000
+}
+
+/// How `util::http_cache::HttpCache` is used by the fetcher.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheMode {
+    /// fresh responses are served from the cache, stale ones are revalidated with the server
+    #[default]
+    Normal,
+    /// responses are served from the cache only (stale or not); never hits the network
+    Offline,
+    /// every response is downloaded again and replaces the cached one
+    Refresh,
+}
+
+/// File system storage (see `storage::FileSystemStorage`, `storage::CbzStorage` and `storage::EpubStorage`).
+#[derive(Debug, Deserialize, Clone)]
+pub struct StorageConfig {
+    /// how the scraped items are written, default `files`
+    #[serde(default)]
+    pub format: StorageFormat,
+    /// root directory of the scraped files, default `output`
+    #[serde(default = "default_storage_output")]
+    pub output: String,
+    /// path template of every file, default `{output}/{domain_key}/{page_slug}/{index}_{filename}`
+    #[serde(default = "default_storage_layout")]
+    pub layout: String,
+    /// what to do when the file already exists, default `suffix`
+    #[serde(default)]
+    pub collision: CollisionPolicy,
+    /// `epub` only: bundle every article of the process into `{output}/{book}.epub`, one chapter
+    /// per article; one book per article if missing
+    #[serde(default)]
+    pub book: Option<String>,
+    /// `epub` only: language of the books, default `en`
+    #[serde(default = "default_storage_language")]
+    pub language: String,
+    /// SQLite database recording every item persisted (whatever the storage), e.g.
+    /// `output/catalog.db`; no catalog if missing (see `storage::CatalogStorage`)
+    #[serde(default)]
+    pub catalog: Option<String>,
+    /// stores identical files once (see `storage::DedupStorage`); no deduplication if missing
+    #[serde(default)]
+    pub dedup: Option<DedupConfig>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            format: StorageFormat::default(),
+            output: default_storage_output(),
+            layout: default_storage_layout(),
+            collision: CollisionPolicy::default(),
+            book: None,
+            language: default_storage_language(),
+            catalog: None,
+            dedup: None,
+        }
+    }
+}
+
+fn default_storage_output() -> String {
+    "output".to_string()
+}
+
+fn default_storage_layout() -> String {
+    "{output}/{domain_key}/{page_slug}/{index}_{filename}".to_string()
+}
+
+fn default_storage_language() -> String {
+    "en".to_string()
+}
+
+/// How the storage fallback writes the scraped items.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageFormat {
+    /// one file per item, laid out by `StorageConfig::layout`
+    #[default]
+    Files,
+    /// one comic archive per run (chapter) holding its images, `{output}/{domain_key}/{page_slug}.cbz`
+    Cbz,
+    /// one EPUB book per run (article) holding its text and images, `{output}/{domain_key}/{page_slug}.epub`,
+    /// or a single book for all of them (`StorageConfig::book`)
+    Epub,
+}
+
+/// Content-addressed deduplication of the files written (see `storage::BlobStore`).
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct DedupConfig {
+    /// folder of the blobs, default `{output}/.blobs`; on the same file system as the output
+    /// for hard links to work
+    #[serde(default)]
+    pub root: Option<String>,
+    /// how the written paths refer to their blob, default `hardlink`
+    #[serde(default)]
+    pub link: LinkMode,
+}
+
+/// How a path deduplicated by `storage::BlobStore` refers to its blob.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+    /// the file is a hard link to the blob
+    #[default]
+    Hardlink,
+    /// no file, the path is a manifest entry of the blob store only
+    Manifest,
+}
+
+/// What to do when a file to write already exists.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// keep the existing file, the new content is discarded
+    Skip,
+    /// replace the existing file
+    Overwrite,
+    /// write the new content next to the existing file, e.g. `0001_cat_1.jpg`
+    #[default]
+    Suffix,
+}
+
+/// Pagination limits; the "next" links matched by `PatternType::Pagination` are followed until
+/// there is none, a page repeats or `max_pages` is reached.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CrawlConfig {
+    /// maximum number of pages fetched per run (first page included), `0` means unlimited; default 200
+    #[serde(default = "default_max_pages")]
+    pub max_pages: usize,
+}
+
+impl Default for CrawlConfig {
+    fn default() -> Self {
+        Self {
+            max_pages: default_max_pages(),
+        }
+    }
+}
+
+fn default_max_pages() -> usize {
+    200
+}
+
+/// Content extraction (see `util::html_parsing::parse_plain_text`); the content itself is
+/// selected by the `pattern` of the `PatternType::Content` patterns.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ContentConfig {
+    /// CSS selectors of the elements left out of the content, e.g. `nav`, `.infobox`, `footer`
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// format the content is extracted as, default `text`
+    #[serde(default)]
+    pub format: ContentFormat,
+}
+
+/// Formats of the content extracted by `PatternType::Content` patterns.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    /// plain-text, see `util::html_parsing::parse_plain_text`
+    #[default]
+    Text,
+    /// GitHub flavored Markdown, see `util::markdown::parse_markdown`
+    Markdown,
+}
+
+/// robots.txt compliance (see `util::robots::RobotsCache`).
+#[derive(Debug, Deserialize, Clone)]
+pub struct RobotsConfig {
+    /// check the `Allow` / `Disallow` rules and honor `Crawl-delay` before every request, default true
+    #[serde(default = "default_robots_enabled")]
+    pub enabled: bool,
+}
+
+impl Default for RobotsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_robots_enabled(),
+        }
+    }
+}
+
+fn default_robots_enabled() -> bool {
+    true
+}
+
+/// Retry policy (exponential backoff with jitter) for transient HTTP failures;
+/// i.e. connection errors, timeouts, HTTP 408 / 429 / 500 / 502 / 503 / 504.
+///
+/// A `Retry-After` header sent by the remote server takes precedence over the computed backoff.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RetryConfig {
+    /// maximum number of retries after the first attempt, `0` disables retries; default 3
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// backoff before the first retry, default 500 ms
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// upper bound of the backoff (and of an acceptable `Retry-After`), default 30 seconds
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// growth factor of the backoff per retry, default 2.0
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// randomize the backoff to avoid retrying in lock-step, default true
+    #[serde(default = "default_true")]
+    pub jitter: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            multiplier: default_multiplier(),
+            jitter: true,
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_max_redirects() -> usize {
+    10
+}
+
+fn default_true() -> bool {
+    true
 }
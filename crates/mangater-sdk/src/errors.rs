@@ -63,6 +63,14 @@ pub enum SdkError {
     #[error("authentication failed")]
     Authentication,
 
+    /// The url is disallowed by the site's robots.txt.
+    #[error("blocked by robots.txt: {0}")]
+    RobotsDisallowed(String),
+
+    /// A plugin could not be loaded, e.g. a dynamic library built against another ABI.
+    #[error("plugin error: {0}")]
+    Plugin(String),
+
     /// Generic plugin error for site-specific cases.
     #[error("site error: {0}")]
     Site(String),
@@ -91,7 +99,7 @@ mod tests {
 
     #[test]
     fn test_io_conversion() {
-        let io_err = std::io::Error::new(std::io::ErrorKind::Other, "disk");
+        let io_err = std::io::Error::other("disk");
         let sdk_err: SdkError = io_err.into();
 
         match sdk_err {
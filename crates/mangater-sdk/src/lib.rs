@@ -26,6 +26,8 @@
 // SOFTWARE.
 
 pub mod entity;
+pub mod plugin;
+pub mod storage;
 pub mod traits;
 pub mod util;
 
@@ -0,0 +1,190 @@
+// mangater-sdk - the interface for Mangater
+// Copyright (C) 2026 Takara-Mono <quoeamaster@gmail.com>
+//
+// This file is dual-licensed under the terms of the MIT.
+//
+// You may choose either license at your option.
+// For a copy of the MIT license, see <https://opensource.org/licenses/MIT>.
+//
+// MIT License:
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The ABI of dynamic plugins: `Domain` implementations built as shared libraries (`.so`)
+//! and loaded at startup, instead of being compiled into the application.
+//!
+//! A plugin crate (e.g. `site-wikipedia-plugin`) is built as a `cdylib` and exports its entry
+//! points with [`export_plugin!`]:
+//! - `mangater_plugin_abi_version` - the [`PLUGIN_ABI_VERSION`] it was built against
+//! - `mangater_plugin_build` - the [`PLUGIN_BUILD`] (sdk and compiler versions) it was built with
+//! - `mangater_plugin_create` - creates the `Domain`, from which the application gets the
+//!   `get_domain_key` / `get_domain_registerable` (and `match_domain`) entry points
+//!
+//! The first two are plain C functions, checked by the application before anything else;
+//! the `Domain` itself is handed over as a Rust trait object, hence the same sdk version and
+//! compiler are required on both sides.
+//!
+//! This is *not* a stable ABI: Rust makes no layout guarantee for trait objects across
+//! compilers, so a plugin has to be rebuilt with every new rustc or sdk release of the
+//! application (the handshake rejects it otherwise). Plugins meant to outlive a build are
+//! better off as WebAssembly components (`core.wasm`) or declarative sites.
+//!
+//! # Example
+//! ```ignore
+//! // Cargo.toml: [lib] crate-type = ["cdylib"]
+//! use mangater_sdk::traits::Config;
+//!
+//! mangater_sdk::export_plugin!(|plugins| {
+//!     let mut manga = MangaInstance::new();
+//!     manga.load(plugins.clone())?;
+//!     Ok(manga)
+//! });
+//! ```
+
+use crate::errors::SdkError;
+use crate::traits::Domain;
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
+use std::sync::Arc;
+
+/// Version of the plugin ABI; bumped on any change to the entry points or their signatures.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Versions of the sdk and the compiler a plugin has to share with the application,
+/// e.g. `mangater-sdk 0.1.0; rustc 1.95.0 (59807616e 2026-04-14)`.
+pub const PLUGIN_BUILD: &CStr = match CStr::from_bytes_with_nul(
+    concat!(
+        "mangater-sdk ",
+        env!("CARGO_PKG_VERSION"),
+        "; ",
+        env!("MANGATER_RUSTC_VERSION"),
+        "\0"
+    )
+    .as_bytes(),
+) {
+    Ok(build) => build,
+    Err(_) => panic!("the plugin build info should be a C string"),
+};
+
+/// symbol of the `extern "C" fn() -> u32` returning the plugin's [`PLUGIN_ABI_VERSION`]
+pub const ABI_VERSION_SYMBOL: &[u8] = b"mangater_plugin_abi_version\0";
+/// symbol of the `extern "C" fn() -> *const c_char` returning the plugin's [`PLUGIN_BUILD`]
+pub const BUILD_SYMBOL: &[u8] = b"mangater_plugin_build\0";
+/// symbol of the [`CreateFn`] creating the plugin's `Domain`
+pub const CREATE_SYMBOL: &[u8] = b"mangater_plugin_create\0";
+
+/// the `plugins` section of the config (`AppConfigJson5.plugins`), as handed to `Config::load`
+pub type PluginConfigValues = HashMap<String, Value>;
+
+pub type AbiVersionFn = unsafe extern "C" fn() -> u32;
+pub type BuildFn = unsafe extern "C" fn() -> *const c_char;
+/// Creates the plugin's `Domain` out of the `plugins` section of the config.
+pub type CreateFn = fn(&PluginConfigValues) -> Result<Arc<dyn Domain>, SdkError>;
+
+/// Builds the plugin's `Domain` with the given constructor; used by [`export_plugin!`].
+pub fn create_plugin<D: Domain + 'static>(
+    constructor: fn(&PluginConfigValues) -> Result<D, SdkError>,
+    plugins: &PluginConfigValues,
+) -> Result<Arc<dyn Domain>, SdkError> {
+    Ok(Arc::new(constructor(plugins)?))
+}
+
+/// Checks the handshake of a plugin (see [`ABI_VERSION_SYMBOL`], [`BUILD_SYMBOL`]) against
+/// the application's.
+///
+/// # Errors
+///
+/// Returns `SdkError::Plugin` telling both sides apart if they do not match.
+pub fn check_handshake(name: &str, abi_version: u32, build: &CStr) -> Result<(), SdkError> {
+    if abi_version != PLUGIN_ABI_VERSION {
+        return Err(SdkError::Plugin(format!(
+            "{} - plugin ABI version {} is not supported, expected {}; rebuild the plugin against this version of mangater-sdk",
+            name, abi_version, PLUGIN_ABI_VERSION
+        )));
+    }
+    if build != PLUGIN_BUILD {
+        return Err(SdkError::Plugin(format!(
+            "{} - plugin built with {}, expected {}; rebuild the plugin with the same mangater-sdk and compiler",
+            name,
+            build.to_string_lossy(),
+            PLUGIN_BUILD.to_string_lossy()
+        )));
+    }
+    Ok(())
+}
+
+/// Exports the entry points of a dynamic plugin (see the [module docs](crate::plugin)), given
+/// the constructor of its `Domain` out of the `plugins` section of the config.
+///
+/// Invoke it once, in the root of a `cdylib` crate.
+#[macro_export]
+macro_rules! export_plugin {
+    ($constructor:expr) => {
+        #[no_mangle]
+        pub extern "C" fn mangater_plugin_abi_version() -> u32 {
+            $crate::plugin::PLUGIN_ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn mangater_plugin_build() -> *const ::std::ffi::c_char {
+            $crate::plugin::PLUGIN_BUILD.as_ptr()
+        }
+
+        #[no_mangle]
+        pub fn mangater_plugin_create(
+            plugins: &$crate::plugin::PluginConfigValues,
+        ) -> ::std::result::Result<::std::sync::Arc<dyn $crate::traits::Domain>, $crate::SdkError> {
+            $crate::plugin::create_plugin($constructor, plugins)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_handshake() {
+        assert!(check_handshake("libmanga.so", PLUGIN_ABI_VERSION, PLUGIN_BUILD).is_ok());
+        assert!(PLUGIN_BUILD.to_string_lossy().starts_with("mangater-sdk "));
+
+        match check_handshake("libmanga.so", PLUGIN_ABI_VERSION + 1, PLUGIN_BUILD) {
+            Err(SdkError::Plugin(msg)) => {
+                assert!(msg.contains("libmanga.so"));
+                assert!(msg.contains(&format!(
+                    "ABI version {} is not supported, expected {}",
+                    PLUGIN_ABI_VERSION + 1,
+                    PLUGIN_ABI_VERSION
+                )));
+            }
+            other => panic!("Expected SdkError::Plugin, got {:?}", other),
+        }
+        match check_handshake(
+            "libmanga.so",
+            PLUGIN_ABI_VERSION,
+            c"mangater-sdk 0.0.1; rustc 1.0.0",
+        ) {
+            Err(SdkError::Plugin(msg)) => {
+                assert!(msg.contains("built with mangater-sdk 0.0.1; rustc 1.0.0"))
+            }
+            other => panic!("Expected SdkError::Plugin, got {:?}", other),
+        }
+    }
+}
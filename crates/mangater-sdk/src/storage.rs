@@ -0,0 +1,42 @@
+// mangater-sdk - the interface for Mangater
+// Copyright (C) 2026 Takara-Mono <quoeamaster@gmail.com>
+//
+// This file is dual-licensed under the terms of the MIT.
+//
+// You may choose either license at your option.
+// For a copy of the MIT license, see <https://opensource.org/licenses/MIT>.
+//
+// MIT License:
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod blob;
+mod catalog;
+mod cbz;
+mod epub;
+mod file_system;
+#[cfg(test)]
+mod test_util;
+
+pub use blob::{BlobStore, DedupStorage};
+pub use catalog::{Catalog, CatalogEntry, CatalogStats, CatalogStorage, DomainStats};
+pub use cbz::{CbzStorage, ComicInfo};
+pub use epub::EpubStorage;
+pub use file_system::FileSystemStorage;
+
+pub(crate) use file_system::write_atomic;
@@ -0,0 +1,578 @@
+use super::file_system::write_atomic;
+use crate::entity::{LinkMode, PatternMatchResult, PersistContext};
+use crate::errors::SdkError;
+use crate::traits::Storage;
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blobs (
+    hash TEXT PRIMARY KEY,
+    byte_size INTEGER NOT NULL,
+    refcount INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS links (
+    path TEXT PRIMARY KEY,
+    hash TEXT NOT NULL REFERENCES blobs (hash)
+);
+CREATE INDEX IF NOT EXISTS links_hash ON links (hash);
+";
+
+/// distinguishes the temp links of concurrent writes
+static LINK_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// SHA-256 of the content, hex encoded
+pub(super) fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Content-addressed store; identical bytes are stored once, however many paths refer to them.
+///
+/// Blobs live under `{root}/objects/{hash[..2]}/{hash}`; the paths referring to them (the
+/// manifest) and the reference count of every blob are kept in `{root}/blobs.db`. A path is
+/// materialized as per [`LinkMode`]:
+/// * `hardlink` - the file at the path becomes a hard link to the blob (falls back to keeping
+///   the file as-is where hard links are not supported, e.g. across file systems)
+/// * `manifest` - no file at the path; [`BlobStore::resolve`] tells which blob holds its content
+///
+/// A blob is deleted once no path refers to it anymore.
+#[derive(Debug)]
+pub struct BlobStore {
+    root: PathBuf,
+    link: LinkMode,
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl BlobStore {
+    /// Opens (or creates) the store under the given folder.
+    pub fn open(root: impl Into<PathBuf>, link: LinkMode) -> Result<Self, SdkError> {
+        let root = root.into();
+        std::fs::create_dir_all(root.join("objects"))?;
+        let connection = Connection::open(root.join("blobs.db")).map_err(blob_error)?;
+        connection.execute_batch(SCHEMA).map_err(blob_error)?;
+        Ok(Self {
+            root,
+            link,
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// where the blob of the given hash is stored
+    pub fn blob_path(&self, hash: &str) -> PathBuf {
+        self.root.join("objects").join(&hash[..2]).join(hash)
+    }
+
+    /// number of paths referring to the blob; 0 if unknown
+    pub async fn refcount(&self, hash: &str) -> Result<u64, SdkError> {
+        let hash = hash.to_string();
+        let refcount: Option<i64> = self
+            .with_connection(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT refcount FROM blobs WHERE hash = ?1",
+                        [hash],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                    .map_err(blob_error)
+            })
+            .await?;
+        Ok(refcount.unwrap_or(0) as u64)
+    }
+
+    /// The blob holding the content of the path, if linked.
+    pub async fn resolve(&self, path: &Path) -> Result<Option<PathBuf>, SdkError> {
+        let path = path.to_string_lossy().to_string();
+        let hash: Option<String> = self
+            .with_connection(move |connection| {
+                connection
+                    .query_row("SELECT hash FROM links WHERE path = ?1", [path], |row| {
+                        row.get(0)
+                    })
+                    .optional()
+                    .map_err(blob_error)
+            })
+            .await?;
+        Ok(hash.map(|hash| self.blob_path(&hash)))
+    }
+
+    /// every linked path and the hash of its blob, ordered by path
+    pub async fn manifest(&self) -> Result<Vec<(PathBuf, String)>, SdkError> {
+        self.with_connection(|connection| {
+            let mut statement = connection
+                .prepare("SELECT path, hash FROM links ORDER BY path")
+                .map_err(blob_error)?;
+            let links = statement
+                .query_map([], |row| {
+                    Ok((PathBuf::from(row.get::<_, String>(0)?), row.get(1)?))
+                })
+                .map_err(blob_error)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(blob_error)?;
+            Ok(links)
+        })
+        .await
+    }
+
+    /// Links the path to the blob of the content (stored first if new); a path linked to another
+    /// blob before releases it. Returns the hash of the content.
+    pub async fn link(&self, path: &Path, content: &[u8]) -> Result<String, SdkError> {
+        let hash = sha256_hex(content);
+        let blob_path = self.blob_path(&hash);
+        if !tokio::fs::try_exists(&blob_path).await? {
+            if let Some(parent) = blob_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            write_atomic(&blob_path, content).await?;
+        }
+
+        let linked_path = path.to_string_lossy().to_string();
+        let linked_hash = hash.clone();
+        let byte_size = content.len() as i64;
+        let released = self
+            .with_connection(move |connection| {
+                let transaction = connection.transaction().map_err(blob_error)?;
+                let previous: Option<String> = transaction
+                    .query_row(
+                        "SELECT hash FROM links WHERE path = ?1",
+                        [&linked_path],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                    .map_err(blob_error)?;
+                let released = match previous {
+                    Some(previous) if previous == linked_hash => None,
+                    previous => {
+                        transaction
+                            .execute(
+                                "INSERT INTO blobs (hash, byte_size, refcount) VALUES (?1, ?2, 1)
+                                 ON CONFLICT (hash) DO UPDATE SET refcount = refcount + 1",
+                                params![linked_hash, byte_size],
+                            )
+                            .map_err(blob_error)?;
+                        transaction
+                            .execute(
+                                "INSERT INTO links (path, hash) VALUES (?1, ?2)
+                                 ON CONFLICT (path) DO UPDATE SET hash = excluded.hash",
+                                params![linked_path, linked_hash],
+                            )
+                            .map_err(blob_error)?;
+                        match previous {
+                            Some(previous) => decrement(&transaction, &previous)?,
+                            None => None,
+                        }
+                    }
+                };
+                transaction.commit().map_err(blob_error)?;
+                Ok(released)
+            })
+            .await?;
+        if let Some(released) = released {
+            self.remove_blob(&released).await?;
+        }
+
+        match self.link {
+            LinkMode::Hardlink => self.hard_link(&blob_path, path).await?,
+            LinkMode::Manifest => {
+                if tokio::fs::try_exists(path).await? {
+                    tokio::fs::remove_file(path).await?;
+                }
+            }
+        }
+        Ok(hash)
+    }
+
+    /// Unlinks the path (its file included); the blob goes once no path refers to it anymore.
+    pub async fn release(&self, path: &Path) -> Result<(), SdkError> {
+        let released_path = path.to_string_lossy().to_string();
+        let released = self
+            .with_connection(move |connection| {
+                let transaction = connection.transaction().map_err(blob_error)?;
+                let hash: Option<String> = transaction
+                    .query_row(
+                        "DELETE FROM links WHERE path = ?1 RETURNING hash",
+                        [released_path],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                    .map_err(blob_error)?;
+                let released = match hash {
+                    Some(hash) => decrement(&transaction, &hash)?,
+                    None => None,
+                };
+                transaction.commit().map_err(blob_error)?;
+                Ok(released)
+            })
+            .await?;
+        if tokio::fs::try_exists(path).await? {
+            tokio::fs::remove_file(path).await?;
+        }
+        if let Some(released) = released {
+            self.remove_blob(&released).await?;
+        }
+        Ok(())
+    }
+
+    /// Runs the SQLite work on tokio's blocking pool rather than on the async worker.
+    async fn with_connection<T, F>(&self, f: F) -> Result<T, SdkError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, SdkError> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || f(&mut connection.lock().unwrap()))
+            .await
+            .map_err(|e| SdkError::Storage(std::io::Error::other(format!("blob store - {}", e))))?
+    }
+
+    async fn remove_blob(&self, hash: &str) -> Result<(), SdkError> {
+        let blob_path = self.blob_path(hash);
+        if tokio::fs::try_exists(&blob_path).await? {
+            tokio::fs::remove_file(&blob_path).await?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the file at `path` with a hard link to the blob (through a temp link, renamed).
+    async fn hard_link(&self, blob_path: &Path, path: &Path) -> Result<(), SdkError> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}-{}.link",
+            file_name,
+            std::process::id(),
+            LINK_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        if let Err(e) = tokio::fs::hard_link(blob_path, &temp_path).await {
+            tracing::debug!(
+                "no hard link for {} ({}), the file is kept as-is",
+                path.display(),
+                e
+            );
+            return Ok(());
+        }
+        if let Err(e) = tokio::fs::rename(&temp_path, path).await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// Decrements the reference count of the blob; returns its hash if no path refers to it anymore.
+fn decrement(
+    transaction: &rusqlite::Transaction<'_>,
+    hash: &str,
+) -> Result<Option<String>, SdkError> {
+    transaction
+        .execute(
+            "UPDATE blobs SET refcount = refcount - 1 WHERE hash = ?1",
+            [hash],
+        )
+        .map_err(blob_error)?;
+    let removed = transaction
+        .execute(
+            "DELETE FROM blobs WHERE hash = ?1 AND refcount <= 0",
+            [hash],
+        )
+        .map_err(blob_error)?;
+    Ok((removed > 0).then(|| hash.to_string()))
+}
+
+fn blob_error(e: rusqlite::Error) -> SdkError {
+    SdkError::Storage(std::io::Error::other(format!("blob store - {}", e)))
+}
+
+/// Storage decorator deduplicating the files the wrapped storage writes through a [`BlobStore`].
+///
+/// Once the wrapped storage persisted an item, the file it was written to (as returned by
+/// [`Storage::persist_with_context`]) is handed over to the blob store if it holds exactly the item's bytes
+/// (i.e. one file per item, unlike archives or books bundling several items).
+pub struct DedupStorage {
+    inner: Arc<dyn Storage + Send + Sync>,
+    blobs: Arc<BlobStore>,
+}
+
+impl DedupStorage {
+    pub fn new(inner: Arc<dyn Storage + Send + Sync>, blobs: Arc<BlobStore>) -> Self {
+        Self { inner, blobs }
+    }
+
+    async fn deduplicate(&self, path: &Path, content: &[u8]) -> Result<(), SdkError> {
+        match tokio::fs::read(path).await {
+            Ok(written) if written == content => {
+                let hash = self.blobs.link(path, content).await?;
+                tracing::debug!("{} deduplicated as {}", path.display(), hash);
+            }
+            _ => tracing::debug!(
+                "{} does not hold the item alone, kept as-is",
+                path.display()
+            ),
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for DedupStorage {
+    async fn persist(
+        &self,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<(), SdkError> {
+        // without context, the written file cannot be told
+        self.inner.persist(resource, resource_content).await
+    }
+
+    async fn persist_with_context(
+        &self,
+        context: &PersistContext,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<Option<PathBuf>, SdkError> {
+        let written = self
+            .inner
+            .persist_with_context(context, resource, resource_content.clone())
+            .await?;
+        if let Some(path) = &written {
+            self.deduplicate(path, &resource_content).await?;
+        }
+        Ok(written)
+    }
+
+    async fn finish(&self, context: &PersistContext) -> Result<(), SdkError> {
+        self.inner.finish(context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::entity::{CollisionPolicy, StorageConfig};
+    use crate::storage::test_util::{self, image};
+    use crate::storage::{CbzStorage, FileSystemStorage};
+
+    fn dedup(
+        output: &Path,
+        link: LinkMode,
+        collision: CollisionPolicy,
+    ) -> (DedupStorage, Arc<BlobStore>) {
+        let blobs = Arc::new(BlobStore::open(output.join(".blobs"), link).unwrap());
+        let storage = FileSystemStorage::new(&StorageConfig {
+            output: output.to_string_lossy().to_string(),
+            collision,
+            ..StorageConfig::default()
+        });
+        (
+            DedupStorage::new(Arc::new(storage), Arc::clone(&blobs)),
+            blobs,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_identical_bytes_stored_once() {
+        let output = test_util::temp_output("blob", "hardlink");
+        let (storage, blobs) = dedup(&output, LinkMode::Hardlink, CollisionPolicy::Overwrite);
+        let logo = b"wikipedia-logo".to_vec();
+
+        for (index, page) in [
+            "https://en.wikipedia.org/wiki/NoSQL",
+            "https://en.wikipedia.org/wiki/SQL",
+        ]
+        .iter()
+        .enumerate()
+        {
+            storage
+                .persist_with_context(
+                    &test_util::context("wikipedia", page, index + 1),
+                    &image("https://upload.example.org/logo.png"),
+                    logo.clone(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let hash = sha256_hex(&logo);
+        assert_eq!(blobs.refcount(&hash).await.unwrap(), 2);
+        let manifest = blobs.manifest().await.unwrap();
+        assert_eq!(manifest.len(), 2);
+        assert!(manifest.iter().all(|(_, linked)| *linked == hash));
+        for (path, _) in &manifest {
+            assert_eq!(std::fs::read(path).unwrap(), logo);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+                assert_eq!(
+                    std::fs::metadata(path).unwrap().ino(),
+                    std::fs::metadata(blobs.blob_path(&hash)).unwrap().ino()
+                );
+            }
+        }
+
+        // the blob goes with its last path
+        blobs.release(&manifest[0].0).await.unwrap();
+        assert_eq!(blobs.refcount(&hash).await.unwrap(), 1);
+        assert!(!manifest[0].0.exists());
+        blobs.release(&manifest[1].0).await.unwrap();
+        assert_eq!(blobs.refcount(&hash).await.unwrap(), 0);
+        assert!(!blobs.blob_path(&hash).exists());
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_manifest_mode_and_relinking() {
+        let output = test_util::temp_output("blob", "manifest");
+        let (storage, blobs) = dedup(&output, LinkMode::Manifest, CollisionPolicy::Overwrite);
+        let page = "https://en.wikipedia.org/wiki/NoSQL";
+
+        let path = storage
+            .persist_with_context(
+                &test_util::context("wikipedia", page, 1),
+                &image("https://upload.example.org/a.png"),
+                b"first".to_vec(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        // no file, the manifest tells where the content is
+        assert!(!path.exists());
+        let blob = blobs.resolve(&path).await.unwrap().unwrap();
+        assert_eq!(std::fs::read(&blob).unwrap(), b"first");
+
+        // the same path with other bytes releases the previous blob
+        storage
+            .persist_with_context(
+                &test_util::context("wikipedia", page, 1),
+                &image("https://upload.example.org/a.png"),
+                b"second".to_vec(),
+            )
+            .await
+            .unwrap();
+        assert!(!blob.exists());
+        assert_eq!(blobs.refcount(&sha256_hex(b"first")).await.unwrap(), 0);
+        assert_eq!(blobs.refcount(&sha256_hex(b"second")).await.unwrap(), 1);
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_suffixed_file_deduplicated() {
+        let output = test_util::temp_output("blob", "suffix");
+        let (storage, blobs) = dedup(&output, LinkMode::Hardlink, CollisionPolicy::Suffix);
+        let page = "https://en.wikipedia.org/wiki/NoSQL";
+        let logo = b"wikipedia-logo".to_vec();
+
+        let mut written = Vec::new();
+        for _ in 0..2 {
+            written.push(
+                storage
+                    .persist_with_context(
+                        &test_util::context("wikipedia", page, 1),
+                        &image("https://upload.example.org/logo.png"),
+                        logo.clone(),
+                    )
+                    .await
+                    .unwrap()
+                    .unwrap(),
+            );
+        }
+
+        // the re-scrape is written next to the first file, and deduplicated as well
+        assert_ne!(written[0], written[1]);
+        assert!(written[1].to_string_lossy().ends_with("_1.png"));
+        let hash = sha256_hex(&logo);
+        assert_eq!(blobs.refcount(&hash).await.unwrap(), 2);
+        let linked: Vec<PathBuf> = blobs
+            .manifest()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert!(written.iter().all(|path| linked.contains(path)));
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_skipped_file_left_alone() {
+        let output = test_util::temp_output("blob", "skip");
+        let (storage, blobs) = dedup(&output, LinkMode::Hardlink, CollisionPolicy::Skip);
+        let page = "https://en.wikipedia.org/wiki/NoSQL";
+
+        let path = storage
+            .persist_with_context(
+                &test_util::context("wikipedia", page, 1),
+                &image("https://upload.example.org/logo.png"),
+                b"first".to_vec(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let skipped = storage
+            .persist_with_context(
+                &test_util::context("wikipedia", page, 1),
+                &image("https://upload.example.org/logo.png"),
+                b"second".to_vec(),
+            )
+            .await
+            .unwrap();
+
+        // nothing written, the file keeps the first bytes and their blob
+        assert!(skipped.is_none());
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+        assert_eq!(blobs.refcount(&sha256_hex(b"first")).await.unwrap(), 1);
+        assert_eq!(blobs.refcount(&sha256_hex(b"second")).await.unwrap(), 0);
+        assert_eq!(blobs.manifest().await.unwrap().len(), 1);
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bundling_storage_left_alone() {
+        let output = test_util::temp_output("blob", "cbz");
+        let blobs = Arc::new(BlobStore::open(output.join(".blobs"), LinkMode::Hardlink).unwrap());
+        let cbz = CbzStorage::new(&StorageConfig {
+            output: output.to_string_lossy().to_string(),
+            ..StorageConfig::default()
+        });
+        let storage = DedupStorage::new(Arc::new(cbz), Arc::clone(&blobs));
+        let chapter = "https://manga.example.com/manga/x/chapter-1";
+
+        storage
+            .persist_with_context(
+                &test_util::context("wikipedia", chapter, 1),
+                &image("https://cdn.example.com/1.png"),
+                b"page".to_vec(),
+            )
+            .await
+            .unwrap();
+        storage
+            .finish(&test_util::context("wikipedia", chapter, 1))
+            .await
+            .unwrap();
+        storage
+            .persist_with_context(
+                &test_util::context("wikipedia", chapter, 1),
+                &image("https://cdn.example.com/1.png"),
+                b"page".to_vec(),
+            )
+            .await
+            .unwrap();
+
+        // the archive holds more than the item; nothing to deduplicate
+        assert!(blobs.manifest().await.unwrap().is_empty());
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+}
@@ -0,0 +1,484 @@
+use super::blob::sha256_hex;
+use super::epub::utc_timestamp;
+use crate::entity::{PatternMatchResult, PatternType, PersistContext};
+use crate::errors::SdkError;
+use crate::traits::Storage;
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, Row};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_url TEXT NOT NULL,
+    page_url TEXT NOT NULL,
+    domain_key TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    pattern_type TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    byte_size INTEGER NOT NULL,
+    mime_type TEXT NOT NULL,
+    local_path TEXT,
+    persisted_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS items_domain_key ON items (domain_key);
+CREATE INDEX IF NOT EXISTS items_source_url ON items (source_url);
+CREATE INDEX IF NOT EXISTS items_page_url ON items (page_url);
+";
+
+const COLUMNS: &str = "source_url, page_url, domain_key, pattern, pattern_type, content_hash, byte_size, mime_type, local_path, persisted_at";
+
+/// A persisted item, as recorded in the [`Catalog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogEntry {
+    /// url of the item itself, e.g. the image url of a `Resource`; the page url for text
+    pub source_url: String,
+    /// url of the page the item was found on
+    pub page_url: String,
+    pub domain_key: String,
+    pub pattern: String,
+    /// `PatternType` of the item, e.g. `Resource`
+    pub pattern_type: String,
+    /// SHA-256 of the content, hex encoded
+    pub content_hash: String,
+    pub byte_size: u64,
+    pub mime_type: String,
+    /// where the storage wrote the item, if on the local file system
+    pub local_path: Option<String>,
+    /// UTC timestamp, e.g. `2026-10-18T10:05:46Z`
+    pub persisted_at: String,
+}
+
+/// Figures of the whole [`Catalog`], with a breakdown per domain key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CatalogStats {
+    pub items: u64,
+    pub bytes: u64,
+    /// ordered by domain key
+    pub domains: Vec<DomainStats>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DomainStats {
+    pub domain_key: String,
+    pub items: u64,
+    pub bytes: u64,
+    /// distinct pages the items were found on
+    pub pages: u64,
+    pub last_persisted_at: String,
+}
+
+/// SQLite database recording every item persisted through a [`CatalogStorage`].
+#[derive(Debug)]
+pub struct Catalog {
+    connection: Mutex<Connection>,
+}
+
+impl Catalog {
+    /// Opens (or creates) the catalog database at the given path, its folder included.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SdkError> {
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::with_connection(Connection::open(path).map_err(catalog_error)?)
+    }
+
+    /// Opens a throwaway catalog, e.g. for testing.
+    pub fn open_in_memory() -> Result<Self, SdkError> {
+        Self::with_connection(Connection::open_in_memory().map_err(catalog_error)?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, SdkError> {
+        connection.execute_batch(SCHEMA).map_err(catalog_error)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    pub fn record(&self, entry: &CatalogEntry) -> Result<(), SdkError> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                &format!(
+                    "INSERT INTO items ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    COLUMNS
+                ),
+                params![
+                    entry.source_url,
+                    entry.page_url,
+                    entry.domain_key,
+                    entry.pattern,
+                    entry.pattern_type,
+                    entry.content_hash,
+                    entry.byte_size as i64,
+                    entry.mime_type,
+                    entry.local_path,
+                    entry.persisted_at,
+                ],
+            )
+            .map_err(catalog_error)?;
+        Ok(())
+    }
+
+    /// Every item of the given plugin, in the order they were persisted.
+    pub fn list_by_domain(&self, domain_key: &str) -> Result<Vec<CatalogEntry>, SdkError> {
+        self.query(
+            &format!(
+                "SELECT {} FROM items WHERE domain_key = ?1 ORDER BY id",
+                COLUMNS
+            ),
+            domain_key,
+        )
+    }
+
+    /// Every item with the given url, either as its own url or as the page it was found on.
+    pub fn find_by_url(&self, url: &str) -> Result<Vec<CatalogEntry>, SdkError> {
+        self.query(
+            &format!(
+                "SELECT {} FROM items WHERE source_url = ?1 OR page_url = ?1 ORDER BY id",
+                COLUMNS
+            ),
+            url,
+        )
+    }
+
+    pub fn stats(&self) -> Result<CatalogStats, SdkError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(
+                "SELECT domain_key, COUNT(*), COALESCE(SUM(byte_size), 0), COUNT(DISTINCT page_url), MAX(persisted_at)
+                 FROM items GROUP BY domain_key ORDER BY domain_key",
+            )
+            .map_err(catalog_error)?;
+        let domains = statement
+            .query_map([], |row| {
+                Ok(DomainStats {
+                    domain_key: row.get(0)?,
+                    items: row.get::<_, i64>(1)? as u64,
+                    bytes: row.get::<_, i64>(2)? as u64,
+                    pages: row.get::<_, i64>(3)? as u64,
+                    last_persisted_at: row.get(4)?,
+                })
+            })
+            .map_err(catalog_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(catalog_error)?;
+
+        Ok(CatalogStats {
+            items: domains.iter().map(|domain| domain.items).sum(),
+            bytes: domains.iter().map(|domain| domain.bytes).sum(),
+            domains,
+        })
+    }
+
+    fn query(&self, sql: &str, parameter: &str) -> Result<Vec<CatalogEntry>, SdkError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(sql).map_err(catalog_error)?;
+        let entries = statement
+            .query_map([parameter], entry_from_row)
+            .map_err(catalog_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(catalog_error)?;
+        Ok(entries)
+    }
+}
+
+fn entry_from_row(row: &Row<'_>) -> rusqlite::Result<CatalogEntry> {
+    Ok(CatalogEntry {
+        source_url: row.get(0)?,
+        page_url: row.get(1)?,
+        domain_key: row.get(2)?,
+        pattern: row.get(3)?,
+        pattern_type: row.get(4)?,
+        content_hash: row.get(5)?,
+        byte_size: row.get::<_, i64>(6)? as u64,
+        mime_type: row.get(7)?,
+        local_path: row.get(8)?,
+        persisted_at: row.get(9)?,
+    })
+}
+
+fn catalog_error(e: rusqlite::Error) -> SdkError {
+    SdkError::Storage(std::io::Error::other(format!("catalog - {}", e)))
+}
+
+/// Storage decorator recording every item the wrapped storage persisted into a [`Catalog`].
+///
+/// The item is recorded once the wrapped storage persisted it, along with the file it was written
+/// to (as returned by [`Storage::persist_with_context`]). The SQLite insert is blocking, so it runs on tokio's blocking
+/// pool rather than on the async worker.
+pub struct CatalogStorage {
+    inner: Arc<dyn Storage + Send + Sync>,
+    catalog: Arc<Catalog>,
+}
+
+impl CatalogStorage {
+    pub fn new(inner: Arc<dyn Storage + Send + Sync>, catalog: Arc<Catalog>) -> Self {
+        Self { inner, catalog }
+    }
+
+    fn entry(
+        &self,
+        context: &PersistContext,
+        resource: &PatternMatchResult,
+        content: &[u8],
+    ) -> CatalogEntry {
+        let source_url = match resource.pattern_type {
+            PatternType::Resource | PatternType::Content => resource
+                .resource_string
+                .clone()
+                .unwrap_or_else(|| context.page_url.clone()),
+            // the resource string is the content itself
+            _ => context.page_url.clone(),
+        };
+        CatalogEntry {
+            mime_type: mime_type(context, &source_url, content),
+            source_url,
+            page_url: context.page_url.clone(),
+            domain_key: context.domain_key.clone(),
+            pattern: resource.pattern.clone(),
+            pattern_type: format!("{:?}", resource.pattern_type),
+            content_hash: sha256_hex(content),
+            byte_size: content.len() as u64,
+            local_path: None,
+            persisted_at: utc_timestamp(SystemTime::now()),
+        }
+    }
+
+    async fn record(&self, entry: CatalogEntry) -> Result<(), SdkError> {
+        let catalog = Arc::clone(&self.catalog);
+        tokio::task::spawn_blocking(move || catalog.record(&entry))
+            .await
+            .map_err(|e| SdkError::Storage(std::io::Error::other(format!("catalog - {}", e))))?
+    }
+}
+
+#[async_trait]
+impl Storage for CatalogStorage {
+    async fn persist(
+        &self,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<(), SdkError> {
+        let entry = self.entry(&PersistContext::default(), resource, &resource_content);
+        self.inner.persist(resource, resource_content).await?;
+        self.record(entry).await
+    }
+
+    async fn persist_with_context(
+        &self,
+        context: &PersistContext,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<Option<PathBuf>, SdkError> {
+        let mut entry = self.entry(context, resource, &resource_content);
+        let written = self
+            .inner
+            .persist_with_context(context, resource, resource_content)
+            .await?;
+        entry.local_path = written
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string());
+        self.record(entry).await?;
+        Ok(written)
+    }
+
+    async fn finish(&self, context: &PersistContext) -> Result<(), SdkError> {
+        self.inner.finish(context).await
+    }
+}
+
+/// media type of the content: the one given by the engine, else sniffed from the content or the
+/// url's extension
+fn mime_type(context: &PersistContext, url: &str, content: &[u8]) -> String {
+    if let Some(content_type) = &context.content_type {
+        return content_type.clone();
+    }
+    let sniffed = match content {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'%', b'P', b'D', b'F', ..] => Some("application/pdf"),
+        _ => None,
+    };
+    if let Some(sniffed) = sniffed {
+        return sniffed.to_string();
+    }
+    let extension = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|url| url.rsplit('/').next())
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("avif") => "image/avif",
+        Some("pdf") => "application/pdf",
+        Some("html" | "htm") => "text/html",
+        Some("txt") => "text/plain",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::entity::{CollisionPolicy, StorageConfig};
+    use crate::storage::test_util;
+    use crate::storage::FileSystemStorage;
+
+    fn context(domain_key: &str, page_url: &str, content_type: Option<&str>) -> PersistContext {
+        PersistContext {
+            content_type: content_type.map(str::to_string),
+            ..test_util::context(domain_key, page_url, 1)
+        }
+    }
+
+    fn item(pattern_type: PatternType, resource_string: &str) -> PatternMatchResult {
+        test_util::item(pattern_type, Some(resource_string))
+    }
+
+    #[tokio::test]
+    async fn test_catalog_storage_records_items() {
+        let output = test_util::temp_output("catalog", "items");
+        let catalog = Arc::new(Catalog::open(output.join("catalog.db")).unwrap());
+        let storage = CatalogStorage::new(
+            Arc::new(FileSystemStorage::new(&StorageConfig {
+                output: output.to_string_lossy().to_string(),
+                ..StorageConfig::default()
+            })),
+            Arc::clone(&catalog),
+        );
+
+        let page = "https://en.wikipedia.org/wiki/NoSQL";
+        storage
+            .persist_with_context(
+                &context("wikipedia", page, None),
+                &item(PatternType::Resource, "https://upload.example.org/db.svg"),
+                b"<svg/>".to_vec(),
+            )
+            .await
+            .unwrap();
+        storage
+            .persist_with_context(
+                &context("wikipedia", page, Some("text/plain; charset=utf-8")),
+                &item(PatternType::Content, page),
+                b"abc".to_vec(),
+            )
+            .await
+            .unwrap();
+        storage
+            .persist_with_context(
+                &context("manga", "https://manga.example.com/chapter/1", None),
+                &item(PatternType::Resource, "https://cdn.example.com/1"),
+                vec![0xFF, 0xD8, 0xFF, 0xE0],
+            )
+            .await
+            .unwrap();
+
+        let entries = catalog.list_by_domain("wikipedia").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].source_url, "https://upload.example.org/db.svg");
+        assert_eq!(entries[0].page_url, page);
+        assert_eq!(entries[0].pattern_type, "Resource");
+        assert_eq!(entries[0].mime_type, "image/svg+xml");
+        assert_eq!(entries[0].byte_size, 6);
+        let local_path = entries[0].local_path.clone().unwrap();
+        assert!(local_path.ends_with("0001_db.svg"));
+        assert!(Path::new(&local_path).exists());
+        assert_eq!(entries[1].pattern_type, "Content");
+        assert_eq!(entries[1].mime_type, "text/plain; charset=utf-8");
+        // sha-256 of "abc"
+        assert_eq!(
+            entries[1].content_hash,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        // as the item's own url or its page's
+        assert_eq!(catalog.find_by_url(page).unwrap().len(), 2);
+        let found = catalog.find_by_url("https://cdn.example.com/1").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].mime_type, "image/jpeg");
+
+        let stats = catalog.stats().unwrap();
+        assert_eq!(stats.items, 3);
+        assert_eq!(stats.bytes, 13);
+        assert_eq!(
+            stats
+                .domains
+                .iter()
+                .map(|domain| (domain.domain_key.as_str(), domain.items, domain.pages))
+                .collect::<Vec<_>>(),
+            vec![("manga", 1, 1), ("wikipedia", 2, 1)]
+        );
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_catalog_storage_records_written_path() {
+        let page = "https://en.wikipedia.org/wiki/NoSQL";
+        for (collision, name) in [
+            (CollisionPolicy::Suffix, "suffix"),
+            (CollisionPolicy::Skip, "skip"),
+        ] {
+            let output = test_util::temp_output("catalog", name);
+            let catalog = Arc::new(Catalog::open(output.join("catalog.db")).unwrap());
+            let storage = CatalogStorage::new(
+                Arc::new(FileSystemStorage::new(&StorageConfig {
+                    output: output.to_string_lossy().to_string(),
+                    collision,
+                    ..StorageConfig::default()
+                })),
+                Arc::clone(&catalog),
+            );
+            for content in ["first", "second"] {
+                storage
+                    .persist_with_context(
+                        &context("wikipedia", page, None),
+                        &item(PatternType::Resource, "https://upload.example.org/db.svg"),
+                        content.as_bytes().to_vec(),
+                    )
+                    .await
+                    .unwrap();
+            }
+
+            let entries = catalog.list_by_domain("wikipedia").unwrap();
+            assert_eq!(entries.len(), 2);
+            let first = entries[0].local_path.clone().unwrap();
+            assert_eq!(std::fs::read(&first).unwrap(), b"first");
+            match collision {
+                // the re-scrape went next to the first file
+                CollisionPolicy::Suffix => {
+                    let second = entries[1].local_path.clone().unwrap();
+                    assert!(second.ends_with("0001_db_1.svg"));
+                    assert_eq!(std::fs::read(&second).unwrap(), b"second");
+                }
+                // nothing written, no file holds the second bytes
+                _ => assert_eq!(entries[1].local_path, None),
+            }
+            std::fs::remove_dir_all(&output).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_catalog_empty_stats() {
+        let catalog = Catalog::open_in_memory().unwrap();
+        assert_eq!(catalog.stats().unwrap(), CatalogStats::default());
+        assert!(catalog.list_by_domain("wikipedia").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,488 @@
+use super::file_system::{page_slug, resolve_collision, sanitize_file_name, write_atomic};
+use crate::entity::{
+    CollisionPolicy, PatternMatchResult, PatternType, PersistContext, StorageConfig,
+};
+use crate::errors::SdkError;
+use crate::traits::Storage;
+
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use url::Url;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// path segments telling where the chapter is rather than which series it belongs to
+const NON_SERIES_SEGMENTS: [&str; 12] = [
+    "chapter", "chapters", "ch", "c", "episode", "ep", "read", "viewer", "manga", "comic",
+    "comics", "title",
+];
+
+/// query parameters holding the chapter number, e.g. `?chapter=3`
+const CHAPTER_PARAMETERS: [&str; 4] = ["chapter", "ch", "c", "episode"];
+
+/// Storage packing the images of a chapter into a comic book archive (`.cbz`, a zip file).
+///
+/// A chapter is everything persisted during one run (the pages reached through pagination
+/// included); its `Resource` items become the pages, in the order the engine persisted them
+/// (i.e. the order of the `Resource` matches). The archive is written on [`Storage::finish`] to
+/// `{output}/{domain_key}/{page_slug}.cbz`, pages named by their zero-padded position
+/// (`001.jpg`, `002.png`, ...) so every reader sorts them alike.
+///
+/// The archive comes with a `ComicInfo.xml` (the metadata format of ComicRack, read by most
+/// readers) holding the series, chapter number, title, page count and source url:
+/// * the series and chapter number are taken from the url the run started from,
+///   e.g. `one piece` and `1045` for `https://example.com/manga/one-piece/chapter-1045`
+/// * the title is the first line of the first `Content` item of the chapter (e.g. a plugin
+///   matching the chapter heading), `Chapter {number}` otherwise
+///
+/// Any other item is left out of the archive.
+#[derive(Debug)]
+pub struct CbzStorage {
+    output: PathBuf,
+    collision: CollisionPolicy,
+    /// chapters being collected, keyed by domain key and url the run started from
+    chapters: Mutex<HashMap<(String, String), Chapter>>,
+    /// running index of the items persisted without context
+    index: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct Chapter {
+    title: Option<String>,
+    pages: Vec<Page>,
+}
+
+#[derive(Debug)]
+struct Page {
+    /// position of the item within the run
+    index: usize,
+    extension: &'static str,
+    content: Vec<u8>,
+}
+
+/// Metadata of a chapter, written as `ComicInfo.xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComicInfo {
+    pub series: String,
+    pub number: Option<String>,
+    pub title: String,
+    pub page_count: usize,
+    pub web: String,
+}
+
+impl CbzStorage {
+    /// Only `output` and `collision` of the config apply; the archive path is not configurable.
+    pub fn new(config: &StorageConfig) -> Self {
+        Self {
+            output: PathBuf::from(&config.output),
+            collision: config.collision,
+            chapters: Mutex::new(HashMap::new()),
+            index: AtomicUsize::new(0),
+        }
+    }
+
+    /// Resolves the path the archive of the given run is written to (before applying the
+    /// collision policy).
+    pub fn archive_path(&self, context: &PersistContext) -> PathBuf {
+        self.output
+            .join(sanitize_file_name(&context.domain_key, "default"))
+            .join(format!("{}.cbz", page_slug(&context.scrap_url)))
+    }
+}
+
+#[async_trait]
+impl Storage for CbzStorage {
+    /// Collects the item into an unnamed chapter, archived on `finish(&PersistContext::default())`.
+    async fn persist(
+        &self,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<(), SdkError> {
+        let context = PersistContext {
+            index: self.index.fetch_add(1, Ordering::SeqCst) + 1,
+            ..PersistContext::default()
+        };
+        self.persist_with_context(&context, resource, resource_content)
+            .await?;
+        Ok(())
+    }
+
+    async fn persist_with_context(
+        &self,
+        context: &PersistContext,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<Option<PathBuf>, SdkError> {
+        let mut chapters = self.chapters.lock().unwrap();
+        let chapter = chapters
+            .entry((context.domain_key.clone(), context.scrap_url.clone()))
+            .or_default();
+        match resource.pattern_type {
+            PatternType::Resource => chapter.pages.push(Page {
+                index: context.index,
+                extension: image_extension(resource, &resource_content),
+                content: resource_content,
+            }),
+            PatternType::Content if chapter.title.is_none() => {
+                chapter.title = String::from_utf8_lossy(&resource_content)
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(str::to_string);
+            }
+            _ => tracing::debug!(
+                "not a chapter page, left out of the archive: {:?}",
+                resource
+            ),
+        }
+        // bundled into the archive on `finish`
+        Ok(None)
+    }
+
+    async fn finish(&self, context: &PersistContext) -> Result<(), SdkError> {
+        let chapter = self
+            .chapters
+            .lock()
+            .unwrap()
+            .remove(&(context.domain_key.clone(), context.scrap_url.clone()));
+        let Some(mut chapter) = chapter.filter(|chapter| !chapter.pages.is_empty()) else {
+            tracing::debug!("no pages for {}, no archive written", context.scrap_url);
+            return Ok(());
+        };
+        chapter.pages.sort_by_key(|page| page.index);
+
+        let mut info = ComicInfo::from_url(&context.scrap_url);
+        info.page_count = chapter.pages.len();
+        if let Some(title) = chapter.title.take() {
+            info.title = title;
+        }
+        let archive = write_archive(&info, &chapter.pages)?;
+
+        let path = self.archive_path(context);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let Some(path) = resolve_collision(path, self.collision).await? else {
+            return Ok(());
+        };
+        write_atomic(&path, &archive).await?;
+        tracing::debug!(
+            "archived {} pages of {} to {}",
+            info.page_count,
+            context.scrap_url,
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+impl ComicInfo {
+    /// Guesses the series and chapter number from the url of a chapter; the title defaults to
+    /// `Chapter {number}` (or the page slug without number) and the page count to 0.
+    pub fn from_url(url: &str) -> Self {
+        let parsed = Url::parse(url).ok();
+        let segments: Vec<String> = parsed
+            .as_ref()
+            .and_then(|url| url.path_segments())
+            .map(|segments| {
+                segments
+                    .filter(|segment| !segment.is_empty())
+                    .map(|segment| percent_decode_str(segment).decode_utf8_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // the chapter number comes from the query (`?chapter=3`) or the last segment with digits
+        let from_query = parsed.as_ref().and_then(|url| {
+            url.query_pairs()
+                .find(|(key, _)| CHAPTER_PARAMETERS.contains(&key.to_lowercase().as_str()))
+                .and_then(|(_, value)| last_number(&value))
+        });
+        let (number, number_segment) = match from_query {
+            Some(number) => (Some(number), None),
+            None => segments
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(position, segment)| {
+                    last_number(segment).map(|number| (Some(number), Some(position)))
+                })
+                .unwrap_or((None, None)),
+        };
+
+        let series = segments
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(position, _)| Some(*position) != number_segment)
+            .map(|(_, segment)| segment)
+            .find(|segment| is_series_segment(segment))
+            .map(|segment| segment.replace(['-', '_', '+'], " ").trim().to_string())
+            .or_else(|| {
+                parsed
+                    .as_ref()
+                    .and_then(|url| url.host_str().map(str::to_string))
+            })
+            .unwrap_or_default();
+
+        let title = match &number {
+            Some(number) => format!("Chapter {}", number),
+            None => page_slug(url),
+        };
+        Self {
+            series,
+            number,
+            title,
+            page_count: 0,
+            web: url.to_string(),
+        }
+    }
+
+    /// Renders the `ComicInfo.xml` document.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<ComicInfo xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n",
+        );
+        xml.push_str(&format!("  <Title>{}</Title>\n", escape_xml(&self.title)));
+        xml.push_str(&format!(
+            "  <Series>{}</Series>\n",
+            escape_xml(&self.series)
+        ));
+        if let Some(number) = &self.number {
+            xml.push_str(&format!("  <Number>{}</Number>\n", escape_xml(number)));
+        }
+        xml.push_str(&format!("  <Web>{}</Web>\n", escape_xml(&self.web)));
+        xml.push_str(&format!("  <PageCount>{}</PageCount>\n", self.page_count));
+        xml.push_str("  <Manga>Yes</Manga>\n");
+        xml.push_str("</ComicInfo>\n");
+        xml
+    }
+}
+
+/// Builds the archive in memory: the pages first (stored as-is, images are compressed already),
+/// then the metadata.
+fn write_archive(info: &ComicInfo, pages: &[Page]) -> Result<Vec<u8>, SdkError> {
+    let width = pages.len().to_string().len().max(3);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let written: Result<(), zip::result::ZipError> = (|| {
+        for (position, page) in pages.iter().enumerate() {
+            writer.start_file(
+                format!("{:0width$}.{}", position + 1, page.extension, width = width),
+                stored,
+            )?;
+            writer.write_all(&page.content)?;
+        }
+        writer.start_file("ComicInfo.xml", deflated)?;
+        writer.write_all(info.to_xml().as_bytes())?;
+        Ok(())
+    })();
+    written
+        .and_then(|_| writer.finish())
+        .map(|cursor| cursor.into_inner())
+        .map_err(|e| SdkError::Other(format!("failed to write the cbz archive: {}", e)))
+}
+
+/// extension of a page; sniffed from the image itself, the url's extension otherwise
+pub(super) fn image_extension(resource: &PatternMatchResult, content: &[u8]) -> &'static str {
+    match content {
+        [0xFF, 0xD8, 0xFF, ..] => return "jpg",
+        [0x89, b'P', b'N', b'G', ..] => return "png",
+        [b'G', b'I', b'F', b'8', ..] => return "gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => return "webp",
+        _ => {}
+    }
+    let extension = resource
+        .resource_string
+        .as_deref()
+        .and_then(|url| url.split(['?', '#']).next())
+        .and_then(|url| url.rsplit('/').next())
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("jpg" | "jpeg") => "jpg",
+        Some("png") => "png",
+        Some("gif") => "gif",
+        Some("webp") => "webp",
+        Some("avif") => "avif",
+        Some("bmp") => "bmp",
+        _ => "jpg",
+    }
+}
+
+/// last number of the segment, decimals included, e.g. `10.5` for `chapter-10.5`
+fn last_number(segment: &str) -> Option<String> {
+    let end = segment.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = segment[..end]
+        .rfind(|c: char| !c.is_ascii_digit() && c != '.')
+        .map(|start| start + 1)
+        .unwrap_or(0);
+    let number = segment[start..end].trim_start_matches('.');
+    (!number.is_empty()).then(|| number.to_string())
+}
+
+fn is_series_segment(segment: &str) -> bool {
+    segment.chars().any(char::is_alphabetic)
+        && !NON_SERIES_SEGMENTS
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(segment))
+        && !segment
+            .to_lowercase()
+            .strip_prefix("chapter")
+            .is_some_and(|rest| rest.chars().all(|c| !c.is_alphabetic()))
+}
+
+pub(super) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::storage::test_util::{self, image};
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    const SCRAP_URL: &str = "https://manga.example.com/manga/one-piece/chapter-1045";
+
+    fn storage(output: &std::path::Path) -> CbzStorage {
+        CbzStorage::new(&StorageConfig {
+            output: output.to_string_lossy().to_string(),
+            ..StorageConfig::default()
+        })
+    }
+
+    fn context(index: usize) -> PersistContext {
+        test_util::context("manga", SCRAP_URL, index)
+    }
+
+    #[test]
+    fn test_comic_info_from_url() {
+        let info = ComicInfo::from_url(SCRAP_URL);
+        assert_eq!(info.series, "one piece");
+        assert_eq!(info.number.as_deref(), Some("1045"));
+        assert_eq!(info.title, "Chapter 1045");
+        assert_eq!(info.web, SCRAP_URL);
+
+        let info = ComicInfo::from_url("https://example.com/title/berserk/chapter/10.5");
+        assert_eq!(info.series, "berserk");
+        assert_eq!(info.number.as_deref(), Some("10.5"));
+
+        let info = ComicInfo::from_url("https://example.com/viewer?series=x&chapter=3");
+        assert_eq!(info.series, "example.com");
+        assert_eq!(info.number.as_deref(), Some("3"));
+
+        let info = ComicInfo::from_url("https://example.com/read/solo_leveling");
+        assert_eq!(info.series, "solo leveling");
+        assert_eq!(info.number, None);
+        assert_eq!(info.title, "solo_leveling");
+    }
+
+    #[test]
+    fn test_comic_info_xml() {
+        let info = ComicInfo {
+            series: "Tom & Jerry".to_string(),
+            number: Some("3".to_string()),
+            title: "<Chase>".to_string(),
+            page_count: 12,
+            web: "https://example.com/?a=1&b=2".to_string(),
+        };
+        let xml = info.to_xml();
+        assert!(xml.contains("<Series>Tom &amp; Jerry</Series>"));
+        assert!(xml.contains("<Number>3</Number>"));
+        assert!(xml.contains("<Title>&lt;Chase&gt;</Title>"));
+        assert!(xml.contains("<PageCount>12</PageCount>"));
+        assert!(xml.contains("<Web>https://example.com/?a=1&amp;b=2</Web>"));
+    }
+
+    #[tokio::test]
+    async fn test_finish_writes_archive() {
+        let output = test_util::temp_output("cbz", "archive");
+        let storage = storage(&output);
+
+        // persisted out of order (e.g. concurrent downloads); the run index wins
+        storage
+            .persist_with_context(
+                &context(3),
+                &image("https://cdn.example.com/p/3"),
+                b"GIF89a-3".to_vec(),
+            )
+            .await
+            .unwrap();
+        storage
+            .persist_with_context(
+                &context(1),
+                &image("https://cdn.example.com/p/1.jpeg"),
+                b"page-1".to_vec(),
+            )
+            .await
+            .unwrap();
+        let heading = PatternMatchResult {
+            pattern: "h1".to_string(),
+            pattern_type: PatternType::Content,
+            resource_string: None,
+        };
+        storage
+            .persist_with_context(&context(2), &heading, b"\n  The Final Battle \n".to_vec())
+            .await
+            .unwrap();
+        storage
+            .persist_with_context(
+                &context(4),
+                &image("https://cdn.example.com/p/4.png?w=800"),
+                b"page-4".to_vec(),
+            )
+            .await
+            .unwrap();
+        // nothing written until the run is over
+        assert!(!output.exists());
+
+        storage.finish(&context(4)).await.unwrap();
+        let path = output.join("manga").join("chapter-1045.cbz");
+        let mut archive = ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(
+            sorted,
+            vec!["001.jpg", "002.gif", "003.png", "ComicInfo.xml"]
+        );
+
+        let mut first = String::new();
+        archive
+            .by_name("001.jpg")
+            .unwrap()
+            .read_to_string(&mut first)
+            .unwrap();
+        assert_eq!(first, "page-1");
+
+        let mut xml = String::new();
+        archive
+            .by_name("ComicInfo.xml")
+            .unwrap()
+            .read_to_string(&mut xml)
+            .unwrap();
+        assert!(xml.contains("<Title>The Final Battle</Title>"));
+        assert!(xml.contains("<Series>one piece</Series>"));
+        assert!(xml.contains("<Number>1045</Number>"));
+        assert!(xml.contains("<PageCount>3</PageCount>"));
+        assert!(xml.contains(&format!("<Web>{}</Web>", SCRAP_URL)));
+
+        // the chapter is done with; finishing again writes nothing more
+        storage.finish(&context(4)).await.unwrap();
+        assert_eq!(std::fs::read_dir(output.join("manga")).unwrap().count(), 1);
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+}
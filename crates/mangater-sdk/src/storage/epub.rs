@@ -0,0 +1,723 @@
+use super::cbz::{escape_xml, image_extension};
+use super::file_system::{page_slug, resolve_collision, sanitize_file_name, write_atomic};
+use crate::entity::{
+    CollisionPolicy, PatternMatchResult, PatternType, PersistContext, StorageConfig,
+};
+use crate::errors::SdkError;
+use crate::traits::Storage;
+
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Storage packaging the extracted text and images of articles into EPUB 3 books.
+///
+/// An article is everything persisted during one run: its `Content` / `ScrapedContent` items make
+/// up the text (plain-text or Markdown, see `ContentConfig::format`), its `Resource` items are
+/// the images. The book is written on [`Storage::finish`]:
+/// * to `{output}/{domain_key}/{page_slug}.epub`, one book per article, or
+/// * to `{output}/{book}.epub` if `StorageConfig::book` is set; every article finished by the
+///   storage so far is bundled into it, one chapter per article (the book is rewritten as the
+///   articles come in)
+///
+/// Every book comes with a navigation document (one entry per chapter) and its metadata: title,
+/// source url(s), language (`StorageConfig::language`) and retrieval date. Markdown headings
+/// become XHTML headings; Markdown images (`![alt](url)`) are re-linked to the local copy of the
+/// downloaded image, the images not referenced by the text are appended to the chapter.
+#[derive(Debug)]
+pub struct EpubStorage {
+    output: PathBuf,
+    collision: CollisionPolicy,
+    book: Option<String>,
+    language: String,
+    /// articles being collected, keyed by domain key and url the run started from
+    articles: Mutex<HashMap<(String, String), Article>>,
+    /// the bundled book: its finished articles and the path it is written to
+    bundle: tokio::sync::Mutex<(Vec<Article>, Option<PathBuf>)>,
+    /// running index of the items persisted without context
+    index: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Article {
+    source: String,
+    title: String,
+    retrieved: SystemTime,
+    texts: Vec<(usize, String)>,
+    images: Vec<Image>,
+}
+
+#[derive(Debug)]
+struct Image {
+    /// position of the item within the run
+    index: usize,
+    url: Option<String>,
+    extension: &'static str,
+    content: Vec<u8>,
+}
+
+impl EpubStorage {
+    pub fn new(config: &StorageConfig) -> Self {
+        Self {
+            output: PathBuf::from(&config.output),
+            collision: config.collision,
+            book: config.book.clone(),
+            language: config.language.clone(),
+            articles: Mutex::new(HashMap::new()),
+            bundle: tokio::sync::Mutex::new((Vec::new(), None)),
+            index: AtomicUsize::new(0),
+        }
+    }
+
+    /// Resolves the path the book of the given run is written to (before applying the collision
+    /// policy).
+    pub fn book_path(&self, context: &PersistContext) -> PathBuf {
+        match &self.book {
+            Some(book) => self
+                .output
+                .join(format!("{}.epub", sanitize_file_name(book, "book"))),
+            None => self
+                .output
+                .join(sanitize_file_name(&context.domain_key, "default"))
+                .join(format!("{}.epub", page_slug(&context.scrap_url))),
+        }
+    }
+
+    async fn write_book(
+        &self,
+        path: PathBuf,
+        title: &str,
+        articles: &[Article],
+    ) -> Result<Option<PathBuf>, SdkError> {
+        let book = write_epub(title, &self.language, articles)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let Some(path) = resolve_collision(path, self.collision).await? else {
+            return Ok(None);
+        };
+        write_atomic(&path, &book).await?;
+        tracing::debug!("wrote {} article(s) to {}", articles.len(), path.display());
+        Ok(Some(path))
+    }
+}
+
+#[async_trait]
+impl Storage for EpubStorage {
+    /// Collects the item into an unnamed article, written on `finish(&PersistContext::default())`.
+    async fn persist(
+        &self,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<(), SdkError> {
+        let context = PersistContext {
+            index: self.index.fetch_add(1, Ordering::SeqCst) + 1,
+            ..PersistContext::default()
+        };
+        self.persist_with_context(&context, resource, resource_content)
+            .await?;
+        Ok(())
+    }
+
+    async fn persist_with_context(
+        &self,
+        context: &PersistContext,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<Option<PathBuf>, SdkError> {
+        let mut articles = self.articles.lock().unwrap();
+        let article = articles
+            .entry((context.domain_key.clone(), context.scrap_url.clone()))
+            .or_insert_with(|| Article::new(&context.scrap_url));
+        match resource.pattern_type {
+            PatternType::Resource => article.images.push(Image {
+                index: context.index,
+                url: resource.resource_string.clone(),
+                extension: image_extension(resource, &resource_content),
+                content: resource_content,
+            }),
+            PatternType::Content | PatternType::ScrapedContent => article.texts.push((
+                context.index,
+                String::from_utf8_lossy(&resource_content).to_string(),
+            )),
+            _ => tracing::debug!(
+                "not part of an article, left out of the book: {:?}",
+                resource
+            ),
+        }
+        // bundled into the book on `finish`
+        Ok(None)
+    }
+
+    async fn finish(&self, context: &PersistContext) -> Result<(), SdkError> {
+        let article = self
+            .articles
+            .lock()
+            .unwrap()
+            .remove(&(context.domain_key.clone(), context.scrap_url.clone()));
+        let Some(mut article) = article.filter(|article| !article.texts.is_empty()) else {
+            tracing::debug!("no content for {}, no book written", context.scrap_url);
+            return Ok(());
+        };
+        article.texts.sort_by_key(|(index, _)| *index);
+        article.images.sort_by_key(|image| image.index);
+        if let Some(heading) = markdown_title(&article.texts[0].1) {
+            article.title = heading;
+        }
+
+        let Some(book) = &self.book else {
+            let title = article.title.clone();
+            self.write_book(self.book_path(context), &title, &[article])
+                .await?;
+            return Ok(());
+        };
+
+        // the first write of the bundle settles its path; later ones replace it
+        let mut bundle = self.bundle.lock().await;
+        let (articles, path) = &mut *bundle;
+        articles.push(article);
+        let collision = path.is_none();
+        let target = path.clone().unwrap_or_else(|| self.book_path(context));
+        let written = if collision {
+            self.write_book(target, book, articles).await?
+        } else {
+            let book = write_epub(book, &self.language, articles)?;
+            write_atomic(&target, &book).await?;
+            Some(target)
+        };
+        if written.is_some() {
+            *path = written;
+        }
+        Ok(())
+    }
+}
+
+impl Article {
+    fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            title: title_from_url(source),
+            retrieved: SystemTime::now(),
+            texts: Vec::new(),
+            images: Vec::new(),
+        }
+    }
+
+    /// local path (within the book) of the `position`-th image of the `chapter`-th article
+    fn image_href(chapter: usize, position: usize, image: &Image) -> String {
+        format!("images/{:03}-{:03}.{}", chapter, position, image.extension)
+    }
+
+    /// Renders the article as the XHTML document of the `chapter`-th chapter.
+    fn to_xhtml(&self, chapter: usize, language: &str) -> String {
+        let local_images: HashMap<&str, String> = self
+            .images
+            .iter()
+            .enumerate()
+            .filter_map(|(position, image)| {
+                image
+                    .url
+                    .as_deref()
+                    .map(|url| (url, Self::image_href(chapter, position + 1, image)))
+            })
+            .collect();
+        let mut referenced = Vec::new();
+
+        let mut body = format!("<h1>{}</h1>\n", escape_xml(&self.title));
+        for (_, text) in &self.texts {
+            for block in text.split("\n\n").map(str::trim).filter(|b| !b.is_empty()) {
+                body.push_str(&render_block(
+                    block,
+                    &self.title,
+                    &local_images,
+                    &mut referenced,
+                ));
+            }
+        }
+        for (position, image) in self.images.iter().enumerate() {
+            let href = Self::image_href(chapter, position + 1, image);
+            if !referenced.contains(&href) {
+                body.push_str(&format!(
+                    "<figure><img src=\"{}\" alt=\"\"/></figure>\n",
+                    href
+                ));
+            }
+        }
+        body.push_str(&format!(
+            "<p class=\"source\">Source: <a href=\"{0}\">{0}</a>, retrieved {1}</p>\n",
+            escape_xml(&self.source),
+            &utc_timestamp(self.retrieved)[..10]
+        ));
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{0}\" xml:lang=\"{0}\">\n<head><title>{1}</title></head>\n<body>\n<section epub:type=\"chapter\">\n{2}</section>\n</body>\n</html>\n",
+            escape_xml(language),
+            escape_xml(&self.title),
+            body
+        )
+    }
+}
+
+/// Renders a paragraph of plain-text or Markdown; headings and standalone images are recognized,
+/// the rest is kept as text (line breaks included).
+fn render_block(
+    block: &str,
+    title: &str,
+    local_images: &HashMap<&str, String>,
+    referenced: &mut Vec<String>,
+) -> String {
+    if let Some((level, heading)) = markdown_heading(block) {
+        // the article title is the chapter heading already
+        if level == 1 && heading == title {
+            return String::new();
+        }
+        let level = (level + 1).min(6);
+        return format!("<h{0}>{1}</h{0}>\n", level, escape_xml(heading));
+    }
+
+    let mut html = String::new();
+    let mut lines = Vec::new();
+    for line in block.lines().map(str::trim) {
+        match markdown_image(line) {
+            Some((alt, url)) => {
+                if !lines.is_empty() {
+                    html.push_str(&format!("<p>{}</p>\n", lines.join("<br/>")));
+                    lines.clear();
+                }
+                let src = match local_images.get(url) {
+                    Some(href) => {
+                        referenced.push(href.clone());
+                        href.clone()
+                    }
+                    // not downloaded; linking it would reach out to the network
+                    None => {
+                        lines.push(escape_xml(alt));
+                        continue;
+                    }
+                };
+                html.push_str(&format!(
+                    "<figure><img src=\"{}\" alt=\"{}\"/></figure>\n",
+                    src,
+                    escape_xml(alt)
+                ));
+            }
+            None => lines.push(escape_xml(line)),
+        }
+    }
+    if !lines.is_empty() {
+        html.push_str(&format!("<p>{}</p>\n", lines.join("<br/>")));
+    }
+    html
+}
+
+/// `(level, text)` of a single line ATX heading, e.g. `(2, "History")` for `## History`
+fn markdown_heading(block: &str) -> Option<(usize, &str)> {
+    if block.contains('\n') {
+        return None;
+    }
+    let level = block.chars().take_while(|c| *c == '#').count();
+    let text = block[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then(|| (level, text.trim()))
+}
+
+/// `(alt, url)` of a line made of a single Markdown image, e.g. `![Cat](https://example.com/cat.jpg)`
+fn markdown_image(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix("![")?;
+    let (alt, rest) = rest.split_once("](")?;
+    let target = rest.strip_suffix(')')?;
+    // drop the optional title, e.g. `(url "title")`
+    let url = target.split_whitespace().next()?;
+    Some((alt, url))
+}
+
+/// the heading of the first line of the text, if Markdown
+fn markdown_title(text: &str) -> Option<String> {
+    let first = text.trim_start().lines().next()?;
+    markdown_heading(first)
+        .filter(|(level, _)| *level == 1)
+        .map(|(_, heading)| heading.to_string())
+}
+
+/// title of an article from its url, e.g. `Rust (programming language)` for
+/// `https://en.wikipedia.org/wiki/Rust_(programming_language)`
+fn title_from_url(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.path_segments()
+                .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+                .map(|segment| {
+                    percent_decode_str(segment)
+                        .decode_utf8_lossy()
+                        .replace('_', " ")
+                })
+                .or_else(|| url.host_str().map(str::to_string))
+        })
+        .unwrap_or_else(|| "Untitled".to_string())
+}
+
+fn media_type(extension: &str) -> &'static str {
+    match extension {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        _ => "image/jpeg",
+    }
+}
+
+/// Builds the book in memory; the `mimetype` entry comes first and uncompressed, as the
+/// EPUB Open Container Format requires.
+fn write_epub(title: &str, language: &str, articles: &[Article]) -> Result<Vec<u8>, SdkError> {
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let written: Result<(), zip::result::ZipError> = (|| {
+        writer.start_file("mimetype", stored)?;
+        writer.write_all(b"application/epub+zip")?;
+        writer.start_file("META-INF/container.xml", deflated)?;
+        writer.write_all(CONTAINER_XML.as_bytes())?;
+        writer.start_file("OEBPS/content.opf", deflated)?;
+        writer.write_all(package_document(title, language, articles).as_bytes())?;
+        writer.start_file("OEBPS/nav.xhtml", deflated)?;
+        writer.write_all(navigation_document(title, language, articles).as_bytes())?;
+        for (chapter, article) in articles.iter().enumerate() {
+            let chapter = chapter + 1;
+            writer.start_file(format!("OEBPS/chapter-{:03}.xhtml", chapter), deflated)?;
+            writer.write_all(article.to_xhtml(chapter, language).as_bytes())?;
+            for (position, image) in article.images.iter().enumerate() {
+                writer.start_file(
+                    format!(
+                        "OEBPS/{}",
+                        Article::image_href(chapter, position + 1, image)
+                    ),
+                    stored,
+                )?;
+                writer.write_all(&image.content)?;
+            }
+        }
+        Ok(())
+    })();
+    written
+        .and_then(|_| writer.finish())
+        .map(|cursor| cursor.into_inner())
+        .map_err(|e| SdkError::Other(format!("failed to write the epub book: {}", e)))
+}
+
+const CONTAINER_XML: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n  <rootfiles>\n    <rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n  </rootfiles>\n</container>\n";
+
+/// the package document (`content.opf`): metadata, manifest and reading order
+fn package_document(title: &str, language: &str, articles: &[Article]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for article in articles {
+        article.source.hash(&mut hasher);
+    }
+    let retrieved = articles
+        .iter()
+        .map(|article| article.retrieved)
+        .min()
+        .unwrap_or_else(SystemTime::now);
+
+    let mut metadata = format!(
+        "    <dc:identifier id=\"book-id\">urn:mangater:{:016x}</dc:identifier>\n    <dc:title>{}</dc:title>\n    <dc:language>{}</dc:language>\n",
+        hasher.finish(),
+        escape_xml(title),
+        escape_xml(language)
+    );
+    for article in articles {
+        metadata.push_str(&format!(
+            "    <dc:source>{}</dc:source>\n",
+            escape_xml(&article.source)
+        ));
+    }
+    metadata.push_str(&format!(
+        "    <dc:date>{}</dc:date>\n    <meta property=\"dcterms:modified\">{}</meta>\n",
+        utc_timestamp(retrieved),
+        utc_timestamp(SystemTime::now())
+    ));
+
+    let mut manifest = String::from(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    let mut spine = String::new();
+    for (chapter, article) in articles.iter().enumerate() {
+        let chapter = chapter + 1;
+        manifest.push_str(&format!(
+            "    <item id=\"chapter-{0:03}\" href=\"chapter-{0:03}.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+            chapter
+        ));
+        spine.push_str(&format!(
+            "    <itemref idref=\"chapter-{:03}\"/>\n",
+            chapter
+        ));
+        for (position, image) in article.images.iter().enumerate() {
+            manifest.push_str(&format!(
+                "    <item id=\"image-{:03}-{:03}\" href=\"{}\" media-type=\"{}\"/>\n",
+                chapter,
+                position + 1,
+                Article::image_href(chapter, position + 1, image),
+                media_type(image.extension)
+            ));
+        }
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{}\">\n  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{}  </metadata>\n  <manifest>\n{}  </manifest>\n  <spine>\n{}  </spine>\n</package>\n",
+        escape_xml(language),
+        metadata,
+        manifest,
+        spine
+    )
+}
+
+/// the navigation document (`nav.xhtml`): the table of contents, one entry per chapter
+fn navigation_document(title: &str, language: &str, articles: &[Article]) -> String {
+    let entries: String = articles
+        .iter()
+        .enumerate()
+        .map(|(chapter, article)| {
+            format!(
+                "      <li><a href=\"chapter-{:03}.xhtml\">{}</a></li>\n",
+                chapter + 1,
+                escape_xml(&article.title)
+            )
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{0}\" xml:lang=\"{0}\">\n<head><title>{1}</title></head>\n<body>\n  <nav epub:type=\"toc\" id=\"toc\">\n    <h1>{1}</h1>\n    <ol>\n{2}    </ol>\n  </nav>\n</body>\n</html>\n",
+        escape_xml(language),
+        escape_xml(title),
+        entries
+    )
+}
+
+/// `CCYY-MM-DDThh:mm:ssZ`, as expected by `dcterms:modified`
+pub(super) fn utc_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+
+    // civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3_600,
+        rest % 3_600 / 60,
+        rest % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::storage::test_util::{self, item};
+    use std::io::Read;
+    use std::path::Path;
+    use std::time::Duration;
+    use zip::ZipArchive;
+
+    fn storage(output: &Path, book: Option<&str>) -> EpubStorage {
+        EpubStorage::new(&StorageConfig {
+            output: output.to_string_lossy().to_string(),
+            book: book.map(str::to_string),
+            ..StorageConfig::default()
+        })
+    }
+
+    fn read_entry(archive: &mut ZipArchive<std::fs::File>, name: &str) -> String {
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            utc_timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            "2023-11-14T22:13:20Z"
+        );
+        assert_eq!(
+            utc_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_article_xhtml() {
+        let mut article = Article::new("https://en.wikipedia.org/wiki/Rust_(programming_language)");
+        article.texts.push((
+            2,
+            "# Rust\n\nRust is a language.\nIt is fast & safe.\n\n## History\n\n![Ferris](https://upload.example.org/ferris.png)\n\n![Missing](https://upload.example.org/missing.png)".to_string(),
+        ));
+        article.title = markdown_title(&article.texts[0].1).unwrap();
+        for (index, url) in [
+            "https://upload.example.org/ferris.png",
+            "https://upload.example.org/logo.svg.png",
+        ]
+        .iter()
+        .enumerate()
+        {
+            article.images.push(Image {
+                index,
+                url: Some(url.to_string()),
+                extension: "png",
+                content: Vec::new(),
+            });
+        }
+
+        let xhtml = article.to_xhtml(2, "en");
+        assert!(xhtml.contains("<h1>Rust</h1>\n<p>Rust is a language.<br/>It is fast &amp; safe.</p>\n<h3>History</h3>\n"));
+        // re-linked to the local copy
+        assert!(xhtml.contains("<figure><img src=\"images/002-001.png\" alt=\"Ferris\"/></figure>"));
+        // not downloaded, no remote link
+        assert!(xhtml.contains("<p>Missing</p>"));
+        assert!(!xhtml.contains("missing.png"));
+        // not referenced by the text, appended
+        assert!(xhtml.contains("<figure><img src=\"images/002-002.png\" alt=\"\"/></figure>"));
+        assert!(xhtml.contains(
+            "Source: <a href=\"https://en.wikipedia.org/wiki/Rust_(programming_language)\">"
+        ));
+        assert_eq!(xhtml.matches("<h1>").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_finish_writes_book() {
+        let output = test_util::temp_output("epub", "book");
+        let storage = storage(&output, None);
+        let url = "https://en.wikipedia.org/wiki/NoSQL";
+
+        storage
+            .persist_with_context(
+                &test_util::context("wikipedia", url, 1),
+                &item(
+                    PatternType::Resource,
+                    Some("https://upload.example.org/db.png"),
+                ),
+                b"\x89PNG-db".to_vec(),
+            )
+            .await
+            .unwrap();
+        storage
+            .persist_with_context(
+                &test_util::context("wikipedia", url, 2),
+                &item(PatternType::Content, None),
+                b"NoSQL databases store data.".to_vec(),
+            )
+            .await
+            .unwrap();
+        storage
+            .finish(&test_util::context("wikipedia", url, 2))
+            .await
+            .unwrap();
+
+        let path = output.join("wikipedia").join("NoSQL.epub");
+        let mut archive = ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(archive.by_index(0).unwrap().name(), "mimetype");
+        assert_eq!(
+            archive.by_index(0).unwrap().compression(),
+            CompressionMethod::Stored
+        );
+        assert_eq!(read_entry(&mut archive, "mimetype"), "application/epub+zip");
+        assert!(read_entry(&mut archive, "META-INF/container.xml").contains("OEBPS/content.opf"));
+
+        let opf = read_entry(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>NoSQL</dc:title>"));
+        assert!(opf.contains("<dc:language>en</dc:language>"));
+        assert!(opf.contains(&format!("<dc:source>{}</dc:source>", url)));
+        assert!(opf.contains("<dc:date>"));
+        assert!(opf.contains("property=\"dcterms:modified\""));
+        assert!(opf.contains("href=\"images/001-001.png\" media-type=\"image/png\""));
+        assert!(opf.contains("<itemref idref=\"chapter-001\"/>"));
+
+        let nav = read_entry(&mut archive, "OEBPS/nav.xhtml");
+        assert!(nav.contains("<li><a href=\"chapter-001.xhtml\">NoSQL</a></li>"));
+        let chapter = read_entry(&mut archive, "OEBPS/chapter-001.xhtml");
+        assert!(chapter.contains("<p>NoSQL databases store data.</p>"));
+        assert!(chapter.contains("images/001-001.png"));
+        let mut image = Vec::new();
+        archive
+            .by_name("OEBPS/images/001-001.png")
+            .unwrap()
+            .read_to_end(&mut image)
+            .unwrap();
+        assert_eq!(image, b"\x89PNG-db".to_vec());
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_book_bundles_articles() {
+        let output = test_util::temp_output("epub", "bundle");
+        let storage = storage(&output, Some("Databases"));
+
+        for (index, url) in [
+            "https://en.wikipedia.org/wiki/NoSQL",
+            "https://en.wikipedia.org/wiki/SQL",
+        ]
+        .iter()
+        .enumerate()
+        {
+            storage
+                .persist_with_context(
+                    &test_util::context("wikipedia", url, 1),
+                    &item(PatternType::Content, None),
+                    format!("article {}", index + 1).into_bytes(),
+                )
+                .await
+                .unwrap();
+            storage
+                .finish(&test_util::context("wikipedia", url, 1))
+                .await
+                .unwrap();
+        }
+
+        // one book, rewritten as the articles come in
+        assert_eq!(std::fs::read_dir(&output).unwrap().count(), 1);
+        let mut archive =
+            ZipArchive::new(std::fs::File::open(output.join("Databases.epub")).unwrap()).unwrap();
+        let opf = read_entry(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>Databases</dc:title>"));
+        assert!(opf.contains("<dc:source>https://en.wikipedia.org/wiki/SQL</dc:source>"));
+        let nav = read_entry(&mut archive, "OEBPS/nav.xhtml");
+        assert!(nav.contains("<a href=\"chapter-001.xhtml\">NoSQL</a>"));
+        assert!(nav.contains("<a href=\"chapter-002.xhtml\">SQL</a>"));
+        assert!(read_entry(&mut archive, "OEBPS/chapter-002.xhtml").contains("<p>article 2</p>"));
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+}
@@ -0,0 +1,464 @@
+use crate::entity::{
+    CollisionPolicy, PatternMatchResult, PatternType, PersistContext, StorageConfig,
+};
+use crate::errors::SdkError;
+use crate::traits::Storage;
+
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+/// distinguishes the temp files of concurrent writes
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// longest file name (in bytes) written; most file systems cap it at 255
+const MAX_FILE_NAME_LENGTH: usize = 200;
+
+/// names Windows refuses as file names, whatever the extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Storage writing every persisted payload as an individual file, laid out by a path template.
+///
+/// The template (`StorageConfig::layout`) supports the following placeholders:
+/// * `{output}` - the output directory (`StorageConfig::output`)
+/// * `{domain_key}` - the domain key of the plugin, e.g. `wikipedia`
+/// * `{page_slug}` - the last path segment (plus query) of the url the scrap started from,
+///   e.g. `NoSQL` for `https://en.wikipedia.org/wiki/NoSQL`
+/// * `{index}` - the 1-based position of the item within the run, zero-padded (e.g. `0001`)
+/// * `{filename}` - the file name of a resource (e.g. `cat.jpg`), `content.txt` / `content.md` for text
+///
+/// Every placeholder value is sanitized into a valid file name. Files are written atomically
+/// (to a temp file first, then renamed); existing files are handled as per `StorageConfig::collision`.
+///
+/// # Example
+/// ```
+/// use mangater_sdk::entity::{PatternMatchResult, PatternType, PersistContext, StorageConfig};
+/// use mangater_sdk::storage::FileSystemStorage;
+/// use std::path::PathBuf;
+///
+/// let storage = FileSystemStorage::new(&StorageConfig::default());
+/// let context = PersistContext {
+///     domain_key: "wikipedia".to_string(),
+///     scrap_url: "https://en.wikipedia.org/wiki/NoSQL".to_string(),
+///     page_url: "https://en.wikipedia.org/wiki/NoSQL".to_string(),
+///     index: 1,
+///     content_type: None,
+/// };
+/// let resource = PatternMatchResult {
+///     pattern: "img".to_string(),
+///     pattern_type: PatternType::Resource,
+///     resource_string: Some("https://upload.wikimedia.org/wikipedia/Cat%20photo.jpg".to_string()),
+/// };
+/// assert_eq!(
+///     storage.file_path(&context, &resource),
+///     PathBuf::from("output/wikipedia/NoSQL/0001_Cat_photo.jpg")
+/// );
+/// ```
+#[derive(Debug)]
+pub struct FileSystemStorage {
+    output: PathBuf,
+    layout: String,
+    collision: CollisionPolicy,
+    /// running index of the items persisted without context
+    index: AtomicUsize,
+}
+
+impl FileSystemStorage {
+    pub fn new(config: &StorageConfig) -> Self {
+        Self {
+            output: PathBuf::from(&config.output),
+            layout: config.layout.clone(),
+            collision: config.collision,
+            index: AtomicUsize::new(0),
+        }
+    }
+
+    /// Resolves the path the given item is written to (before applying the collision policy).
+    pub fn file_path(&self, context: &PersistContext, resource: &PatternMatchResult) -> PathBuf {
+        let path = self
+            .layout
+            .replace("{output}", &self.output.to_string_lossy())
+            .replace(
+                "{domain_key}",
+                &sanitize_file_name(&context.domain_key, "default"),
+            )
+            .replace("{page_slug}", &page_slug(&context.scrap_url))
+            .replace("{index}", &format!("{:04}", context.index))
+            .replace("{filename}", &file_name(context, resource));
+        PathBuf::from(path)
+    }
+}
+
+#[async_trait]
+impl Storage for FileSystemStorage {
+    async fn persist(
+        &self,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<(), SdkError> {
+        let context = PersistContext {
+            index: self.index.fetch_add(1, Ordering::SeqCst) + 1,
+            ..PersistContext::default()
+        };
+        self.persist_with_context(&context, resource, resource_content)
+            .await?;
+        Ok(())
+    }
+
+    async fn persist_with_context(
+        &self,
+        context: &PersistContext,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<Option<PathBuf>, SdkError> {
+        let path = self.file_path(context, resource);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let Some(path) = resolve_collision(path, self.collision).await? else {
+            return Ok(None);
+        };
+        write_atomic(&path, &resource_content).await?;
+        tracing::debug!("persisted {:?} to {}", resource, path.display());
+        Ok(Some(path))
+    }
+}
+
+/// Writes the content to a temp file next to `path`, then renames it; a reader never sees
+/// a partially written file.
+pub(crate) async fn write_atomic(path: &Path, content: &[u8]) -> Result<(), SdkError> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+
+    let written = async {
+        let mut file = tokio::fs::File::create(&temp_path).await?;
+        file.write_all(content).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temp_path, path).await
+    }
+    .await;
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Applies the collision policy to the path to write; `None` if the write is to be skipped.
+pub(super) async fn resolve_collision(
+    path: PathBuf,
+    collision: CollisionPolicy,
+) -> Result<Option<PathBuf>, SdkError> {
+    match collision {
+        CollisionPolicy::Overwrite => Ok(Some(path)),
+        CollisionPolicy::Skip => {
+            if tokio::fs::try_exists(&path).await? {
+                tracing::debug!("{} already exists, skipped", path.display());
+                return Ok(None);
+            }
+            Ok(Some(path))
+        }
+        CollisionPolicy::Suffix => free_path(path).await.map(Some),
+    }
+}
+
+/// Returns the path itself if no file exists there, otherwise the first free `{stem}_{n}.{extension}`.
+async fn free_path(path: PathBuf) -> Result<PathBuf, SdkError> {
+    if !tokio::fs::try_exists(&path).await? {
+        return Ok(path);
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut suffix = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{}_{}{}", stem, suffix, extension));
+        if !tokio::fs::try_exists(&candidate).await? {
+            return Ok(candidate);
+        }
+        suffix += 1;
+    }
+}
+
+/// slug of the page the run started from, e.g. `NoSQL` for `https://en.wikipedia.org/wiki/NoSQL`
+/// or `viewer_chapter_3` for `https://example.com/viewer?chapter=3`
+pub(super) fn page_slug(url: &str) -> String {
+    let Ok(url) = Url::parse(url) else {
+        return "page".to_string();
+    };
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy().to_string())
+        .or_else(|| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_default();
+    let slug = match url.query() {
+        Some(query) => format!(
+            "{}_{}",
+            segment,
+            percent_decode_str(query).decode_utf8_lossy()
+        ),
+        None => segment,
+    };
+    sanitize_file_name(&slug, "page")
+}
+
+fn file_name(context: &PersistContext, resource: &PatternMatchResult) -> String {
+    match resource.pattern_type {
+        PatternType::Resource => {
+            let name = resource
+                .resource_string
+                .as_deref()
+                .and_then(|url| url.split(['?', '#']).next())
+                .and_then(|url| url.rsplit('/').next())
+                .map(|name| percent_decode_str(name).decode_utf8_lossy().to_string())
+                .unwrap_or_default();
+            sanitize_file_name(&name, "resource")
+        }
+        _ => {
+            let content_type = context.content_type.as_deref().unwrap_or("");
+            let extension = if content_type.starts_with("text/markdown") {
+                "md"
+            } else if content_type.starts_with("text/html") {
+                "html"
+            } else {
+                "txt"
+            };
+            format!("content.{}", extension)
+        }
+    }
+}
+
+/// Turns any string into a portable file name; anything but alphanumerics, `.`, `_` and `-`
+/// is replaced with `_`, leading / trailing dots are removed (no hidden files, no `..`),
+/// reserved Windows names are prefixed and overlong names are shortened (keeping the extension).
+pub(super) fn sanitize_file_name(name: &str, fallback: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "._-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let sanitized = sanitized.trim_matches('.');
+    if sanitized.is_empty() {
+        return fallback.to_string();
+    }
+
+    let (stem, extension) = match sanitized.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (sanitized, None),
+    };
+    let mut stem = stem.to_string();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(&stem))
+    {
+        stem.insert(0, '_');
+    }
+
+    let extension_length = extension.map(|extension| extension.len() + 1).unwrap_or(0);
+    let max_stem_length = MAX_FILE_NAME_LENGTH.saturating_sub(extension_length).max(1);
+    if stem.len() > max_stem_length {
+        let mut end = max_stem_length;
+        while !stem.is_char_boundary(end) {
+            end -= 1;
+        }
+        stem.truncate(end);
+    }
+
+    match extension {
+        Some(extension) => format!("{}.{}", stem, extension),
+        None => stem,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::storage::test_util::{self, image};
+
+    fn storage(output: &Path, collision: CollisionPolicy) -> FileSystemStorage {
+        FileSystemStorage::new(&StorageConfig {
+            output: output.to_string_lossy().to_string(),
+            collision,
+            ..StorageConfig::default()
+        })
+    }
+
+    fn context(index: usize, content_type: Option<&str>) -> PersistContext {
+        PersistContext {
+            page_url: "https://example.com/read/one-piece/chapter-1?page=2".to_string(),
+            content_type: content_type.map(|content_type| content_type.to_string()),
+            ..test_util::context(
+                "manga",
+                "https://example.com/read/one-piece/chapter-1",
+                index,
+            )
+        }
+    }
+
+    fn content() -> PatternMatchResult {
+        PatternMatchResult {
+            pattern: "article".to_string(),
+            pattern_type: PatternType::Content,
+            resource_string: None,
+        }
+    }
+
+    fn files(dir: &Path) -> Vec<String> {
+        let mut files: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_file_path_layout() {
+        let storage = FileSystemStorage::new(&StorageConfig {
+            output: "out".to_string(),
+            layout: "{output}/{domain_key}-{page_slug}/{filename}.{index}".to_string(),
+            ..StorageConfig::default()
+        });
+        assert_eq!(
+            storage.file_path(
+                &context(12, None),
+                &image("https://cdn.example.com/p/%E6%97%A5%20001.png?w=1#x")
+            ),
+            PathBuf::from("out/manga-chapter-1/日_001.png.0012")
+        );
+        assert_eq!(
+            storage.file_path(
+                &context(3, Some("text/markdown; charset=utf-8")),
+                &content()
+            ),
+            PathBuf::from("out/manga-chapter-1/content.md.0003")
+        );
+
+        let default = FileSystemStorage::new(&StorageConfig::default());
+        let context = PersistContext {
+            domain_key: "../..".to_string(),
+            scrap_url: "https://example.com/viewer?chapter=3&lang=en".to_string(),
+            ..context(1, None)
+        };
+        assert_eq!(
+            default.file_path(&context, &image("https://example.com/")),
+            PathBuf::from("output/_/viewer_chapter_3_lang_en/0001_resource")
+        );
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("cat photo?.jpg", "x"), "cat_photo_.jpg");
+        assert_eq!(
+            sanitize_file_name("../../etc/passwd", "x"),
+            "_.._etc_passwd"
+        );
+        assert_eq!(sanitize_file_name(".hidden", "x"), "hidden");
+        assert_eq!(sanitize_file_name("...", "x"), "x");
+        assert_eq!(sanitize_file_name("con.txt", "x"), "_con.txt");
+        assert_eq!(sanitize_file_name("Lpt1", "x"), "_Lpt1");
+        assert_eq!(sanitize_file_name("console.txt", "x"), "console.txt");
+
+        let long = format!("{}.jpeg", "日".repeat(100));
+        let sanitized = sanitize_file_name(&long, "x");
+        assert!(sanitized.len() <= MAX_FILE_NAME_LENGTH);
+        assert!(sanitized.ends_with("日.jpeg"));
+    }
+
+    #[tokio::test]
+    async fn test_persist_collision_policies() {
+        let output = test_util::temp_output("fs", "collision");
+        let resource = image("https://example.com/img/001.jpg");
+        let page_dir = output.join("manga").join("chapter-1");
+
+        let suffix = storage(&output, CollisionPolicy::Suffix);
+        let mut written = Vec::new();
+        for content in ["first", "second", "third"] {
+            written.push(
+                suffix
+                    .persist_with_context(&context(1, None), &resource, content.as_bytes().to_vec())
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_eq!(
+            files(&page_dir),
+            vec!["0001_001.jpg", "0001_001_1.jpg", "0001_001_2.jpg"]
+        );
+        // the path actually written, not the one before the collision
+        assert_eq!(written[2], Some(page_dir.join("0001_001_2.jpg")));
+        assert_eq!(
+            std::fs::read_to_string(page_dir.join("0001_001_2.jpg")).unwrap(),
+            "third"
+        );
+
+        let skip = storage(&output, CollisionPolicy::Skip);
+        let skipped = skip
+            .persist_with_context(&context(1, None), &resource, b"skipped".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(skipped, None);
+        assert_eq!(
+            std::fs::read_to_string(page_dir.join("0001_001.jpg")).unwrap(),
+            "first"
+        );
+
+        let overwrite = storage(&output, CollisionPolicy::Overwrite);
+        overwrite
+            .persist_with_context(&context(1, None), &resource, b"replaced".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(page_dir.join("0001_001.jpg")).unwrap(),
+            "replaced"
+        );
+        // no temp file left behind
+        assert_eq!(files(&page_dir).len(), 3);
+
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persist_without_context() {
+        let output = test_util::temp_output("fs", "no-context");
+        let storage = storage(&output, CollisionPolicy::Suffix);
+        storage.persist(&content(), b"text".to_vec()).await.unwrap();
+        storage
+            .persist(&image("https://example.com/a.png"), b"png".to_vec())
+            .await
+            .unwrap();
+
+        let page_dir = output.join("default").join("page");
+        assert_eq!(files(&page_dir), vec!["0001_content.txt", "0002_a.png"]);
+
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+}
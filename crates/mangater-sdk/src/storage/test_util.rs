@@ -0,0 +1,44 @@
+//! Helpers shared by the tests of the storages.
+
+use crate::entity::{PatternMatchResult, PatternType, PersistContext};
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// a folder under the system's temp dir to write to, e.g. `mangater-cbz-archive-<pid>-<nanos>`
+pub(super) fn temp_output(kind: &str, name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    std::env::temp_dir().join(format!(
+        "mangater-{}-{}-{}-{}",
+        kind,
+        name,
+        std::process::id(),
+        nanos
+    ))
+}
+
+/// the `index`-th item of the plugin `domain_key` scraping `scrap_url` (its page url as well)
+pub(super) fn context(domain_key: &str, scrap_url: &str, index: usize) -> PersistContext {
+    PersistContext {
+        domain_key: domain_key.to_string(),
+        scrap_url: scrap_url.to_string(),
+        page_url: scrap_url.to_string(),
+        index,
+        content_type: None,
+    }
+}
+
+pub(super) fn item(pattern_type: PatternType, resource_string: Option<&str>) -> PatternMatchResult {
+    PatternMatchResult {
+        pattern: "img".to_string(),
+        pattern_type,
+        resource_string: resource_string.map(str::to_string),
+    }
+}
+
+pub(super) fn image(url: &str) -> PatternMatchResult {
+    item(PatternType::Resource, Some(url))
+}
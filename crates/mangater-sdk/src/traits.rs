@@ -34,5 +34,5 @@ mod storage;
 pub use config::Config;
 pub use domain::Domain;
 pub use matcher::Matcher;
-pub use registry::Registry;
+pub use registry::{Registry, DEFAULT_PRIORITY};
 pub use storage::Storage;
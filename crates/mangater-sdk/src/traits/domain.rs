@@ -44,6 +44,20 @@ pub trait Domain: Send + Sync {
     /// - `Err(SdkError)` if an error occurs during the matching process.
     fn match_domain(&self, domain: String) -> Result<bool, SdkError>;
 
+    /// Hosts the implementation supports, letting the registry skip it for the urls of any other
+    /// host instead of calling [`Domain::match_domain`]; empty (the default) means not indexed.
+    ///
+    /// A pattern is either an exact host (e.g. `"en.wikipedia.org"`) or a suffix wildcard
+    /// matching any subdomain (e.g. `"*.wikipedia.org"`, which does not match `wikipedia.org`
+    /// itself). Case insensitive.
+    ///
+    /// The patterns only narrow the candidates down, `match_domain` is not a mere tie-breaker:
+    /// a url whose host matches them still has to pass `match_domain` (e.g. its scheme or path
+    /// may not be supported), even if no other domain is a candidate for it.
+    fn host_patterns(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     // /// Registers a domain along with its associated trait implementations in the given registry.
     // ///
     // /// # Parameters
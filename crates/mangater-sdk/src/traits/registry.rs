@@ -1,16 +1,49 @@
+use crate::entity::DomainCandidate;
+use crate::errors::SdkError;
 use crate::traits::Domain;
 
 use std::sync::Arc;
 
+/// priority of the domains registered through [`Registry::add_to_registry`]
+pub const DEFAULT_PRIORITY: i32 = 0;
+
 pub trait Registry {
-    /// Register a domain under the given key. Replaces any existing entry with the same key (no duplicates per key).
+    /// Register a domain under the given key with the [`DEFAULT_PRIORITY`].
+    /// Replaces any existing entry with the same key (no duplicates per key).
     /// key could be the following:
     /// - domain name (e.g. "www.wikipedia.org")
     /// - plugin / implementation name (e.g. "wikipedia")
+    ///
     /// as long as it can uniquely identify the implementation.
-    fn add_to_registry(&mut self, key: Option<String>, domain: Arc<dyn Domain>);
+    fn add_to_registry(&mut self, key: Option<String>, domain: Arc<dyn Domain>) {
+        self.add_to_registry_with_priority(key, domain, DEFAULT_PRIORITY);
+    }
+
+    /// Register a domain under the given key with an explicit priority; when several domains
+    /// match the same url, the highest priority wins, then the earliest registered one.
+    /// Replacing an existing key keeps its registration position.
+    fn add_to_registry_with_priority(
+        &mut self,
+        key: Option<String>,
+        domain: Arc<dyn Domain>,
+        priority: i32,
+    );
+
+    /// Resolves the domain supporting the url: the first matching one in resolution order
+    /// (see [`Registry::add_to_registry_with_priority`]).
+    ///
+    /// The url is parsed once to look its host up in the `Domain::host_patterns` of the
+    /// domains: the ones whose patterns miss it are skipped, `Domain::match_domain` decides
+    /// among the remaining ones (the ones declaring no patterns included).
+    ///
+    /// A domain failing to match (`Domain::match_domain` returning an error) is logged and
+    /// skipped; if no other domain matches, its error is returned instead of `Ok(None)`.
+    fn resolve_domain(&self, url: &str) -> Result<Option<Arc<dyn Domain>>, SdkError>;
 
-    fn resolve_domain(&self, url: &str) -> Option<Arc<dyn Domain>>;
+    /// Every registered domain in resolution order, each with its answer for the url;
+    /// the first one matching is the one [`Registry::resolve_domain`] returns.
+    fn resolve_candidates(&self, url: &str) -> Vec<DomainCandidate>;
 
+    /// keys of the registered domains, in resolution order
     fn list_registered_domains(&self) -> Vec<String>;
 }
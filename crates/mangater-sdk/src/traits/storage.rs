@@ -25,10 +25,11 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::entity::PatternMatchResult;
+use crate::entity::{PatternMatchResult, PersistContext};
 use crate::errors::SdkError;
 
 use async_trait::async_trait;
+use std::path::PathBuf;
 
 /// The `Storage` trait defines the interface for persisting resources extracted by the Mangater SDK.
 ///
@@ -51,4 +52,47 @@ pub trait Storage: Send + Sync {
         resource: &PatternMatchResult,
         resource_content: Vec<u8>,
     ) -> Result<(), SdkError>;
+
+    /// Persists the given resource and its content, knowing where it comes from (e.g. the plugin's
+    /// domain key and the page url); called by the engine for every item of a run.
+    ///
+    /// Defaults to [`Storage::persist`]; storages laying out the content per page or per plugin
+    /// (e.g. `storage::FileSystemStorage`) override it.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - where the resource comes from, and its position within the run.
+    /// * `resource` - A reference to the `PatternMatchResult` that describes the matched resource metadata.
+    /// * `resource_content` - A vector of bytes representing the actual content to be stored.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<PathBuf>, SdkError>` - the file of the local file system the item was
+    ///   written to, after handling any collision (e.g. for `storage::CatalogStorage` to record
+    ///   it); `None` if the item was not written to a file of its own (skipped, bundled with
+    ///   other items, or not stored on the local file system).
+    async fn persist_with_context(
+        &self,
+        context: &PersistContext,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<Option<PathBuf>, SdkError> {
+        let _ = context;
+        self.persist(resource, resource_content).await?;
+        Ok(None)
+    }
+
+    /// Called by the engine once a run is over (whether it succeeded or not), after every item
+    /// of the run has been persisted.
+    ///
+    /// Does nothing by default; storages bundling the items of a run (e.g. `storage::CbzStorage`
+    /// packing the pages of a chapter into one archive) write them out here.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - the run being finished; `index` is the number of items persisted.
+    async fn finish(&self, context: &PersistContext) -> Result<(), SdkError> {
+        let _ = context;
+        Ok(())
+    }
 }
@@ -1,2 +1,9 @@
+pub mod fixture;
 pub mod html_parsing;
+pub mod http;
+pub mod http_cache;
+pub mod markdown;
+pub mod rate_limit;
 pub mod resource;
+pub mod retry;
+pub mod robots;
@@ -0,0 +1,271 @@
+use crate::entity::{DownloadedResource, ResourceMetadata};
+use crate::errors::SdkError;
+use crate::storage::write_atomic;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// environment variable switching [`Fixtures::from_env`] to recording, e.g. `MANGATER_FIXTURES=record`
+pub const FIXTURES_ENV: &str = "MANGATER_FIXTURES";
+
+/// What [`Fixtures`] do with the requests of an [`HttpFetcher`](crate::util::http::HttpFetcher).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FixtureMode {
+    /// responses are served from the fixtures only; never hits the network
+    #[default]
+    Replay,
+    /// responses are downloaded as usual and saved as fixtures, replacing the existing ones
+    Record,
+}
+
+/// Recorded request / response pairs, for deterministic tests without network.
+///
+/// A recording run saves every response downloaded through the fetcher into the fixture folder;
+/// a replay run serves them back, so the same scrap (e.g. `Engine::run_scrap_workflow`) gives
+/// the same results offline. Every url has a pair of files named after it
+/// (e.g. `en.wikipedia.org_wiki_NoSQL-1a2b3c4d`): the body as-is in `.body`, the status and
+/// response metadata in `.json`. Error statuses (404, 401 / 403, 429) are recorded too, e.g.
+/// a site without robots.txt.
+///
+/// # Example
+/// ```no_run
+/// use mangater_sdk::entity::HttpConfig;
+/// use mangater_sdk::util::fixture::Fixtures;
+/// use mangater_sdk::util::http::HttpFetcher;
+/// use std::sync::Arc;
+///
+/// # async fn run() -> Result<(), mangater_sdk::SdkError> {
+/// // replay, unless run with MANGATER_FIXTURES=record
+/// let fixtures = Arc::new(Fixtures::from_env("testdata/fixtures")?);
+/// let fetcher = HttpFetcher::new(&HttpConfig::default(), None)?.with_fixtures(fixtures);
+/// let page = fetcher.fetch_text("https://en.wikipedia.org/wiki/NoSQL", None).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    root: PathBuf,
+    mode: FixtureMode,
+}
+
+/// The recorded response of a url, stored next to its body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Fixture {
+    url: String,
+    status: u16,
+    final_url: String,
+    content_type: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Fixtures {
+    /// Opens the fixture folder; created on recording.
+    pub fn open(root: impl Into<PathBuf>, mode: FixtureMode) -> Result<Self, SdkError> {
+        let root = root.into();
+        if mode == FixtureMode::Record {
+            std::fs::create_dir_all(&root)?;
+        }
+        Ok(Self { root, mode })
+    }
+
+    /// Opens the fixture folder in replay mode, or in record mode if [`FIXTURES_ENV`] is `record`.
+    pub fn from_env(root: impl Into<PathBuf>) -> Result<Self, SdkError> {
+        let mode = match std::env::var(FIXTURES_ENV) {
+            Ok(value) if value.eq_ignore_ascii_case("record") => FixtureMode::Record,
+            _ => FixtureMode::Replay,
+        };
+        Self::open(root, mode)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// Serves the recorded response of the url; a recorded error status is returned as the
+    /// fetcher would have (e.g. `SdkError::NotFound` for 404).
+    ///
+    /// # Errors
+    ///
+    /// Returns `SdkError::Network` if nothing was recorded for the url.
+    pub async fn replay(&self, url: &str) -> Result<DownloadedResource, SdkError> {
+        let (fixture_path, body_path) = self.fixture_paths(url);
+        let not_recorded = || SdkError::Network(format!("no fixture recorded for {}", url));
+        let fixture = tokio::fs::read(&fixture_path)
+            .await
+            .map_err(|_| not_recorded())?;
+        let fixture: Fixture = serde_json::from_slice(&fixture)
+            .map_err(|e| SdkError::Parse(format!("{} - {}", fixture_path.display(), e)))?;
+        if fixture.url != url {
+            return Err(not_recorded());
+        }
+
+        match fixture.status {
+            200..=299 => {}
+            404 | 410 => return Err(SdkError::NotFound(url.to_string())),
+            401 | 403 => return Err(SdkError::Authentication),
+            429 | 503 => return Err(SdkError::RateLimited),
+            status => {
+                return Err(SdkError::Network(format!(
+                    "HTTP status {} for url ({})",
+                    status, url
+                )))
+            }
+        }
+        let bytes = tokio::fs::read(&body_path).await?;
+        let metadata = ResourceMetadata {
+            content_type: fixture.content_type,
+            content_length: Some(bytes.len() as u64),
+            final_url: fixture.final_url,
+            etag: fixture.etag,
+            last_modified: fixture.last_modified,
+        };
+        Ok(DownloadedResource { bytes, metadata })
+    }
+
+    /// Saves the outcome of fetching the url; failures other than HTTP error statuses
+    /// (e.g. a timeout) are not worth replaying, hence not recorded.
+    pub async fn record(
+        &self,
+        url: &str,
+        fetched: &Result<DownloadedResource, SdkError>,
+    ) -> Result<(), SdkError> {
+        let (status, resource) = match fetched {
+            Ok(resource) => (200, Some(resource)),
+            Err(SdkError::NotFound(_)) => (404, None),
+            Err(SdkError::Authentication) => (403, None),
+            Err(SdkError::RateLimited) => (429, None),
+            Err(_) => return Ok(()),
+        };
+        let metadata = resource
+            .map(|resource| resource.metadata.clone())
+            .unwrap_or_default();
+        let fixture = Fixture {
+            url: url.to_string(),
+            status,
+            final_url: if metadata.final_url.is_empty() {
+                url.to_string()
+            } else {
+                metadata.final_url
+            },
+            content_type: metadata.content_type,
+            etag: metadata.etag,
+            last_modified: metadata.last_modified,
+        };
+
+        let (fixture_path, body_path) = self.fixture_paths(url);
+        let body = resource
+            .map(|resource| resource.bytes.as_slice())
+            .unwrap_or_default();
+        write_atomic(&body_path, body).await?;
+        let fixture = serde_json::to_vec_pretty(&fixture)
+            .map_err(|e| SdkError::Storage(std::io::Error::other(e)))?;
+        write_atomic(&fixture_path, &fixture).await
+    }
+
+    /// paths of the metadata and the body recorded for the url
+    fn fixture_paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let hash: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .take(4)
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let readable: String = url
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .unwrap_or(url)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(80)
+            .collect();
+        let name = format!("{}-{}", readable.trim_matches('_'), hash);
+        (
+            self.root.join(format!("{}.json", name)),
+            self.root.join(format!("{}.body", name)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_root(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "mangater-fixtures-{}-{}-{}",
+            name,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() -> Result<(), SdkError> {
+        let root = temp_root("record");
+        let recorder = Fixtures::open(&root, FixtureMode::Record)?;
+        let url = "https://example.com/chapter/1?page=2";
+        let resource = DownloadedResource {
+            bytes: vec![0xFF, 0xD8, 0x00],
+            metadata: ResourceMetadata {
+                content_type: Some("image/jpeg".to_string()),
+                content_length: Some(3),
+                final_url: "https://cdn.example.com/1.jpg".to_string(),
+                etag: Some("\"v1\"".to_string()),
+                last_modified: None,
+            },
+        };
+        recorder.record(url, &Ok(resource.clone())).await?;
+        recorder
+            .record(
+                "https://example.com/robots.txt",
+                &Err(SdkError::NotFound(
+                    "https://example.com/robots.txt".to_string(),
+                )),
+            )
+            .await?;
+        // not worth replaying
+        recorder
+            .record(
+                "https://example.com/timeout",
+                &Err(SdkError::Network("timeout".to_string())),
+            )
+            .await?;
+
+        let (fixture_path, _) = recorder.fixture_paths(url);
+        let name = fixture_path.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("example.com_chapter_1_page_2-"));
+
+        let replayer = Fixtures::open(&root, FixtureMode::Replay)?;
+        let replayed = replayer.replay(url).await?;
+        assert_eq!(replayed.bytes, resource.bytes);
+        assert_eq!(replayed.metadata, resource.metadata);
+        match replayer.replay("https://example.com/robots.txt").await {
+            Err(SdkError::NotFound(_)) => {}
+            other => panic!("Expected SdkError::NotFound, got {:?}", other),
+        }
+        match replayer.replay("https://example.com/timeout").await {
+            Err(SdkError::Network(msg)) => assert!(msg.contains("no fixture recorded")),
+            other => panic!("Expected SdkError::Network, got {:?}", other),
+        }
+
+        std::fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
@@ -1,8 +1,13 @@
+use encoding_rs::{Encoding, UTF_8};
 use scraper::{node::Node, ElementRef, Html, Selector};
+use url::Url;
 
-use crate::entity::{HtmlImage, HtmlPlainTextAndImages};
+use crate::entity::{HtmlImage, HtmlPlainTextAndImages, PatternMatchResult};
+use crate::errors::SdkError;
 
-fn clean_text(element: &ElementRef) -> String {
+/// Collects the text of the element, skipping the elements (and their children) matching any
+/// of the `exclude` selectors.
+fn clean_text(element: &ElementRef, exclude: &[Selector]) -> String {
     let mut text = String::new();
 
     for node in element.children() {
@@ -12,7 +17,10 @@ fn clean_text(element: &ElementRef) -> String {
             }
             Node::Element(e) if e.name() != "style" && e.name() != "script" => {
                 if let Some(child_elem) = ElementRef::wrap(node) {
-                    let child_text = clean_text(&child_elem);
+                    if exclude.iter().any(|selector| selector.matches(&child_elem)) {
+                        continue;
+                    }
+                    let child_text = clean_text(&child_elem, exclude);
                     // Insert space only for block elements
                     //println!("*Child element: {}", e.name());
                     match e.name() {
@@ -33,70 +41,432 @@ fn clean_text(element: &ElementRef) -> String {
     text
 }
 
+/// Parses the provided HTML document and extracts all `<img>` elements as a collection of `HtmlImage`.
+///
+/// Every image url is resolved to an absolute url against the document's `<base href>` (if any)
+/// or the page url. Lazy-loaded images (`data-src` / `data-srcset`) and `srcset` candidates are
+/// honored, picking the highest resolution candidate. Inline `data:` images are skipped.
+///
+/// # Arguments
+///
+/// * `content` - the HTML document
+/// * `base_url` - the url the document was fetched from
+///
+/// # Errors
+///
+/// Returns `SdkError::Parse` if the `base_url` is not a valid absolute url.
+///
 /// # Example
 ///
 /// ```
-/// use scraper::Html;
 /// use mangater_sdk::util::html_parsing::parse_images;
 ///
-/// const CONTENT: &str = r#"<img src="cat.jpg"><img src="dog.png">"#;
-/// let images = parse_images(CONTENT.to_string());
+/// const CONTENT: &str = r#"<img src="cat.jpg" alt="cat"><img src="//cdn.example.com/dog.png" srcset="dog-2x.png 2x">"#;
+/// let images = parse_images(CONTENT, "https://example.com/pets/").unwrap();
 /// assert_eq!(images.len(), 2);
-/// assert_eq!(images[0].src, "cat.jpg");
-/// assert_eq!(images[1].src, "dog.png");
+/// assert_eq!(images[0].src, "https://example.com/pets/cat.jpg");
+/// assert_eq!(images[0].alt.as_deref(), Some("cat"));
+/// assert_eq!(images[1].src, "https://example.com/pets/dog-2x.png");
 /// ```
-pub fn parse_images(content: String) -> Vec<HtmlImage> {
-    let document = Html::parse_document(&content);
-    parse_images_through_html(&document)
+pub fn parse_images(content: &str, base_url: &str) -> Result<Vec<HtmlImage>, SdkError> {
+    let document = Html::parse_document(content);
+    let base_url = document_base_url(&document, base_url)?;
+    parse_images_through_html(document.root_element(), &base_url, &[])
 }
 
-/// Parses the provided HTML document and extracts all `<img>` elements as a collection of `HtmlImage`.
+/// Resolves the base url of the document; its `<base href>` (relative to the page url) if any,
+/// otherwise the page url.
+///
+/// # Errors
 ///
-/// This utility function takes a reference to a [`scraper::Html`] document and returns a vector of
-/// [`HtmlImage`] structs, each containing the `src` attribute and the inner HTML of the image element.
+/// Returns `SdkError::Parse` if the `page_url` is not a valid absolute url.
+pub fn resolve_base_url(content: &str, page_url: &str) -> Result<Url, SdkError> {
+    let document = Html::parse_document(content);
+    document_base_url(&document, page_url)
+}
+
+pub(crate) fn document_base_url(document: &Html, page_url: &str) -> Result<Url, SdkError> {
+    let page_url =
+        Url::parse(page_url).map_err(|e| SdkError::Parse(format!("{} - {}", page_url, e)))?;
+    let selector = parse_selector("base[href]")?;
+    let base_url = document
+        .select(&selector)
+        .next()
+        .and_then(|base| base.value().attr("href"))
+        .and_then(|href| page_url.join(href.trim()).ok())
+        .unwrap_or(page_url);
+    Ok(base_url)
+}
+
+/// Extracts the `<img>` elements under the given element, resolving their urls against `base_url`.
 ///
 /// # Arguments
 ///
-/// * `document` - A reference to the parsed HTML content.
+/// * `element` - the element to search for images (e.g. the root element of the document)
+/// * `base_url` - the url relative image urls are resolved against
+/// * `exclude` - images within elements matching any of these selectors are skipped
 ///
 /// # Returns
 ///
-/// A vector of [`HtmlImage`] structs representing all images found in the document.
+/// A vector of [`HtmlImage`] structs representing all images found, in document order.
 ///
-fn parse_images_through_html(document: &Html) -> Vec<HtmlImage> {
-    let selector = Selector::parse("img").unwrap();
-    let images = document.select(&selector).collect::<Vec<_>>();
-    let mut html_images = Vec::new();
-    for image in images {
-        if let Some(src) = image.value().attr("src") {
-            let inner_html = image.html();
-            html_images.push(HtmlImage {
+fn parse_images_through_html(
+    element: ElementRef,
+    base_url: &Url,
+    exclude: &[Selector],
+) -> Result<Vec<HtmlImage>, SdkError> {
+    let selector = parse_selector("img")?;
+    let images = element
+        .select(&selector)
+        .filter(|image| !is_excluded(image, &element, exclude))
+        .filter_map(|image| {
+            let src = image_source(&image)?;
+            let src = base_url.join(&src).ok()?;
+            let attr = |name: &str| image.value().attr(name);
+            Some(HtmlImage {
                 src: src.to_string(),
-                inner_html: inner_html.to_string(),
-            });
+                inner_html: image.html(),
+                alt: attr("alt").map(|alt| alt.to_string()),
+                width: attr("width").and_then(parse_dimension),
+                height: attr("height").and_then(parse_dimension),
+            })
+        })
+        .collect();
+    Ok(images)
+}
+
+/// Picks the (unresolved) url of the highest resolution candidate of an `<img>`;
+/// lazy-load attributes (`data-srcset`, `data-src`) win over the `srcset` / `src` ones,
+/// which usually hold a placeholder.
+pub(crate) fn image_source(image: &ElementRef) -> Option<String> {
+    let attr = |name: &str| {
+        image
+            .value()
+            .attr(name)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut candidates = Vec::new();
+    for (src, srcset) in [("data-src", "data-srcset"), ("src", "srcset")] {
+        if let Some(src) = attr(src) {
+            candidates.push((src.to_string(), ImageDescriptor::Density(1.0)));
+        }
+        if let Some(srcset) = attr(srcset) {
+            candidates.extend(parse_srcset(srcset));
+        }
+        candidates.retain(|(url, _)| !url.starts_with("data:"));
+        if !candidates.is_empty() {
+            break;
+        }
+    }
+
+    // width descriptors are more precise than densities, prefer them if any
+    let has_width = candidates
+        .iter()
+        .any(|(_, descriptor)| matches!(descriptor, ImageDescriptor::Width(_)));
+    candidates
+        .into_iter()
+        .filter(|(_, descriptor)| !has_width || matches!(descriptor, ImageDescriptor::Width(_)))
+        .max_by(|(_, a), (_, b)| a.value().total_cmp(&b.value()))
+        .map(|(url, _)| url)
+}
+
+/// descriptor of a `srcset` candidate, e.g. `640w` or `2x`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ImageDescriptor {
+    Width(u32),
+    Density(f64),
+}
+
+impl ImageDescriptor {
+    fn value(&self) -> f64 {
+        match self {
+            ImageDescriptor::Width(width) => *width as f64,
+            ImageDescriptor::Density(density) => *density,
         }
     }
-    html_images
 }
 
-pub fn parse_plain_text_and_images(content: String) -> HtmlPlainTextAndImages {
-    let document = Html::parse_document(&content);
-    let selector = Selector::parse("#mw-content-text").unwrap();
-    let content = document.select(&selector).next().unwrap();
+/// Parses a `srcset` value (e.g. `a.png 1x, b.png 2x` or `a.png 320w, b.png 640w`) into its candidates;
+/// a candidate without descriptor is `1x`, candidates with an invalid descriptor are skipped.
+fn parse_srcset(srcset: &str) -> Vec<(String, ImageDescriptor)> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        // the url runs up to the next whitespace; a trailing comma ends the candidate
+        let url_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let url = &rest[..url_end];
+        rest = &rest[url_end..];
 
-    let text = clean_text(&content);
+        let (url, descriptor) = match url.strip_suffix(',') {
+            Some(url) => (url.trim_end_matches(','), ""),
+            None => {
+                let descriptor_end = rest.find(',').unwrap_or(rest.len());
+                let descriptor = rest[..descriptor_end].trim();
+                rest = &rest[descriptor_end..];
+                (url, descriptor)
+            }
+        };
 
-    HtmlPlainTextAndImages {
-        text: text,
-        images: parse_images_through_html(&document),
+        let descriptor = if descriptor.is_empty() {
+            Some(ImageDescriptor::Density(1.0))
+        } else if let Some(width) = descriptor.strip_suffix('w') {
+            width.parse().ok().map(ImageDescriptor::Width)
+        } else if let Some(density) = descriptor.strip_suffix('x') {
+            density
+                .parse::<f64>()
+                .ok()
+                .filter(|density| density.is_finite() && *density > 0.0)
+                .map(ImageDescriptor::Density)
+        } else {
+            None
+        };
+        if let Some(descriptor) = descriptor {
+            candidates.push((url.to_string(), descriptor));
+        }
     }
+    candidates
+}
+
+/// parses a `width` / `height` attribute, e.g. `220` or `220px`
+fn parse_dimension(value: &str) -> Option<u32> {
+    value.trim().trim_end_matches("px").trim().parse().ok()
+}
+
+/// Extracts the plain-text content and the images of the first element matching the
+/// pattern's CSS selector (e.g. `#mw-content-text` for Wikipedia, `article` for most blogs).
+///
+/// Elements matching any of the `exclude` selectors (e.g. `nav`, `.infobox`, `footer`) are
+/// skipped, together with their text and images. Image urls are resolved the same way as
+/// [`parse_images`] does.
+///
+/// # Arguments
+///
+/// * `content` - the HTML document
+/// * `base_url` - the url the document was fetched from
+/// * `pattern` - the `Content` pattern; its `pattern` is the CSS selector of the content
+/// * `exclude` - CSS selectors of the elements to leave out
+///
+/// # Errors
+///
+/// Returns `SdkError::Parse` if any selector or the `base_url` is invalid, or nothing on the page
+/// matches the pattern.
+///
+/// # Example
+///
+/// ```
+/// use mangater_sdk::entity::{PatternMatchResult, PatternType};
+/// use mangater_sdk::util::html_parsing::parse_plain_text_and_images;
+///
+/// const CONTENT: &str = r#"<article><nav>Home</nav><p>Chapter 1</p><img src="1.png"></article>"#;
+/// let pattern = PatternMatchResult {
+///     pattern: "article".to_string(),
+///     pattern_type: PatternType::Content,
+///     resource_string: None,
+/// };
+/// let result = parse_plain_text_and_images(
+///     CONTENT,
+///     "https://example.com/chapter/1",
+///     &pattern,
+///     &["nav".to_string()],
+/// )
+/// .unwrap();
+/// assert_eq!(result.text.trim(), "Chapter 1");
+/// assert_eq!(result.images[0].src, "https://example.com/chapter/1.png");
+/// ```
+pub fn parse_plain_text_and_images(
+    content: &str,
+    base_url: &str,
+    pattern: &PatternMatchResult,
+    exclude: &[String],
+) -> Result<HtmlPlainTextAndImages, SdkError> {
+    let document = Html::parse_document(content);
+    let base_url = document_base_url(&document, base_url)?;
+    let element = select_first(&document, &pattern.pattern)?;
+    let exclude = parse_selectors(exclude)?;
+
+    Ok(HtmlPlainTextAndImages {
+        text: clean_text(&element, &exclude),
+        images: parse_images_through_html(element, &base_url, &exclude)?,
+    })
+}
+
+/// Checks whether the element, or any of its ancestors up to `root`, matches an `exclude` selector.
+fn is_excluded(element: &ElementRef, root: &ElementRef, exclude: &[Selector]) -> bool {
+    std::iter::once(*element)
+        .chain(
+            element
+                .ancestors()
+                .take_while(|node| node.id() != root.id())
+                .filter_map(ElementRef::wrap),
+        )
+        .any(|element| exclude.iter().any(|selector| selector.matches(&element)))
+}
+
+/// number of leading bytes scanned for a `<meta charset>` declaration (same as the HTML spec's prescan)
+const META_CHARSET_PRESCAN_SIZE: usize = 1024;
+
+/// Decodes a HTML document into text.
+///
+/// The character encoding is resolved in the following order; defaulting to UTF-8:
+/// 1. byte order mark (BOM)
+/// 2. the `charset` parameter of the `Content-Type` header (e.g. `text/html; charset=Shift_JIS`)
+/// 3. `<meta charset="...">` or `<meta http-equiv="Content-Type" content="...; charset=...">`
+///
+/// Malformed byte sequences are replaced with U+FFFD instead of failing.
+///
+/// # Example
+///
+/// ```
+/// use mangater_sdk::util::html_parsing::decode_html;
+///
+/// // "café" in ISO-8859-1 / windows-1252
+/// let bytes = b"<html><head><meta charset=\"iso-8859-1\"></head>caf\xe9</html>";
+/// assert!(decode_html(bytes, None).contains("café"));
+/// assert!(decode_html(bytes, Some("text/html; charset=windows-1252")).contains("café"));
+/// ```
+pub fn decode_html(bytes: &[u8], content_type: Option<&str>) -> String {
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| {
+            content_type
+                .and_then(charset_from_content_type)
+                .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+        })
+        .or_else(|| sniff_meta_charset(bytes))
+        .unwrap_or(UTF_8);
+
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+/// Extracts the `charset` parameter from a `Content-Type` value.
+fn charset_from_content_type(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("charset") {
+            Some(value.trim().trim_matches(['"', '\'']).to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Looks for a charset declared through a `<meta>` tag within the beginning of the document.
+fn sniff_meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(META_CHARSET_PRESCAN_SIZE)];
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+
+    head.match_indices("<meta").find_map(|(start, _)| {
+        let tag = &head[start..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        let charset = &tag[tag.find("charset")? + "charset".len()..];
+        let charset = charset.trim_start().strip_prefix('=')?.trim_start();
+        let charset: String = charset
+            .trim_start_matches(['"', '\''])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || "-_:.".contains(*c))
+            .collect();
+        Encoding::for_label(charset.as_bytes())
+    })
+}
+
+fn parse_selector(selector: &str) -> Result<Selector, SdkError> {
+    Selector::parse(selector)
+        .map_err(|e| SdkError::Parse(format!("invalid selector `{}` - {}", selector, e)))
+}
+
+pub(crate) fn parse_selectors(selectors: &[String]) -> Result<Vec<Selector>, SdkError> {
+    selectors
+        .iter()
+        .map(|selector| parse_selector(selector))
+        .collect()
+}
+
+/// Returns the first element matching the selector; `SdkError::Parse` if nothing matches.
+pub(crate) fn select_first<'a>(
+    document: &'a Html,
+    selector: &str,
+) -> Result<ElementRef<'a>, SdkError> {
+    let parsed_selector = parse_selector(selector)?;
+    document
+        .select(&parsed_selector)
+        .next()
+        .ok_or_else(|| SdkError::Parse(format!("selector `{}` matched nothing", selector)))
+}
+
+/// Collects the link targets of every element matching the given CSS selector.
+///
+/// The `src` attribute is preferred (e.g. `<img>`, `<video>`), falling back to `href`
+/// (e.g. `<a>`, `<link>`); for `<img>` the highest resolution candidate is picked (see
+/// [`parse_images`]). Elements without either attribute are skipped. The values are returned
+/// as-is (i.e. relative links are NOT resolved, see [`resolve_base_url`]), in document order.
+///
+/// # Errors
+///
+/// Returns `SdkError::Parse` if the selector is invalid.
+///
+/// # Example
+///
+/// ```
+/// use mangater_sdk::util::html_parsing::parse_resource_links;
+///
+/// const CONTENT: &str = r#"<img src="cat.jpg"><a class="next" href="/page/2">next</a>"#;
+/// let links = parse_resource_links(CONTENT, "a.next").unwrap();
+/// assert_eq!(links, vec!["/page/2".to_string()]);
+/// ```
+pub fn parse_resource_links(content: &str, selector: &str) -> Result<Vec<String>, SdkError> {
+    let document = Html::parse_document(content);
+    let selector = parse_selector(selector)?;
+
+    let links = document
+        .select(&selector)
+        .filter_map(|element| {
+            if element.value().name() == "img" {
+                return image_source(&element);
+            }
+            element
+                .value()
+                .attr("src")
+                .or_else(|| element.value().attr("href"))
+                .map(|link| link.trim().to_string())
+        })
+        .filter(|link| !link.is_empty())
+        .collect();
+    Ok(links)
+}
+
+/// Extracts the plain-text content of the first element matching the given CSS selector,
+/// leaving out the elements matching any of the `exclude` selectors (e.g. `nav`, `.infobox`).
+///
+/// # Errors
+///
+/// Returns `SdkError::Parse` if any selector is invalid or nothing on the page matches `selector`.
+pub fn parse_plain_text(
+    content: &str,
+    selector: &str,
+    exclude: &[String],
+) -> Result<String, SdkError> {
+    let document = Html::parse_document(content);
+    let element = select_first(&document, selector)?;
+    let exclude = parse_selectors(exclude)?;
+    Ok(clean_text(&element, &exclude))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::entity::HttpConfig;
+    use crate::util::fixture::Fixtures;
+    use crate::util::http::HttpFetcher;
     use std::fs;
+    use std::sync::Arc;
 
     use scraper::{Html, Selector};
     use tokio::fs::File;
@@ -104,39 +474,27 @@ mod tests {
 
     #[tokio::test]
     async fn test_parse_html_01() -> Result<(), Box<dyn std::error::Error>> {
-        //let client = Client::new(); // without user agent, the request will be blocked by the server
-        let client = reqwest::Client::builder()
-            .user_agent("mangater-sdk/0.1 (+https://github.com/quoeamaster/mangater)")
-            .build()?;
-
-        let response = client
-            .get("https://en.wikipedia.org/wiki/NoSQL")
-            .send()
-            .await?
-            .error_for_status()?;
-
-        let body = response.text().await?;
+        // replays the recorded page (MANGATER_FIXTURES=record to record it again)
+        let fixtures = Arc::new(Fixtures::from_env("testdata/fixtures")?);
+        let fetcher = HttpFetcher::new(&HttpConfig::default(), None)?.with_fixtures(fixtures);
+
+        let body = fetcher
+            .fetch_text("https://en.wikipedia.org/wiki/NoSQL", None)
+            .await?;
         assert!(!body.is_empty());
 
         let document = Html::parse_document(&body);
-        let mut file = File::create("testdata/wikipedia_nosql_scrap_result.txt").await?;
 
         // parse only images
         let selector = Selector::parse("img").unwrap();
         let images = document.select(&selector).collect::<Vec<_>>();
+        assert!(!images.is_empty());
 
         for image in images {
             let src = image.value().attr("src").unwrap();
-            let class = image.value().attr("class");
-            if class.is_some() {
-                file.write_all(
-                    format!("Image src: {}, class: {}\n", src, class.unwrap()).as_bytes(),
-                )
-                .await?;
-                println!("Image src: {}, class: {}", src, class.unwrap());
+            if let Some(class) = image.value().attr("class") {
+                println!("Image src: {}, class: {}", src, class);
             } else {
-                file.write_all(format!("Image src: {}\n", src).as_bytes())
-                    .await?;
                 println!("Image src: {}", src);
             }
         }
@@ -145,25 +503,14 @@ mod tests {
         let selector = Selector::parse("#mw-content-text").unwrap();
         let content = document.select(&selector).next().unwrap();
         // recursive way to clean the text
-        let text = clean_text(&content);
-        file.write_all(text.as_bytes()).await?;
+        let text = clean_text(&content, &[]);
+        assert!(text.contains("NoSQL"));
 
         // brute force way... not too accurate...
         // let text = content.text().map(str::trim)
         //     .filter(|s| !s.is_empty())
         //     .collect::<Vec<_>>()
         //     .join(" ");
-        // file.write_all(format!("Content: {}\n", text).as_bytes()).await?;
-
-        // works if no parsing required...
-        // let mut stream = response.bytes_stream();
-        //
-        // while let Some(chunk) = stream.next().await {
-        //     let chunk = chunk?;
-        //     file.write_all(&chunk).await?;
-        // }
-
-        file.flush().await?;
 
         Ok(())
     }
@@ -171,9 +518,15 @@ mod tests {
     #[test]
     fn test_parse_images() {
         let content = fs::read_to_string("testdata/wikipedia_nosql_local.html.txt").unwrap();
-        let images = parse_images(content);
+        let images = parse_images(&content, "https://en.wikipedia.org/wiki/NoSQL").unwrap();
 
         assert!(!images.is_empty());
+        assert!(images.iter().all(|image| image.src.starts_with("https://")));
+        assert_eq!(
+            images[0].src,
+            "https://en.wikipedia.org/static/images/icons/enwiki-25.svg"
+        );
+        assert_eq!((images[0].width, images[0].height), (Some(50), Some(50)));
         for image in images {
             println!(
                 "* Image src: {}\r\n - inner_html: {}",
@@ -182,19 +535,171 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_images_resolve() {
+        let content = r#"
+            <html><head><base href="/media/"></head><body>
+                <img src="//upload.example.org/a.png" alt="protocol relative" width="220px" height="auto">
+                <img src="b.png" srcset="b-320.png 320w, b-1280.png 1280w, b-640.png 640w">
+                <img src="c.png" srcset="c-2x.png 2x, c-3x.png 3x">
+                <img src="data:image/gif;base64,R0lGOD" data-src="lazy.png" data-srcset="lazy-2x.png 2x">
+                <img src="data:image/gif;base64,R0lGOD">
+                <img alt="no source">
+            </body></html>
+        "#;
+        let images = parse_images(content, "https://example.org/wiki/Manga").unwrap();
+        let srcs: Vec<&str> = images.iter().map(|image| image.src.as_str()).collect();
+        assert_eq!(
+            srcs,
+            vec![
+                "https://upload.example.org/a.png",
+                "https://example.org/media/b-1280.png",
+                "https://example.org/media/c-3x.png",
+                "https://example.org/media/lazy-2x.png",
+            ]
+        );
+        assert_eq!(images[0].alt.as_deref(), Some("protocol relative"));
+        assert_eq!(images[0].width, Some(220));
+        assert_eq!(images[0].height, None);
+        assert_eq!(images[1].alt, None);
+
+        // the same candidates for the engine's resource links
+        let links = parse_resource_links(content, "img").unwrap();
+        assert_eq!(links[1], "b-1280.png");
+        assert_eq!(links[3], "lazy-2x.png");
+
+        assert!(matches!(
+            parse_images(content, "not a url"),
+            Err(SdkError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_srcset() {
+        assert_eq!(
+            parse_srcset("a.png, b.png 2x,c.png 1.5x , d,e.png 640w, f.png bogus"),
+            vec![
+                ("a.png".to_string(), ImageDescriptor::Density(1.0)),
+                ("b.png".to_string(), ImageDescriptor::Density(2.0)),
+                ("c.png".to_string(), ImageDescriptor::Density(1.5)),
+                ("d,e.png".to_string(), ImageDescriptor::Width(640)),
+            ]
+        );
+        assert!(parse_srcset(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_resource_links() {
+        let content = r#"
+            <img src="cat.jpg"><img alt="no-src">
+            <a class="next" href=" /page/2 ">next</a>
+            <video src="clip.mp4"></video>
+        "#;
+        let links = parse_resource_links(content, "img, video").unwrap();
+        assert_eq!(links, vec!["cat.jpg".to_string(), "clip.mp4".to_string()]);
+
+        let links = parse_resource_links(content, "a.next").unwrap();
+        assert_eq!(links, vec!["/page/2".to_string()]);
+
+        match parse_resource_links(content, "a[") {
+            Err(SdkError::Parse(msg)) => assert!(msg.contains("invalid selector")),
+            other => panic!("Expected SdkError::Parse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_plain_text() {
+        let content = fs::read_to_string("testdata/wikipedia_nosql_local.html.txt").unwrap();
+        let text = parse_plain_text(&content, "#mw-content-text", &[]).unwrap();
+        assert!(text.contains("NoSQL"));
+
+        match parse_plain_text(&content, "#not-exists", &[]) {
+            Err(SdkError::Parse(msg)) => assert!(msg.contains("matched nothing")),
+            other => panic!("Expected SdkError::Parse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_plain_text_exclude() {
+        let content = r#"
+            <div id="content">
+                <nav>Home | Next</nav>
+                <p>Chapter <b>one</b></p>
+                <table class="infobox"><tr><td>Author</td></tr></table>
+                <footer>Copyright</footer>
+            </div>
+        "#;
+        let exclude = vec!["nav".to_string(), ".infobox, footer".to_string()];
+        let text = parse_plain_text(content, "#content", &exclude).unwrap();
+        assert_eq!(
+            text.split_whitespace().collect::<Vec<_>>(),
+            ["Chapter", "one"]
+        );
+
+        match parse_plain_text(content, "#content", &["nav[".to_string()]) {
+            Err(SdkError::Parse(msg)) => assert!(msg.contains("invalid selector")),
+            other => panic!("Expected SdkError::Parse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_html() {
+        // "日本" in Shift_JIS
+        let shift_jis = b"<html><body>\x93\xfa\x96\x7b</body></html>";
+        assert!(decode_html(shift_jis, Some("text/html; charset=\"Shift_JIS\"")).contains("日本"));
+
+        // http-equiv declaration
+        let euc_jp = b"<html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=EUC-JP\"></head><body>\xc6\xfc\xcb\xdc</body></html>";
+        assert!(decode_html(euc_jp, None).contains("日本"));
+
+        // header wins over <meta>
+        let latin1 = b"<meta charset=\"utf-8\">caf\xe9";
+        assert!(decode_html(latin1, Some("text/html; charset=iso-8859-1")).contains("café"));
+
+        // BOM wins over everything else
+        let bom = b"\xef\xbb\xbf<meta charset=\"iso-8859-1\">caf\xc3\xa9";
+        assert!(decode_html(bom, Some("text/html; charset=iso-8859-1")).contains("café"));
+
+        // default to utf-8, invalid sequences replaced instead of failing
+        let invalid = b"caf\xc3\xa9 \xff";
+        assert_eq!(decode_html(invalid, Some("text/html")), "café \u{FFFD}");
+    }
+
     #[tokio::test]
     async fn test_parse_plain_text_and_images() -> Result<(), Box<dyn std::error::Error>> {
         let content = fs::read_to_string("testdata/wikipedia_nosql_local.html.txt").unwrap();
-        let plain_text_and_images = parse_plain_text_and_images(content);
+        let pattern = PatternMatchResult {
+            pattern: "#mw-content-text".to_string(),
+            pattern_type: crate::entity::PatternType::Content,
+            resource_string: None,
+        };
+        let page_url = "https://en.wikipedia.org/wiki/NoSQL";
+        let plain_text_and_images = parse_plain_text_and_images(&content, page_url, &pattern, &[])?;
 
         assert!(!plain_text_and_images.text.is_empty());
         assert!(!plain_text_and_images.images.is_empty());
 
+        // citations and edit links left out
+        let exclude = vec![".reference, .mw-editsection".to_string()];
+        let excluded = parse_plain_text_and_images(&content, page_url, &pattern, &exclude)?;
+        assert!(excluded.text.len() < plain_text_and_images.text.len());
+        assert!(!excluded.text.contains("[edit]"));
+        assert!(excluded.images.len() <= plain_text_and_images.images.len());
+
+        // any other site, no panic on a missing match
+        let pattern = PatternMatchResult {
+            pattern: "article.chapter".to_string(),
+            ..pattern
+        };
+        assert!(matches!(
+            parse_plain_text_and_images(&content, page_url, &pattern, &[]),
+            Err(SdkError::Parse(_))
+        ));
+
         // write it out to a file for debugging... etc
         let mut file = File::create("testdata/wikipedia_nosql_local.html.result.txt").await?;
 
-        file.write_all(format!("plain-text content:\r\n").as_bytes())
-            .await?;
+        file.write_all("plain-text content:\r\n".as_bytes()).await?;
         file.write_all(plain_text_and_images.text.as_bytes())
             .await?;
 
@@ -0,0 +1,404 @@
+use crate::entity::{CacheMode, DownloadedResource, ResourceMetadata};
+use crate::errors::SdkError;
+use crate::storage::write_atomic;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// On-disk cache of HTTP responses, consulted by [`HttpFetcher`](crate::util::http::HttpFetcher)
+/// before hitting the network.
+///
+/// Every response is kept under `{root}/{hash[..2]}/{hash}` (the SHA-256 of the requested url):
+/// the body as-is in `{hash}.body`, its metadata (validators, freshness) in `{hash}.json`.
+///
+/// How long a response may be reused without asking the server follows its `Cache-Control`
+/// (`max-age`, `no-cache`, `no-store`) or `Expires` headers; stale responses carrying an `ETag` or
+/// `Last-Modified` are revalidated with `If-None-Match` / `If-Modified-Since`. See [`CacheMode`]
+/// for the offline and refresh modes.
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    root: PathBuf,
+    mode: CacheMode,
+}
+
+/// Metadata of a cached response, stored next to its body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    final_url: String,
+    content_type: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// unix timestamp (seconds) of the last download or revalidation
+    stored_at: u64,
+    /// unix timestamp (seconds) until which the response is fresh; `None` if it has to be
+    /// revalidated before every reuse
+    fresh_until: Option<u64>,
+}
+
+/// A response found in the [`HttpCache`], fresh or stale.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub resource: DownloadedResource,
+    entry: CacheEntry,
+}
+
+impl CachedResponse {
+    /// whether the response may be reused without revalidating it with the server
+    pub fn is_fresh(&self) -> bool {
+        self.entry
+            .fresh_until
+            .is_some_and(|fresh_until| unix_now() < fresh_until)
+    }
+
+    /// when the response was downloaded (or last revalidated)
+    pub fn stored_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.entry.stored_at)
+    }
+}
+
+/// Caching directives of a response, from its `Cache-Control` (or else `Expires`) header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    /// `no-store` - the response must not be cached at all
+    pub no_store: bool,
+    /// how long the response stays fresh; `None` if it has to be revalidated before every reuse
+    /// (`no-cache`, or no freshness information given)
+    pub max_age: Option<Duration>,
+}
+
+impl CacheControl {
+    /// Parses the `Cache-Control` and `Expires` header values of a response;
+    /// `max-age` takes precedence over `Expires`.
+    pub fn parse(cache_control: Option<&str>, expires: Option<&str>) -> Self {
+        let mut no_store = false;
+        let mut no_cache = false;
+        let mut max_age = None;
+        for directive in cache_control.unwrap_or_default().split(',') {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "no-store" => no_store = true,
+                "no-cache" => no_cache = true,
+                // an invalid max-age means already stale
+                "max-age" => {
+                    max_age = Some(Duration::from_secs(
+                        value
+                            .and_then(|value| value.parse::<u64>().ok())
+                            .unwrap_or_default(),
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        if max_age.is_none() {
+            // a date in the past (or an invalid one, e.g. `0`) means already expired
+            max_age = expires
+                .and_then(|value| httpdate::parse_http_date(value.trim()).ok())
+                .and_then(|date| date.duration_since(SystemTime::now()).ok());
+        }
+        Self {
+            no_store,
+            max_age: if no_cache { None } else { max_age },
+        }
+    }
+}
+
+impl HttpCache {
+    /// Opens (or creates) the cache under the given folder.
+    pub fn open(root: impl Into<PathBuf>, mode: CacheMode) -> Result<Self, SdkError> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root, mode })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        self.mode
+    }
+
+    /// Returns the cached response of the url (fresh or stale), if any.
+    pub async fn lookup(&self, url: &str) -> Option<CachedResponse> {
+        let (entry_path, body_path) = self.entry_paths(url);
+        let entry = tokio::fs::read(&entry_path).await.ok()?;
+        let entry: CacheEntry = match serde_json::from_slice(&entry) {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::warn!("ignoring the corrupted cache entry of {} - {}", url, e);
+                return None;
+            }
+        };
+        if entry.url != url {
+            return None;
+        }
+        let bytes = tokio::fs::read(&body_path).await.ok()?;
+
+        let metadata = ResourceMetadata {
+            content_type: entry.content_type.clone(),
+            content_length: Some(bytes.len() as u64),
+            final_url: entry.final_url.clone(),
+            etag: entry.etag.clone(),
+            last_modified: entry.last_modified.clone(),
+        };
+        Some(CachedResponse {
+            resource: DownloadedResource { bytes, metadata },
+            entry,
+        })
+    }
+
+    /// Stores the downloaded response of the url, replacing the cached one;
+    /// a no-op for `no-store` responses.
+    pub async fn store(
+        &self,
+        url: &str,
+        resource: &DownloadedResource,
+        cache_control: &CacheControl,
+    ) -> Result<(), SdkError> {
+        if cache_control.no_store {
+            return Ok(());
+        }
+        let now = unix_now();
+        let entry = CacheEntry {
+            url: url.to_string(),
+            final_url: resource.metadata.final_url.clone(),
+            content_type: resource.metadata.content_type.clone(),
+            etag: resource.metadata.etag.clone(),
+            last_modified: resource.metadata.last_modified.clone(),
+            stored_at: now,
+            fresh_until: cache_control.max_age.map(|max_age| now + max_age.as_secs()),
+        };
+
+        let (entry_path, body_path) = self.entry_paths(url);
+        if let Some(parent) = entry_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // the body first, an entry never refers to a partially written body
+        write_atomic(&body_path, &resource.bytes).await?;
+        self.write_entry(&entry_path, &entry).await
+    }
+
+    /// Refreshes a cached response after the server confirmed it is still valid
+    /// (`304 Not Modified`); the validators sent along with the confirmation replace the cached ones.
+    pub async fn revalidated(
+        &self,
+        cached: &mut CachedResponse,
+        validators: &ResourceMetadata,
+        cache_control: &CacheControl,
+    ) -> Result<(), SdkError> {
+        let now = unix_now();
+        let entry = &mut cached.entry;
+        entry.stored_at = now;
+        entry.fresh_until = cache_control.max_age.map(|max_age| now + max_age.as_secs());
+        if validators.etag.is_some() {
+            entry.etag = validators.etag.clone();
+        }
+        if validators.last_modified.is_some() {
+            entry.last_modified = validators.last_modified.clone();
+        }
+        cached.resource.metadata.etag = entry.etag.clone();
+        cached.resource.metadata.last_modified = entry.last_modified.clone();
+
+        if cache_control.no_store {
+            return self.remove(&entry.url).await;
+        }
+        let (entry_path, _) = self.entry_paths(&entry.url);
+        self.write_entry(&entry_path, entry).await
+    }
+
+    /// Removes the cached response of the url, if any.
+    pub async fn remove(&self, url: &str) -> Result<(), SdkError> {
+        let (entry_path, body_path) = self.entry_paths(url);
+        for path in [entry_path, body_path] {
+            match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_entry(&self, path: &Path, entry: &CacheEntry) -> Result<(), SdkError> {
+        let entry = serde_json::to_vec_pretty(entry)
+            .map_err(|e| SdkError::Storage(std::io::Error::other(e)))?;
+        write_atomic(path, &entry).await
+    }
+
+    /// paths of the metadata and the body of the url's entry
+    fn entry_paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let hash: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let folder = self.root.join(&hash[..2]);
+        (
+            folder.join(format!("{}.json", hash)),
+            folder.join(format!("{}.body", hash)),
+        )
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "mangater-http-cache-{}-{}-{}",
+            name,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    fn resource(body: &[u8], etag: Option<&str>) -> DownloadedResource {
+        DownloadedResource {
+            bytes: body.to_vec(),
+            metadata: ResourceMetadata {
+                content_type: Some("text/html".to_string()),
+                content_length: Some(body.len() as u64),
+                final_url: "https://example.com/final".to_string(),
+                etag: etag.map(str::to_string),
+                last_modified: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_cache_control_parse() {
+        let parsed = CacheControl::parse(Some("public, max-age=600"), None);
+        assert_eq!(parsed.max_age, Some(Duration::from_secs(600)));
+        assert!(!parsed.no_store);
+
+        // no-cache wins over max-age: always revalidate
+        let parsed = CacheControl::parse(Some("no-cache, max-age=600"), None);
+        assert_eq!(parsed.max_age, None);
+
+        let parsed = CacheControl::parse(Some("private, No-Store"), None);
+        assert!(parsed.no_store);
+
+        // max-age takes precedence over expires
+        let expires = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(3600));
+        let parsed = CacheControl::parse(Some("max-age=0"), Some(&expires));
+        assert_eq!(parsed.max_age, Some(Duration::ZERO));
+
+        let parsed = CacheControl::parse(None, Some(&expires));
+        assert!(parsed.max_age.unwrap() > Duration::from_secs(3500));
+
+        // expired, or invalid
+        let parsed = CacheControl::parse(None, Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(parsed.max_age, None);
+        assert_eq!(
+            CacheControl::parse(None, Some("0")),
+            CacheControl::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_and_lookup() -> Result<(), SdkError> {
+        let root = temp_root("store");
+        let cache = HttpCache::open(&root, CacheMode::Normal)?;
+        let url = "https://example.com/chapter/1";
+        assert!(cache.lookup(url).await.is_none());
+
+        let fresh = CacheControl {
+            no_store: false,
+            max_age: Some(Duration::from_secs(600)),
+        };
+        cache
+            .store(url, &resource(b"<html>1</html>", Some("\"v1\"")), &fresh)
+            .await?;
+        let cached = cache.lookup(url).await.expect("cached response");
+        assert!(cached.is_fresh());
+        assert_eq!(cached.resource.bytes, b"<html>1</html>".to_vec());
+        assert_eq!(cached.resource.metadata.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            cached.resource.metadata.final_url,
+            "https://example.com/final"
+        );
+        // other urls are not affected
+        assert!(cache
+            .lookup("https://example.com/chapter/2")
+            .await
+            .is_none());
+
+        // without freshness information, the response has to be revalidated
+        cache
+            .store(
+                url,
+                &resource(b"<html>2</html>", None),
+                &CacheControl::default(),
+            )
+            .await?;
+        let cached = cache.lookup(url).await.expect("cached response");
+        assert!(!cached.is_fresh());
+        assert_eq!(cached.resource.bytes, b"<html>2</html>".to_vec());
+
+        std::fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_store_and_revalidated() -> Result<(), SdkError> {
+        let root = temp_root("revalidated");
+        let cache = HttpCache::open(&root, CacheMode::Normal)?;
+        let url = "https://example.com/chapter/1";
+
+        let no_store = CacheControl {
+            no_store: true,
+            max_age: None,
+        };
+        cache
+            .store(url, &resource(b"secret", None), &no_store)
+            .await?;
+        assert!(cache.lookup(url).await.is_none());
+
+        cache
+            .store(
+                url,
+                &resource(b"body", Some("\"v1\"")),
+                &CacheControl::default(),
+            )
+            .await?;
+        let mut cached = cache.lookup(url).await.unwrap();
+        assert!(!cached.is_fresh());
+
+        let validators = ResourceMetadata {
+            etag: Some("\"v2\"".to_string()),
+            ..ResourceMetadata::default()
+        };
+        let fresh = CacheControl {
+            no_store: false,
+            max_age: Some(Duration::from_secs(60)),
+        };
+        cache.revalidated(&mut cached, &validators, &fresh).await?;
+        assert!(cached.is_fresh());
+        assert_eq!(cached.resource.metadata.etag.as_deref(), Some("\"v2\""));
+
+        let cached = cache.lookup(url).await.unwrap();
+        assert!(cached.is_fresh());
+        assert_eq!(cached.resource.bytes, b"body".to_vec());
+        assert_eq!(cached.resource.metadata.etag.as_deref(), Some("\"v2\""));
+
+        std::fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
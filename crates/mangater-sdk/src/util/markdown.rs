@@ -0,0 +1,592 @@
+use scraper::{node::Node, ElementRef, Html, Selector};
+use url::Url;
+
+use crate::errors::SdkError;
+use crate::util::html_parsing::{document_base_url, image_source, parse_selectors, select_first};
+
+/// placeholder for the indentation of nested blocks (e.g. list items); replaced with spaces once
+/// rendered, so that the line trimming done while rendering does not strip it.
+const INDENT: char = '\u{1}';
+
+/// Renders the first element matching the given CSS selector as (GitHub flavored) Markdown.
+///
+/// Unlike the plain-text extraction (see `util::html_parsing::parse_plain_text`), the structure
+/// is kept: headings (with their level), ordered and unordered (nested) lists, links and images
+/// (with absolute urls), emphasis, inline code and code blocks, blockquotes and tables.
+///
+/// # Arguments
+///
+/// * `content` - the HTML document
+/// * `base_url` - the url the document was fetched from; links are resolved against it
+///   (or the document's `<base href>`)
+/// * `selector` - CSS selector of the content, e.g. `#mw-content-text` or `article`
+/// * `exclude` - CSS selectors of the elements to leave out, e.g. `nav`, `.infobox`
+///
+/// # Errors
+///
+/// Returns `SdkError::Parse` if any selector or the `base_url` is invalid, or nothing on the page
+/// matches `selector`.
+///
+/// # Example
+///
+/// ```
+/// use mangater_sdk::util::markdown::parse_markdown;
+///
+/// const CONTENT: &str = r#"<article>
+///     <h2>Chapter 1</h2>
+///     <p>Read the <a href="/wiki/Manga">manga</a> <em>now</em>.</p>
+///     <ul><li>one</li><li>two</li></ul>
+/// </article>"#;
+/// let markdown = parse_markdown(CONTENT, "https://example.com/", "article", &[]).unwrap();
+/// assert_eq!(
+///     markdown,
+///     "## Chapter 1\n\nRead the [manga](https://example.com/wiki/Manga) *now*.\n\n- one\n- two"
+/// );
+/// ```
+pub fn parse_markdown(
+    content: &str,
+    base_url: &str,
+    selector: &str,
+    exclude: &[String],
+) -> Result<String, SdkError> {
+    let document = Html::parse_document(content);
+    let base_url = document_base_url(&document, base_url)?;
+    let element = select_first(&document, selector)?;
+    let exclude = parse_selectors(exclude)?;
+
+    let renderer = MarkdownRenderer {
+        base_url: &base_url,
+        exclude: &exclude,
+    };
+    let markdown = normalize(&renderer.render_element(&element), false);
+    Ok(markdown.replace(INDENT, " "))
+}
+
+struct MarkdownRenderer<'a> {
+    base_url: &'a Url,
+    exclude: &'a [Selector],
+}
+
+impl MarkdownRenderer<'_> {
+    fn render_children(&self, element: &ElementRef) -> String {
+        let mut markdown = String::new();
+        for node in element.children() {
+            match node.value() {
+                Node::Text(text) => {
+                    let text = escape(&collapse_whitespace(text));
+                    if markdown.is_empty() || markdown.ends_with('\n') {
+                        markdown.push_str(&escape_line_start(&text));
+                    } else {
+                        markdown.push_str(&text);
+                    }
+                }
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(node) {
+                        markdown.push_str(&self.render_element(&child));
+                    }
+                }
+                _ => {}
+            }
+        }
+        markdown
+    }
+
+    fn render_element(&self, element: &ElementRef) -> String {
+        if self
+            .exclude
+            .iter()
+            .any(|selector| selector.matches(element))
+        {
+            return String::new();
+        }
+
+        let name = element.value().name();
+        match name {
+            "script" | "style" | "noscript" | "template" | "head" | "iframe" | "svg" => {
+                String::new()
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = single_line(&self.render_children(element));
+                if text.is_empty() {
+                    return String::new();
+                }
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                block(&format!("{} {}", "#".repeat(level), text))
+            }
+            "br" => "\\\n".to_string(),
+            "hr" => block("---"),
+            "strong" | "b" => wrap_inline(&self.render_children(element), "**"),
+            "em" | "i" => wrap_inline(&self.render_children(element), "*"),
+            "del" | "s" | "strike" => wrap_inline(&self.render_children(element), "~~"),
+            "code" | "kbd" | "samp" | "tt" => inline_code(&element.text().collect::<String>()),
+            "pre" => code_block(element),
+            "a" => self.render_link(element),
+            "img" => self.render_image(element),
+            "ul" | "ol" => self.render_list(element),
+            "blockquote" => self.render_blockquote(element),
+            "table" => self.render_table(element),
+            "p" | "div" | "section" | "article" | "main" | "header" | "footer" | "nav"
+            | "aside" | "figure" | "figcaption" | "dl" | "dt" | "dd" | "li" | "address"
+            | "details" | "summary" | "caption" | "body" | "html" => {
+                block(&self.render_children(element))
+            }
+            _ => self.render_children(element),
+        }
+    }
+
+    fn render_link(&self, element: &ElementRef) -> String {
+        let text = single_line(&self.render_children(element));
+        let url = element
+            .value()
+            .attr("href")
+            .map(str::trim)
+            .filter(|href| !href.is_empty() && !href.starts_with("javascript:"))
+            .and_then(|href| self.base_url.join(href).ok());
+        match url {
+            // e.g. an anchor without target, keep the text only
+            None => text,
+            // e.g. an icon link, nothing to show
+            Some(_) if text.is_empty() => String::new(),
+            Some(url) => format!("[{}]({})", text, destination(&url)),
+        }
+    }
+
+    fn render_image(&self, element: &ElementRef) -> String {
+        let url = image_source(element).and_then(|src| self.base_url.join(&src).ok());
+        match url {
+            Some(url) => {
+                let alt = element.value().attr("alt").unwrap_or("");
+                format!(
+                    "![{}]({})",
+                    escape(&collapse_whitespace(alt)).trim(),
+                    destination(&url)
+                )
+            }
+            None => String::new(),
+        }
+    }
+
+    fn render_list(&self, element: &ElementRef) -> String {
+        let ordered = element.value().name() == "ol";
+        let mut number = element
+            .value()
+            .attr("start")
+            .and_then(|start| start.trim().parse::<i64>().ok())
+            .unwrap_or(1);
+
+        let mut items = Vec::new();
+        for child in element.children().filter_map(ElementRef::wrap) {
+            if child.value().name() != "li" {
+                // e.g. a nested list placed directly within the list
+                items.push(indent(&normalize(&self.render_element(&child), true), "  "));
+                continue;
+            }
+            let marker = if ordered {
+                format!("{}. ", number)
+            } else {
+                "- ".to_string()
+            };
+            number += 1;
+
+            // tight list, no blank line between the blocks of an item
+            let content = normalize(&self.render_children(&child), true);
+            let mut lines = content.lines();
+            let first = lines.next().unwrap_or("");
+            let rest: Vec<&str> = lines.collect();
+            let mut item = format!("{}{}", marker, first).trim_end().to_string();
+            if !rest.is_empty() {
+                item.push('\n');
+                item.push_str(&indent(&rest.join("\n"), &marker));
+            }
+            items.push(item);
+        }
+        block(&items.join("\n"))
+    }
+
+    fn render_blockquote(&self, element: &ElementRef) -> String {
+        let content = normalize(&self.render_children(element), false);
+        let quoted: Vec<String> = content
+            .lines()
+            .map(|line| {
+                if line.is_empty() {
+                    ">".to_string()
+                } else {
+                    format!("> {}", line)
+                }
+            })
+            .collect();
+        block(&quoted.join("\n"))
+    }
+
+    fn render_table(&self, element: &ElementRef) -> String {
+        let mut caption = String::new();
+        let mut rows: Vec<Vec<String>> = Vec::new();
+
+        for child in element.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                "caption" => caption = single_line(&self.render_children(&child)),
+                "thead" | "tbody" | "tfoot" => rows.extend(
+                    child
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .filter(|row| row.value().name() == "tr")
+                        .map(|row| self.render_row(&row)),
+                ),
+                "tr" => rows.push(self.render_row(&child)),
+                _ => {}
+            }
+        }
+        rows.retain(|row| !row.is_empty());
+        if rows.is_empty() {
+            return block(&caption);
+        }
+
+        // GFM tables need a header row; the first row is used as such
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut lines = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<&str> = (0..columns)
+                .map(|column| row.get(column).map(String::as_str).unwrap_or(""))
+                .collect();
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+
+        let table = lines.join("\n");
+        if caption.is_empty() {
+            block(&table)
+        } else {
+            block(&format!("{}\n\n{}", caption, table))
+        }
+    }
+
+    fn render_row(&self, row: &ElementRef) -> Vec<String> {
+        row.children()
+            .filter_map(ElementRef::wrap)
+            .filter(|cell| matches!(cell.value().name(), "th" | "td"))
+            .map(|cell| single_line(&self.render_children(&cell)).replace('|', "\\|"))
+            .collect()
+    }
+}
+
+/// Renders a `<pre>` element as a fenced code block; the language is taken from a
+/// `language-*` / `lang-*` class of the `<pre>` or its `<code>`.
+fn code_block(element: &ElementRef) -> String {
+    let language = std::iter::once(*element)
+        .chain(
+            element
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|child| child.value().name() == "code"),
+        )
+        .flat_map(|element| element.value().classes())
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+        })
+        .unwrap_or("")
+        .to_string();
+
+    let code = element.text().collect::<String>();
+    let code = code.trim_matches('\n');
+    let fence = if code.contains("```") { "~~~~" } else { "```" };
+    format!("\n\n{}{}\n{}\n{}\n\n", fence, language, code, fence)
+}
+
+fn inline_code(code: &str) -> String {
+    let code = collapse_whitespace(code);
+    let code = code.trim();
+    if code.is_empty() {
+        String::new()
+    } else if code.contains('`') {
+        format!("`` {} ``", code)
+    } else {
+        format!("`{}`", code)
+    }
+}
+
+/// wraps inline content with the given marker (e.g. `**`), keeping the surrounding whitespace
+/// outside of the markers as Markdown requires
+fn wrap_inline(content: &str, marker: &str) -> String {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return content.to_string();
+    }
+    let leading = if content.starts_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+    let trailing = if content.ends_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+    format!("{}{}{}{}{}", leading, marker, trimmed, marker, trailing)
+}
+
+fn block(content: &str) -> String {
+    format!("\n\n{}\n\n", content)
+}
+
+/// indents every non-empty line with the width of the given marker (e.g. a list item's `1. `)
+fn indent(content: &str, marker: &str) -> String {
+    let indentation: String = std::iter::repeat_n(INDENT, marker.len()).collect();
+    content
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", indentation, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// a link / image destination; wrapped in `<>` if it would otherwise break the Markdown syntax
+fn destination(url: &Url) -> String {
+    let url = url.as_str();
+    if url.contains(['(', ')', ' ']) {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// renders the content on a single line, e.g. for headings and table cells
+fn single_line(content: &str) -> String {
+    normalize(&content.replace("\\\n", " "), true)
+        .lines()
+        .map(|line| line.trim_start_matches(INDENT))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes the marker the text would start a block with at the start of a line: a heading
+/// (`#`), a quote (`>`), a list item (`-`, `+`, `1.`, `1)`); e.g. `1\. place`.
+fn escape_line_start(text: &str) -> String {
+    let content = text.trim_start();
+    let indent = &text[..text.len() - content.len()];
+    let digits = content.len()
+        - content
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    let marker = match content[digits..].chars().next() {
+        Some('#' | '>' | '-' | '+') if digits == 0 => 0,
+        Some('.' | ')') if digits > 0 => digits,
+        _ => return text.to_string(),
+    };
+    format!("{}{}\\{}", indent, &content[..marker], &content[marker..])
+}
+
+/// Trims every line (outside of code blocks) and collapses consecutive blank lines into one;
+/// `tight` removes the blank lines altogether (e.g. within a list item).
+fn normalize(markdown: &str, tight: bool) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut blank = false;
+
+    for line in markdown.lines() {
+        let content = line.trim().trim_start_matches(INDENT);
+        if let Some(opening) = fence {
+            if content == opening {
+                fence = None;
+                lines.push(line.trim());
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        if content.is_empty() {
+            blank = !tight && !lines.is_empty();
+            continue;
+        }
+        if blank {
+            lines.push("");
+            blank = false;
+        }
+        if content.starts_with("```") || content.starts_with("~~~") {
+            let length = content
+                .find(|c| c != '`' && c != '~')
+                .unwrap_or(content.len());
+            fence = Some(&content[..length]);
+        }
+        lines.push(line.trim());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    fn render(content: &str) -> String {
+        parse_markdown(content, "https://example.com/wiki/Page", "body", &[]).unwrap()
+    }
+
+    #[test]
+    fn test_headings_and_emphasis() {
+        let markdown = render(
+            "<h1>Title</h1><h3> Sub  <i>title</i> </h3>
+             <p>Some <b>bold </b>and <em>italic</em>, <del>gone</del>; 5 * 3 = snake_case<br>next line</p>
+             <hr><h4></h4>",
+        );
+        assert_eq!(
+            markdown,
+            "# Title\n\n### Sub *title*\n\nSome **bold** and *italic*, ~~gone~~; 5 \\* 3 = snake\\_case\\\nnext line\n\n---"
+        );
+    }
+
+    #[test]
+    fn test_escape_line_start() {
+        let markdown = render(
+            "<p># not a heading</p><p> &gt; not a quote</p><p>- not a list</p>
+             <p>+ nor this<br>1. nor this<br>2) nor this</p><p>2026. A year, a #tag -</p>",
+        );
+        assert_eq!(
+            markdown,
+            "\\# not a heading\n\n\\> not a quote\n\n\\- not a list\n\n\\+ nor this\\\n1\\. nor this\\\n2\\) nor this\n\n2026\\. A year, a #tag -"
+        );
+    }
+
+    #[test]
+    fn test_links_and_images() {
+        let markdown = render(
+            r#"<p><a href="/wiki/Manga">Manga</a>, <a href="Other_(disambiguation)">other</a>,
+               <a href="javascript:void(0)">js</a>, <a name="anchor">anchor</a>, <a href="/icon"></a></p>
+               <p><img src="//upload.example.com/a.png" alt="A [cover]"></p>"#,
+        );
+        assert_eq!(
+            markdown,
+            "[Manga](https://example.com/wiki/Manga), [other](<https://example.com/wiki/Other_(disambiguation)>), js, anchor,\n\n![A \\[cover\\]](https://upload.example.com/a.png)"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let markdown = render(
+            r#"<ul>
+                 <li>one</li>
+                 <li>two
+                   <ol start="3"><li>three</li><li><p>four</p><p>still four</p></li></ol>
+                 </li>
+               </ul>
+               <ol><li>first</li><li>second</li></ol>"#,
+        );
+        assert_eq!(
+            markdown,
+            "- one\n- two\n  3. three\n  4. four\n     still four\n\n1. first\n2. second"
+        );
+    }
+
+    #[test]
+    fn test_code_and_blockquote() {
+        let markdown = render(
+            "<p>Call <code>parse()</code> or <code>a`b</code></p>
+             <pre class=\"language-rust\"><code>fn main() {\n    println!(\"hi\");\n\n}\n</code></pre>
+             <blockquote><p>quoted</p><p>twice</p></blockquote>",
+        );
+        assert_eq!(
+            markdown,
+            "Call `parse()` or `` a`b ``\n\n```rust\nfn main() {\n    println!(\"hi\");\n\n}\n```\n\n> quoted\n>\n> twice"
+        );
+    }
+
+    #[test]
+    fn test_code_block_in_list() {
+        let markdown = render("<ul><li>run<pre>cargo  build\n  --release</pre></li></ul>");
+        assert_eq!(
+            markdown,
+            "- run\n  ```\n  cargo  build\n    --release\n  ```"
+        );
+    }
+
+    #[test]
+    fn test_tables() {
+        let markdown = render(
+            r#"<table>
+                 <caption>Volumes</caption>
+                 <thead><tr><th>No.</th><th>Title</th></tr></thead>
+                 <tbody>
+                   <tr><td>1</td><td>A | B</td></tr>
+                   <tr><td>2</td><td><a href="/v2">Two</a></td><td>extra</td></tr>
+                 </tbody>
+               </table>
+               <table><tr><td>no header</td></tr></table>"#,
+        );
+        assert_eq!(
+            markdown,
+            "Volumes\n\n| No. | Title |  |\n| --- | --- | --- |\n| 1 | A \\| B |  |\n| 2 | [Two](https://example.com/v2) | extra |\n\n| no header |\n| --- |"
+        );
+    }
+
+    #[test]
+    fn test_exclude_and_errors() {
+        let content = "<div id=\"c\"><nav>menu</nav><p>text</p><script>x()</script></div>";
+        let markdown =
+            parse_markdown(content, "https://example.com/", "#c", &["nav".to_string()]).unwrap();
+        assert_eq!(markdown, "text");
+
+        assert!(matches!(
+            parse_markdown(content, "https://example.com/", "#missing", &[]),
+            Err(SdkError::Parse(_))
+        ));
+        assert!(matches!(
+            parse_markdown(content, "https://example.com/", "#c", &["[".to_string()]),
+            Err(SdkError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_markdown_wikipedia() {
+        let content = fs::read_to_string("testdata/wikipedia_nosql_local.html.txt").unwrap();
+        let markdown = parse_markdown(
+            &content,
+            "https://en.wikipedia.org/wiki/NoSQL",
+            "#mw-content-text",
+            &[".mw-editsection".to_string()],
+        )
+        .unwrap();
+
+        assert!(markdown.contains("\n## "));
+        assert!(markdown.contains("](https://en.wikipedia.org/wiki/"));
+        assert!(markdown.contains("\n| --- |"));
+        assert!(!markdown.contains(INDENT));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::entity::RateLimitConfig;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Politeness controls for the requests made against a single domain key (plugin);
+/// a token bucket for the request rate plus a cap on the number of in-flight requests.
+///
+/// # Example
+/// ```
+/// use mangater_sdk::entity::RateLimitConfig;
+/// use mangater_sdk::util::rate_limit::RateLimiter;
+///
+/// # async fn run() {
+/// let limiter = RateLimiter::new(&RateLimitConfig {
+///     requests_per_second: Some(2.0),
+///     burst: 1,
+///     max_concurrency: 4,
+/// });
+/// // waits for a free slot and a token before sending the request
+/// let _permit = limiter.acquire().await;
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: Option<Mutex<TokenBucket>>,
+    concurrency: Arc<Semaphore>,
+    max_concurrency: usize,
+}
+
+/// Held while a request is in-flight; the concurrency slot is released on drop.
+#[derive(Debug)]
+pub struct RateLimitPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let bucket = config
+            .requests_per_second
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| {
+                let capacity = config.burst.max(1) as f64;
+                Mutex::new(TokenBucket {
+                    capacity,
+                    tokens: capacity,
+                    refill_per_second: rate,
+                    last_refill: Instant::now(),
+                })
+            });
+        let max_concurrency = match config.max_concurrency {
+            0 => Semaphore::MAX_PERMITS,
+            max => max,
+        };
+
+        Self {
+            bucket,
+            concurrency: Arc::new(Semaphore::new(max_concurrency)),
+            max_concurrency,
+        }
+    }
+
+    /// maximum number of in-flight requests allowed
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// Waits until a concurrency slot is free and a token is available.
+    pub async fn acquire(&self) -> RateLimitPermit {
+        let permit = Arc::clone(&self.concurrency)
+            .acquire_owned()
+            .await
+            .expect("rate limiter semaphore is never closed");
+
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let wait = bucket.lock().unwrap().try_take();
+                match wait {
+                    None => break,
+                    Some(wait) => tokio::time::sleep(wait).await,
+                }
+            }
+        }
+        RateLimitPermit { _permit: permit }
+    }
+}
+
+impl TokenBucket {
+    /// Takes a token if available; otherwise returns how long to wait for the next token.
+    fn try_take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.refill_per_second,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_token_bucket() {
+        let limiter = RateLimiter::new(&RateLimitConfig {
+            requests_per_second: Some(20.0),
+            burst: 2,
+            max_concurrency: 0,
+        });
+
+        let started = Instant::now();
+        // 2 tokens available right away (burst), the other 4 at 20 per second
+        for _ in 0..6 {
+            limiter.acquire().await;
+        }
+        let elapsed = started.elapsed();
+        assert!(
+            elapsed >= Duration::from_millis(190),
+            "elapsed: {:?}",
+            elapsed
+        );
+        assert!(elapsed < Duration::from_secs(2), "elapsed: {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_unlimited_rate() {
+        let limiter = RateLimiter::new(&RateLimitConfig {
+            requests_per_second: None,
+            burst: 1,
+            max_concurrency: 0,
+        });
+        let started = Instant::now();
+        for _ in 0..100 {
+            limiter.acquire().await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_max_concurrency() {
+        let limiter = Arc::new(RateLimiter::new(&RateLimitConfig {
+            requests_per_second: None,
+            burst: 1,
+            max_concurrency: 2,
+        }));
+        assert_eq!(limiter.max_concurrency(), 2);
+
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+
+        // no free slot until a permit is dropped
+        let waiting = tokio::spawn({
+            let limiter = Arc::clone(&limiter);
+            async move { limiter.acquire().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .expect("permit should be granted once a slot is free")
+            .unwrap();
+    }
+}
@@ -1,146 +1,165 @@
+use crate::entity::DownloadedResource;
 use crate::errors::SdkError;
-use futures_util::StreamExt;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
-
-const DEFAULT_USER_AGENT: &str = "mangater-sdk/0.1 (+https://github.com/quoeamaster/mangater)";
-
+use crate::util::http::HttpFetcher;
+
+pub const DEFAULT_USER_AGENT: &str = "mangater-sdk/0.1 (+https://github.com/quoeamaster/mangater)";
+
+/// Downloads the given uri through the given [`HttpFetcher`]; the body is returned as raw bytes
+/// (no text decoding involved), hence safe for binary resources such as images and pdfs.
+///
+/// The fetcher carries the proxy, rate limits, cache and fixtures the request goes through;
+/// e.g. the one of a plugin (`Engine::fetcher_for` of mangater-core), or one of its own
+/// (`HttpFetcher::new(&HttpConfig::default(), None)`).
+///
+/// `user_agent` overrides the configured user agent for this request only.
 pub async fn download_resource(
+    fetcher: &HttpFetcher,
     uri: String,
     user_agent: Option<String>,
 ) -> Result<Vec<u8>, SdkError> {
-    let user_agent = user_agent.unwrap_or(DEFAULT_USER_AGENT.to_string());
-    let client = reqwest::Client::builder()
-        .user_agent(user_agent)
-        .build()
-        .map_err(|e| SdkError::Network(e.to_string()))?;
-
-    let response = client
-        .get(uri)
-        .send()
-        .await
-        .map_err(|e| SdkError::Network(e.to_string()))?
-        .error_for_status()
-        .map_err(|e| SdkError::Network(e.to_string()))?;
-
-    let body = response
-        .text()
-        .await
-        .map_err(|e| SdkError::Network(e.to_string()))?;
-
-    Ok(body.as_bytes().to_vec())
+    fetcher.fetch_bytes(&uri, user_agent).await
+}
+
+/// Downloads the given uri through the given [`HttpFetcher`]; returning the raw bytes
+/// along with the response metadata (content-type, final url after redirects, etag etc).
+///
+/// `user_agent` overrides the configured user agent for this request only.
+pub async fn download_resource_with_metadata(
+    fetcher: &HttpFetcher,
+    uri: String,
+    user_agent: Option<String>,
+) -> Result<DownloadedResource, SdkError> {
+    fetcher.fetch_resource(&uri, user_agent).await
 }
 
+/// Downloads the given uri as text through the given [`HttpFetcher`]; the body is decoded with
+/// the charset declared by the `Content-Type` header or the page's `<meta charset>`.
+///
+/// `user_agent` overrides the configured user agent for this request only.
+pub async fn download_text(
+    fetcher: &HttpFetcher,
+    uri: String,
+    user_agent: Option<String>,
+) -> Result<String, SdkError> {
+    fetcher.fetch_text(&uri, user_agent).await
+}
+
+/// Downloads the given uri into `file_path` through the given [`HttpFetcher`].
+///
+/// `user_agent` overrides the configured user agent for this request only.
 pub async fn download_resource_to_file(
+    fetcher: &HttpFetcher,
     uri: String,
     user_agent: Option<String>,
     file_path: String,
 ) -> Result<(), SdkError> {
-    let user_agent = user_agent.unwrap_or(DEFAULT_USER_AGENT.to_string());
-    let client = reqwest::Client::builder()
-        .user_agent(user_agent)
-        .build()
-        .map_err(|e| SdkError::Network(e.to_string()))?;
-
-    let response = client
-        .get(uri)
-        .send()
-        .await
-        .map_err(|e| SdkError::Network(e.to_string()))?
-        .error_for_status()
-        .map_err(|e| SdkError::Network(e.to_string()))?;
-
-    let mut file = File::create(file_path).await?;
-    let mut stream = response.bytes_stream();
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| SdkError::Network(e.to_string()))?;
-        file.write_all(&chunk)
-            .await
-            .map_err(|e| SdkError::Storage(e))?;
-    }
-    file.flush()
-        .await
-        .map_err(|e| SdkError::Storage(e.into()))?;
-
+    fetcher.fetch_to_file(&uri, user_agent, &file_path).await?;
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::entity::HttpConfig;
+    use crate::util::fixture::Fixtures;
     use futures::stream::{self};
     use futures_util::StreamExt;
     use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    const NOSQL_URL: &str = "https://en.wikipedia.org/wiki/NoSQL";
+
+    /// a fetcher replaying the recorded pages (MANGATER_FIXTURES=record to record them again)
+    fn fixtures_fetcher() -> Result<HttpFetcher, SdkError> {
+        let fixtures = Arc::new(Fixtures::from_env("testdata/fixtures")?);
+        Ok(HttpFetcher::new(&HttpConfig::default(), None)?.with_fixtures(fixtures))
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mangater-resource-{}-{}", std::process::id(), name))
+    }
 
     #[tokio::test]
     async fn test_download_resource() -> Result<(), SdkError> {
-        let uri = "https://en.wikipedia.org/wiki/NoSQL";
+        let fetcher = fixtures_fetcher()?;
 
-        let content = download_resource(uri.to_string(), None).await?;
+        let content = download_resource(&fetcher, NOSQL_URL.to_string(), None).await?;
         assert!(!content.is_empty());
-        assert_eq!(content.len() > 100000, true); // actual content is around 183886 (check /testdata/wikipedia_nosql_local.html.txt)
+        assert!(content.len() > 100000); // actual content is around 183886 (check /testdata/wikipedia_nosql_local.html.txt)
         println!("content length: {}", content.len());
 
+        let resource =
+            download_resource_with_metadata(&fetcher, NOSQL_URL.to_string(), None).await?;
+        assert_eq!(resource.bytes, content);
+        assert_eq!(
+            resource.metadata.content_type.as_deref(),
+            Some("text/html; charset=UTF-8")
+        );
+
         Ok(())
     }
 
     #[tokio::test]
     async fn test_download_resource_to_file() -> Result<(), SdkError> {
-        let uri = "https://en.wikipedia.org/wiki/NoSQL";
-        let file_path = "testdata/resource_test_download_resource_to_file.txt";
-
-        download_resource_to_file(uri.to_string(), None, file_path.to_string()).await?;
-
-        let content = fs::read_to_string(file_path)?;
+        let fetcher = fixtures_fetcher()?;
+        let file_path = temp_file("download_resource_to_file.txt");
+
+        download_resource_to_file(
+            &fetcher,
+            NOSQL_URL.to_string(),
+            None,
+            file_path.to_string_lossy().to_string(),
+        )
+        .await?;
+
+        let content = fs::read_to_string(&file_path)?;
         assert!(!content.is_empty());
-        assert_eq!(content.len() > 100000, true); // actual content is around 183886 (check /testdata/wikipedia_nosql_local.html.txt)
+        assert!(content.len() > 100000); // actual content is around 183886 (check /testdata/wikipedia_nosql_local.html.txt)
         println!("after-stream download - content length: {}", content.len());
 
+        fs::remove_file(&file_path)?;
         Ok(())
     }
 
     /// test downloading multiple resources in parallel using a stream approach.
     #[tokio::test]
     async fn test_download_resources_in_parallel() -> Result<(), SdkError> {
+        let fetcher = fixtures_fetcher()?;
+
         // declare a struct for holding url and file_path
         struct UrlFile {
             url: String,
-            file_path: String,
+            file_path: PathBuf,
         }
-        let urls = vec![
-            UrlFile {
-                url: "https://upload.wikimedia.org/wikipedia/commons/thumb/b/b7/Last.fm_software_screenshot.png/250px-Last.fm_software_screenshot.png".to_string(),
-                file_path: "testdata/parallel_download/250px-Last.fm_software_screenshot.png".to_string(),
-            },
-            UrlFile {
-                url: "https://en.wikipedia.org/static/images/icons/enwiki-25.svg".to_string(),
-                file_path: "testdata/parallel_download/enwiki-25.svg".to_string(),
-            },
-            UrlFile {
-                url: "https://en.wikipedia.org/w/resources/assets/mediawiki_compact.svg".to_string(),
-                file_path: "testdata/parallel_download/mediawiki_compact.svg".to_string(),
-            },
-        ];
+        let urls = (0..3)
+            .map(|index| UrlFile {
+                url: NOSQL_URL.to_string(),
+                file_path: temp_file(&format!("parallel_{}.html", index)),
+            })
+            .collect::<Vec<_>>();
 
+        let fetcher = &fetcher;
         let results = stream::iter(urls)
             .map(|url_file| async move {
-                let result = download_resource_to_file(
+                download_resource_to_file(
+                    fetcher,
                     url_file.url.to_string(),
                     None,
-                    url_file.file_path.to_string(),
+                    url_file.file_path.to_string_lossy().to_string(),
                 )
-                .await;
-                if let Err(err) = result {
-                    tracing::error!("error downloading resource: {} {:?}", url_file.url, err);
-                }
+                .await?;
+                Ok::<PathBuf, SdkError>(url_file.file_path)
             })
             .buffer_unordered(5)
             .collect::<Vec<_>>()
             .await;
 
+        let expected = fs::read("testdata/wikipedia_nosql_local.html.txt")?;
         for result in results {
-            println!("result: {:?}", result);
+            let file_path = result?;
+            assert_eq!(fs::read(&file_path)?, expected);
+            fs::remove_file(&file_path)?;
         }
 
         Ok(())
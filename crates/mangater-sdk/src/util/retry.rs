@@ -0,0 +1,148 @@
+use crate::entity::RetryConfig;
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+
+/// Exponential backoff policy for retrying transient failures (e.g. HTTP 429 / 503, timeouts).
+///
+/// The delay before retry `n` (0-based) is `initial_backoff * multiplier^n` capped at `max_backoff`;
+/// with jitter enabled, a random delay between half and the full computed value is used instead
+/// so that concurrent scrappers do not retry in lock-step.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    pub fn new(config: &RetryConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+            multiplier: config.multiplier.max(1.0),
+            jitter: config.jitter,
+        }
+    }
+
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self::new(&RetryConfig {
+            max_retries: 0,
+            ..RetryConfig::default()
+        })
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Returns the delay before the given retry attempt (0-based);
+    /// `None` if the attempt exceeds the max number of retries.
+    ///
+    /// A delay requested by the remote server (e.g. `Retry-After`) takes precedence over the
+    /// computed backoff; `None` is returned as well if it exceeds the max backoff, as retrying
+    /// any earlier would only be rejected again.
+    pub fn delay_for(&self, attempt: u32, requested: Option<Duration>) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        if let Some(requested) = requested {
+            return (requested <= self.max_backoff).then_some(requested);
+        }
+
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt as i32);
+        let backoff = Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()));
+        if self.jitter {
+            Some(backoff.mul_f64(0.5 + random_fraction() / 2.0))
+        } else {
+            Some(backoff)
+        }
+    }
+}
+
+/// Parses a `Retry-After` header value; either delay seconds (e.g. `120`)
+/// or an HTTP date (e.g. `Wed, 21 Oct 2015 07:28:00 GMT`).
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // a date in the past means "retry now"
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// random value within [0, 1); good enough for jitter without pulling in a rng crate.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(jitter: bool) -> RetryPolicy {
+        RetryPolicy::new(&RetryConfig {
+            max_retries: 4,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 500,
+            multiplier: 2.0,
+            jitter,
+        })
+    }
+
+    #[test]
+    fn test_delay_for_exponential_backoff() {
+        let policy = policy(false);
+        assert_eq!(policy.delay_for(0, None), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay_for(1, None), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay_for(2, None), Some(Duration::from_millis(400)));
+        // capped at max backoff
+        assert_eq!(policy.delay_for(3, None), Some(Duration::from_millis(500)));
+        // no more retries
+        assert_eq!(policy.delay_for(4, None), None);
+        assert_eq!(RetryPolicy::none().delay_for(0, None), None);
+    }
+
+    #[test]
+    fn test_delay_for_jitter() {
+        let policy = policy(true);
+        for _ in 0..50 {
+            let delay = policy.delay_for(2, None).unwrap();
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+        }
+    }
+
+    #[test]
+    fn test_delay_for_requested_delay() {
+        let policy = policy(true);
+        assert_eq!(
+            policy.delay_for(0, Some(Duration::from_millis(300))),
+            Some(Duration::from_millis(300))
+        );
+        // longer than the max backoff, give up
+        assert_eq!(policy.delay_for(0, Some(Duration::from_secs(60))), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(3600));
+        let delay = parse_retry_after(&later).unwrap();
+        assert!(delay > Duration::from_secs(3500) && delay <= Duration::from_secs(3600));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
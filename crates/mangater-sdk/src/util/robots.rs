@@ -0,0 +1,470 @@
+use crate::entity::CacheMode;
+use crate::errors::SdkError;
+use crate::util::http::HttpFetcher;
+
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tokio::time::Instant;
+
+/// Parsed robots.txt rules applying to a single user agent.
+///
+/// Follows RFC 9309: the groups whose `User-agent` is the agent's product token (compared
+/// case-insensitively) are used (falling back to the `*` groups), the longest matching `Allow` / `Disallow` rule wins and
+/// `Allow` wins on ties. `*` and a trailing `$` are supported in rule paths.
+///
+/// # Example
+/// ```
+/// use mangater_sdk::util::robots::RobotsTxt;
+///
+/// let robots = RobotsTxt::parse(
+///     "User-agent: *\nDisallow: /private/\nAllow: /private/public\nCrawl-delay: 2",
+///     "mangater-sdk/0.1",
+/// );
+/// assert!(robots.is_allowed("/wiki/Manga"));
+/// assert!(!robots.is_allowed("/private/notes"));
+/// assert!(robots.is_allowed("/private/public/index.html"));
+/// assert_eq!(robots.crawl_delay(), Some(std::time::Duration::from_secs(2)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsTxt {
+    rules: Vec<RobotsRule>,
+    crawl_delay: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RobotsRule {
+    allow: bool,
+    path: String,
+}
+
+/// A `User-agent` group of a robots.txt, before being selected for an agent.
+#[derive(Debug, Default)]
+struct RobotsGroup {
+    agents: Vec<String>,
+    rules: Vec<RobotsRule>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsTxt {
+    /// Parses the robots.txt content, keeping only the groups applying to the given user agent.
+    ///
+    /// # Arguments
+    /// * `content` - the robots.txt content
+    /// * `user_agent` - the full user agent (e.g. `mangater-sdk/0.1 (+https://...)`);
+    ///   its product token (`mangater-sdk`) is compared to the `User-agent` lines as a whole
+    pub fn parse(content: &str, user_agent: &str) -> Self {
+        let mut groups: Vec<RobotsGroup> = Vec::new();
+        // consecutive User-agent lines share the same group
+        let mut collecting_agents = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if !collecting_agents {
+                        groups.push(RobotsGroup::default());
+                        collecting_agents = true;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    collecting_agents = false;
+                    // an empty Disallow allows everything, i.e. no rule at all
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.rules.push(RobotsRule {
+                            allow: key == "allow",
+                            path: value.to_string(),
+                        });
+                    }
+                }
+                "crawl-delay" => {
+                    collecting_agents = false;
+                    if let Some(group) = groups.last_mut() {
+                        group.crawl_delay = value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                            .map(Duration::from_secs_f64);
+                    }
+                }
+                // sitemap and any unknown key do not end the User-agent lines of a group
+                _ => {}
+            }
+        }
+
+        let token = product_token(user_agent);
+        // the agents are lowercased already; `m` must not select the group of `mangater-sdk`
+        let matches_agent = |group: &&RobotsGroup| {
+            group
+                .agents
+                .iter()
+                .any(|agent| !token.is_empty() && *agent == token)
+        };
+        let mut selected: Vec<&RobotsGroup> = groups.iter().filter(matches_agent).collect();
+        if selected.is_empty() {
+            selected = groups
+                .iter()
+                .filter(|group| group.agents.iter().any(|agent| agent == "*"))
+                .collect();
+        }
+
+        Self {
+            rules: selected
+                .iter()
+                .flat_map(|group| group.rules.iter().cloned())
+                .collect(),
+            crawl_delay: selected.iter().filter_map(|group| group.crawl_delay).max(),
+        }
+    }
+
+    /// A robots.txt allowing everything (e.g. the site has none).
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// A robots.txt disallowing everything (e.g. the site's robots.txt is unreachable).
+    pub fn disallow_all() -> Self {
+        Self {
+            rules: vec![RobotsRule {
+                allow: false,
+                path: "/".to_string(),
+            }],
+            crawl_delay: None,
+        }
+    }
+
+    /// Checks whether the given path (including the query, e.g. `/wiki/Manga?action=raw`) may be fetched.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        if path == "/robots.txt" {
+            return true;
+        }
+        self.rules
+            .iter()
+            .filter(|rule| rule_matches(&rule.path, path))
+            .max_by(|a, b| a.path.len().cmp(&b.path.len()).then(a.allow.cmp(&b.allow)))
+            .map(|rule| rule.allow)
+            .unwrap_or(true)
+    }
+
+    /// minimum delay between 2 requests against the site
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+}
+
+/// Fetches and caches the robots.txt of every host (origin) visited; also spaces out the
+/// requests against a host according to its `Crawl-delay`.
+///
+/// A missing robots.txt (4xx) allows everything; an unreachable one (5xx, network failure)
+/// disallows everything, as per RFC 9309.
+#[derive(Debug)]
+pub struct RobotsCache {
+    user_agent: String,
+    robots: Mutex<HashMap<String, Arc<OnceCell<Arc<RobotsTxt>>>>>,
+    next_request: Mutex<HashMap<String, Instant>>,
+}
+
+impl RobotsCache {
+    /// # Arguments
+    /// * `user_agent` - the user agent the requests are sent with, e.g. `util::resource::DEFAULT_USER_AGENT`
+    pub fn new(user_agent: &str) -> Self {
+        Self {
+            user_agent: user_agent.to_string(),
+            robots: Mutex::new(HashMap::new()),
+            next_request: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks the url against its host's robots.txt (fetched through `fetcher` on first use)
+    /// and waits for the host's `Crawl-delay` if any.
+    ///
+    /// # Errors
+    /// * `SdkError::RobotsDisallowed` - the url is disallowed for our user agent
+    /// * `SdkError::Parse` - the url is invalid
+    pub async fn check(&self, fetcher: &HttpFetcher, url: &str) -> Result<(), SdkError> {
+        let parsed = Url::parse(url).map_err(|e| SdkError::Parse(format!("{} - {}", url, e)))?;
+        let origin = parsed.origin().ascii_serialization();
+        let robots = self.robots_for(fetcher, &origin).await;
+
+        let path = match parsed.query() {
+            Some(query) => format!("{}?{}", parsed.path(), query),
+            None => parsed.path().to_string(),
+        };
+        if !robots.is_allowed(&path) {
+            return Err(SdkError::RobotsDisallowed(url.to_string()));
+        }
+
+        if let Some(delay) = robots.crawl_delay() {
+            // reserve the next slot before waiting, so concurrent requests queue up
+            let wait_until = {
+                let mut next_request = self.next_request.lock().unwrap();
+                let now = Instant::now();
+                let slot = next_request
+                    .get(&origin)
+                    .copied()
+                    .filter(|slot| *slot > now)
+                    .unwrap_or(now);
+                next_request.insert(origin, slot + delay);
+                slot
+            };
+            tokio::time::sleep_until(wait_until).await;
+        }
+        Ok(())
+    }
+
+    async fn robots_for(&self, fetcher: &HttpFetcher, origin: &str) -> Arc<RobotsTxt> {
+        // one cell per host: the map is only locked to get the cell, the fetch happens outside of
+        // it so other hosts are not held up; concurrent checks of the same host wait on the cell
+        let cell = {
+            let mut cells = self.robots.lock().unwrap();
+            Arc::clone(cells.entry(origin.to_string()).or_default())
+        };
+        let robots = cell
+            .get_or_init(|| self.fetch_robots(fetcher, origin))
+            .await;
+        Arc::clone(robots)
+    }
+
+    async fn fetch_robots(&self, fetcher: &HttpFetcher, origin: &str) -> Arc<RobotsTxt> {
+        let robots_url = format!("{}/robots.txt", origin);
+        let robots = match fetcher.fetch_text(&robots_url, None).await {
+            Ok(content) => RobotsTxt::parse(&content, &self.user_agent),
+            Err(SdkError::NotFound(_)) | Err(SdkError::Authentication) => RobotsTxt::allow_all(),
+            // offline, the host is never hit; a robots.txt not cached has nothing to protect
+            Err(_)
+                if fetcher
+                    .cache()
+                    .is_some_and(|cache| cache.mode() == CacheMode::Offline) =>
+            {
+                RobotsTxt::allow_all()
+            }
+            Err(e) => {
+                tracing::warn!("robots.txt unreachable, disallow all of {}: {}", origin, e);
+                RobotsTxt::disallow_all()
+            }
+        };
+        Arc::new(robots)
+    }
+}
+
+/// the product token of a user agent, e.g. `mangater-sdk` for `mangater-sdk/0.1 (+https://...)`
+fn product_token(user_agent: &str) -> String {
+    user_agent
+        .split(|c: char| c == '/' || c.is_whitespace())
+        .next()
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// matches a rule path (supporting `*` wildcards and a `$` end anchor) against a url path
+fn rule_matches(rule: &str, path: &str) -> bool {
+    let (rule, anchored) = match rule.strip_suffix('$') {
+        Some(rule) => (rule, true),
+        None => (rule, false),
+    };
+    let mut parts = rule.split('*');
+    let Some(mut rest) = parts.next().and_then(|first| path.strip_prefix(first)) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    if parts.is_empty() {
+        return !anchored || rest.is_empty();
+    }
+    for (index, part) in parts.iter().enumerate() {
+        if anchored && index == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::HttpConfig;
+    use crate::util::resource::DEFAULT_USER_AGENT;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const ROBOTS: &str = "
+# generic crawlers
+User-agent: *
+Disallow: /
+
+User-agent: googlebot
+User-agent: Mangater-SDK
+Disallow: /w/
+Allow: /w/load.php
+Disallow: /*.pdf$
+Crawl-delay: 1.5
+
+Sitemap: https://example.com/sitemap.xml
+";
+
+    #[test]
+    fn test_parse_matching_group() {
+        let robots = RobotsTxt::parse(ROBOTS, DEFAULT_USER_AGENT);
+        assert!(robots.is_allowed("/wiki/Manga"));
+        assert!(!robots.is_allowed("/w/index.php?title=Manga"));
+        // longer rule wins
+        assert!(robots.is_allowed("/w/load.php?modules=site"));
+        assert!(!robots.is_allowed("/files/volume-1.pdf"));
+        assert!(robots.is_allowed("/files/volume-1.pdf?download=1"));
+        assert_eq!(robots.crawl_delay(), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_parse_fallback_group() {
+        let robots = RobotsTxt::parse(ROBOTS, "otherbot/2.0");
+        assert!(!robots.is_allowed("/wiki/Manga"));
+        assert!(robots.is_allowed("/robots.txt"));
+        assert_eq!(robots.crawl_delay(), None);
+
+        // no group at all
+        let robots = RobotsTxt::parse("Sitemap: /sitemap.xml", DEFAULT_USER_AGENT);
+        assert_eq!(robots, RobotsTxt::allow_all());
+        assert!(robots.is_allowed("/anything"));
+    }
+
+    #[test]
+    fn test_parse_whole_product_token() {
+        // a prefix of the product token is another agent
+        let robots = RobotsTxt::parse(
+            "User-agent: m\nDisallow: /\n\nUser-agent: *\nDisallow: /private/",
+            DEFAULT_USER_AGENT,
+        );
+        assert!(robots.is_allowed("/wiki/Manga"));
+        assert!(!robots.is_allowed("/private/notes"));
+
+        let robots = RobotsTxt::parse("User-agent: MANGATER-sdk\nDisallow: /", DEFAULT_USER_AGENT);
+        assert!(!robots.is_allowed("/wiki/Manga"));
+    }
+
+    #[test]
+    fn test_is_allowed_tie_and_empty_disallow() {
+        let robots = RobotsTxt::parse(
+            "User-agent: *\nDisallow: /page\nAllow: /page\nDisallow:",
+            DEFAULT_USER_AGENT,
+        );
+        // allow wins on ties
+        assert!(robots.is_allowed("/page"));
+        assert!(!RobotsTxt::disallow_all().is_allowed("/"));
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        assert!(rule_matches("/wiki", "/wiki/Manga"));
+        assert!(!rule_matches("/wiki$", "/wiki/Manga"));
+        assert!(rule_matches("/wiki$", "/wiki"));
+        assert!(rule_matches("/*/edit", "/wiki/Manga/edit"));
+        assert!(rule_matches("/*.jpg$", "/a/b.c.jpg"));
+        assert!(!rule_matches("/*.jpg$", "/a/b.jpg.png"));
+        assert!(!rule_matches("/a*b", "/ac"));
+    }
+
+    /// serves a single robots.txt request, once `release` fires
+    async fn serve_robots(release: tokio::sync::oneshot::Receiver<()>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let _ = socket.read(&mut buffer).await.unwrap();
+            let _ = release.await;
+            let body = "User-agent: *\nDisallow: /private/";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+        });
+        base_url
+    }
+
+    #[tokio::test]
+    async fn test_check_slow_host_does_not_block_others() {
+        let (release_slow, slow_release) = tokio::sync::oneshot::channel();
+        let (release_fast, fast_release) = tokio::sync::oneshot::channel();
+        let slow_url = serve_robots(slow_release).await;
+        let fast_url = serve_robots(fast_release).await;
+        release_fast.send(()).unwrap();
+
+        let fetcher = HttpFetcher::new(&HttpConfig::default(), None).unwrap();
+        let cache = Arc::new(RobotsCache::new(DEFAULT_USER_AGENT));
+        let slow = tokio::spawn({
+            let cache = Arc::clone(&cache);
+            let fetcher = fetcher.clone();
+            async move { cache.check(&fetcher, &format!("{}/page", slow_url)).await }
+        });
+        // wait for the slow robots.txt to be in flight
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        tokio::time::timeout(
+            Duration::from_secs(2),
+            cache.check(&fetcher, &format!("{}/page", fast_url)),
+        )
+        .await
+        .expect("a pending robots.txt of another host blocked the check")
+        .unwrap();
+
+        release_slow.send(()).unwrap();
+        slow.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_cached_per_host() {
+        // a single connection only; any further robots.txt request would fail to connect
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let body = "User-agent: *\nDisallow: /private/";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+            String::from_utf8_lossy(&buffer[..read]).to_string()
+        });
+
+        let fetcher = HttpFetcher::new(&HttpConfig::default(), None).unwrap();
+        let cache = RobotsCache::new(DEFAULT_USER_AGENT);
+        cache
+            .check(&fetcher, &format!("{}/public/page.html", base_url))
+            .await
+            .unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /robots.txt "));
+
+        let blocked = format!("{}/private/page.html?id=1", base_url);
+        match cache.check(&fetcher, &blocked).await {
+            Err(SdkError::RobotsDisallowed(url)) => assert_eq!(url, blocked),
+            other => panic!("expected RobotsDisallowed, got {:?}", other),
+        }
+    }
+}
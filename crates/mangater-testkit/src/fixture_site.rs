@@ -0,0 +1,179 @@
+use mangater_sdk::entity::{DownloadedResource, ResourceMetadata};
+use mangater_sdk::util::fixture::{FixtureMode, Fixtures};
+use mangater_sdk::SdkError;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use url::Url;
+
+/// distinguishes the fixture folders of concurrent tests
+static SITE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Canned responses of real urls (e.g. `https://en.wikipedia.org/wiki/Manga`), replayed through
+/// the fetcher's [`Fixtures`]; for plugins whose domain only matches their own hosts (and https),
+/// which a [`MockSite`](crate::MockSite) on a local port cannot serve.
+///
+/// Hosts without a `robots.txt` given are served a `404 Not Found` one, allowing every page.
+/// The fixtures are written into a temp folder, removed when the site is dropped; keep the site
+/// alive as long as they are replayed.
+///
+/// # Example
+/// ```no_run
+/// use mangater_testkit::FixtureSite;
+///
+/// # async fn run() -> Result<(), mangater_sdk::SdkError> {
+/// let site = FixtureSite::new()
+///     .page("https://en.wikipedia.org/wiki/Manga", "<html>...</html>")
+///     .asset("https://upload.wikimedia.org/manga.png", b"png".to_vec(), "image/png");
+/// let fixtures = site.fixtures().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FixtureSite {
+    root: PathBuf,
+    responses: BTreeMap<String, Result<DownloadedResource, u16>>,
+}
+
+impl Default for FixtureSite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixtureSite {
+    pub fn new() -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "mangater-testkit-fixtures-{}-{}",
+            std::process::id(),
+            SITE_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        Self {
+            root,
+            responses: BTreeMap::new(),
+        }
+    }
+
+    /// Serves the html page (utf-8) at the given url.
+    pub fn page(self, url: &str, html: impl Into<String>) -> Self {
+        self.asset(url, html.into().into_bytes(), "text/html; charset=utf-8")
+    }
+
+    /// Serves the binary asset (e.g. an image) of the given content type at the given url.
+    pub fn asset(mut self, url: &str, body: Vec<u8>, content_type: &str) -> Self {
+        let resource = DownloadedResource {
+            metadata: ResourceMetadata {
+                content_type: Some(content_type.to_string()),
+                content_length: Some(body.len() as u64),
+                final_url: url.to_string(),
+                etag: None,
+                last_modified: None,
+            },
+            bytes: body,
+        };
+        self.responses.insert(url.to_string(), Ok(resource));
+        self
+    }
+
+    /// Serves the given error status at the given url: 404, 401 / 403 or 429.
+    pub fn status(mut self, url: &str, status: u16) -> Self {
+        self.responses.insert(url.to_string(), Err(status));
+        self
+    }
+
+    /// Writes the fixtures of the site, ready to be replayed.
+    pub async fn fixtures(&self) -> Result<Arc<Fixtures>, SdkError> {
+        let recorder = Fixtures::open(&self.root, FixtureMode::Record)?;
+        for (url, response) in &self.responses {
+            recorder.record(url, &fetched(url, response)).await?;
+        }
+
+        let mut origins: Vec<String> = self
+            .responses
+            .keys()
+            .filter_map(|url| Url::parse(url).ok())
+            .map(|url| url.origin().ascii_serialization())
+            .collect();
+        origins.sort();
+        origins.dedup();
+        for origin in origins {
+            let robots_url = format!("{}/robots.txt", origin);
+            if !self.responses.contains_key(&robots_url) {
+                recorder
+                    .record(&robots_url, &Err(SdkError::NotFound(robots_url.clone())))
+                    .await?;
+            }
+        }
+
+        Ok(Arc::new(Fixtures::open(&self.root, FixtureMode::Replay)?))
+    }
+}
+
+impl Drop for FixtureSite {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// the outcome of fetching the url, as recorded by [`Fixtures::record`]
+fn fetched(
+    url: &str,
+    response: &Result<DownloadedResource, u16>,
+) -> Result<DownloadedResource, SdkError> {
+    match response {
+        Ok(resource) => Ok(resource.clone()),
+        Err(401 | 403) => Err(SdkError::Authentication),
+        Err(429) => Err(SdkError::RateLimited),
+        Err(_) => Err(SdkError::NotFound(url.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fixture_site() -> Result<(), SdkError> {
+        let site = FixtureSite::new()
+            .page("https://en.wikipedia.org/wiki/Manga", "<html>manga</html>")
+            .asset(
+                "https://upload.wikimedia.org/manga.png",
+                b"png".to_vec(),
+                "image/png",
+            )
+            .status("https://en.wikipedia.org/wiki/Private", 403);
+        let fixtures = site.fixtures().await?;
+
+        let page = fixtures
+            .replay("https://en.wikipedia.org/wiki/Manga")
+            .await?;
+        assert_eq!(page.bytes, b"<html>manga</html>");
+        let image = fixtures
+            .replay("https://upload.wikimedia.org/manga.png")
+            .await?;
+        assert_eq!(image.metadata.content_type.as_deref(), Some("image/png"));
+        assert!(matches!(
+            fixtures
+                .replay("https://en.wikipedia.org/wiki/Private")
+                .await,
+            Err(SdkError::Authentication)
+        ));
+        // every host gets a robots.txt allowing every page
+        for robots_url in [
+            "https://en.wikipedia.org/robots.txt",
+            "https://upload.wikimedia.org/robots.txt",
+        ] {
+            assert!(matches!(
+                fixtures.replay(robots_url).await,
+                Err(SdkError::NotFound(_))
+            ));
+        }
+
+        let root = fixtures.root().to_path_buf();
+        drop(site);
+        assert!(!root.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,395 @@
+use mangater_core::orchestration::{Engine, ScrapReport};
+use mangater_sdk::entity::{PatternMatchResult, PatternType, PersistContext, StorageConfig};
+use mangater_sdk::storage::FileSystemStorage;
+use mangater_sdk::traits::{Domain, Storage};
+use mangater_sdk::util::fixture::Fixtures;
+use mangater_sdk::SdkError;
+
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// An item handed over to the storage during a scrap.
+#[derive(Debug, Clone)]
+pub struct CollectedItem {
+    pub context: PersistContext,
+    pub resource: PatternMatchResult,
+    pub content: Vec<u8>,
+}
+
+impl CollectedItem {
+    /// the content as text (lossy utf-8)
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.content).to_string()
+    }
+}
+
+/// Collects every item persisted; writing them into the output folder as well, if any.
+#[derive(Clone, Default)]
+struct CollectingStorage {
+    items: Arc<Mutex<Vec<CollectedItem>>>,
+    files: Option<Arc<FileSystemStorage>>,
+}
+
+#[async_trait]
+impl Storage for CollectingStorage {
+    async fn persist(
+        &self,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<(), SdkError> {
+        self.persist_with_context(&PersistContext::default(), resource, resource_content)
+            .await?;
+        Ok(())
+    }
+
+    async fn persist_with_context(
+        &self,
+        context: &PersistContext,
+        resource: &PatternMatchResult,
+        resource_content: Vec<u8>,
+    ) -> Result<Option<PathBuf>, SdkError> {
+        let written = match &self.files {
+            Some(files) => {
+                files
+                    .persist_with_context(context, resource, resource_content.clone())
+                    .await?
+            }
+            None => None,
+        };
+        self.items.lock().unwrap().push(CollectedItem {
+            context: context.clone(),
+            resource: resource.clone(),
+            content: resource_content,
+        });
+        Ok(written)
+    }
+}
+
+/// Runs scraps of a plugin ([`Domain`]) through a real [`Engine`], collecting what the engine
+/// hands over to the storage for assertions.
+///
+/// Plugins providing their own storage keep it; the items are collected only for plugins
+/// relying on the engine's storage (the usual case).
+///
+/// # Example
+/// ```no_run
+/// use mangater_testkit::{MockSite, ScrapHarness};
+/// use mangater_sdk::entity::PatternType;
+/// # use mangater_sdk::traits::Domain;
+/// # use std::sync::Arc;
+///
+/// # async fn run(plugin: Arc<dyn Domain>) -> Result<(), mangater_sdk::SdkError> {
+/// let site = MockSite::start().await;
+/// site.page("/chapter/1", "<html>...</html>");
+///
+/// let outcome = ScrapHarness::new(plugin).run(&site.url("/chapter/1")).await?;
+/// outcome.assert_item_count(PatternType::Resource, 3);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ScrapHarness {
+    engine: Engine,
+    storage: CollectingStorage,
+    output: Option<PathBuf>,
+}
+
+impl ScrapHarness {
+    /// An engine with the given plugin registered (with its config already loaded, if any).
+    pub fn new(domain: Arc<dyn Domain>) -> Self {
+        let mut engine = Engine::new();
+        engine.registry().add_to_registry(None, domain);
+        let storage = CollectingStorage::default();
+        engine.set_default_storage(Arc::new(storage.clone()));
+        Self {
+            engine,
+            storage,
+            output: None,
+        }
+    }
+
+    /// Loads the engine config (json5), e.g. `{ core: { crawl: { max_pages: 2 } } }`.
+    pub fn with_config(mut self, config: &str) -> Result<Self, SdkError> {
+        self.engine.config_load_from_json5_str(config)?;
+        Ok(self)
+    }
+
+    /// Replays the responses from the given fixtures (e.g. of a [`FixtureSite`](crate::FixtureSite))
+    /// instead of hitting the network.
+    pub fn with_fixtures(mut self, fixtures: Arc<Fixtures>) -> Self {
+        self.engine.set_fixtures(fixtures);
+        self
+    }
+
+    /// Writes the items into the given folder as well, as per the default `core.storage`
+    /// layout; see [`ScrapOutcome::files`].
+    pub fn with_output_dir(mut self, output: impl Into<PathBuf>) -> Self {
+        let output = output.into();
+        let config = StorageConfig {
+            output: output.to_string_lossy().to_string(),
+            ..StorageConfig::default()
+        };
+        self.storage.files = Some(Arc::new(FileSystemStorage::new(&config)));
+        self.engine
+            .set_default_storage(Arc::new(self.storage.clone()));
+        self.output = Some(output);
+        self
+    }
+
+    /// the underlying engine, for anything not covered by the harness
+    pub fn engine(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Runs the scrap workflow against the given url; the outcome holds the items of this run only.
+    pub async fn run(&self, url: &str) -> Result<ScrapOutcome, SdkError> {
+        self.storage.items.lock().unwrap().clear();
+        let report = self.engine.run_scrap_workflow(url.to_string()).await?;
+        let items = std::mem::take(&mut *self.storage.items.lock().unwrap());
+        Ok(ScrapOutcome {
+            report,
+            items,
+            output: self.output.clone(),
+        })
+    }
+}
+
+/// What a [`ScrapHarness`] run collected.
+#[derive(Debug, Clone)]
+pub struct ScrapOutcome {
+    pub report: ScrapReport,
+    /// the items in the order the engine persisted them
+    pub items: Vec<CollectedItem>,
+    output: Option<PathBuf>,
+}
+
+impl ScrapOutcome {
+    /// the pattern results of every item, in order
+    pub fn results(&self) -> Vec<&PatternMatchResult> {
+        self.items.iter().map(|item| &item.resource).collect()
+    }
+
+    /// the items collected by patterns of the given type
+    pub fn items_of(&self, pattern_type: PatternType) -> Vec<&CollectedItem> {
+        self.items
+            .iter()
+            .filter(|item| item.resource.pattern_type == pattern_type)
+            .collect()
+    }
+
+    /// the item resolved to the given url (e.g. an image), if any
+    pub fn item(&self, url: &str) -> Option<&CollectedItem> {
+        self.items
+            .iter()
+            .find(|item| item.resource.resource_string.as_deref() == Some(url))
+    }
+
+    /// Asserts the number of items collected by patterns of the given type.
+    pub fn assert_item_count(&self, pattern_type: PatternType, expected: usize) {
+        let items = self.items_of(pattern_type.clone());
+        assert_eq!(
+            items.len(),
+            expected,
+            "{:?} items: {:?}",
+            pattern_type,
+            items
+                .iter()
+                .map(|item| &item.resource.resource_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    /// Asserts the content of the item resolved to the given url.
+    pub fn assert_resource(&self, url: &str, content: &[u8]) {
+        let item = self
+            .item(url)
+            .unwrap_or_else(|| panic!("no item of {}, got {:?}", url, self.results()));
+        assert_eq!(item.content, content, "content of {}", url);
+    }
+
+    /// Asserts some `Content` (or `ScrapedContent`) item contains the given text.
+    pub fn assert_content_contains(&self, text: &str) {
+        let contents: Vec<String> = self
+            .items
+            .iter()
+            .filter(|item| {
+                matches!(
+                    item.resource.pattern_type,
+                    PatternType::Content | PatternType::ScrapedContent
+                )
+            })
+            .map(CollectedItem::text)
+            .collect();
+        assert!(
+            contents.iter().any(|content| content.contains(text)),
+            "no content contains {:?}, got {:?}",
+            text,
+            contents
+        );
+    }
+
+    /// The files written into the output folder (see [`ScrapHarness::with_output_dir`]),
+    /// relative to it and sorted; empty without an output folder.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let Some(output) = &self.output else {
+            return Vec::new();
+        };
+        let mut files = Vec::new();
+        collect_files(output, output, &mut files);
+        files.sort();
+        files
+    }
+
+    /// Asserts the content of the file at the given path, relative to the output folder.
+    pub fn assert_file(&self, path: impl AsRef<Path>, content: &[u8]) {
+        let output = self
+            .output
+            .as_ref()
+            .expect("no output folder, see ScrapHarness::with_output_dir");
+        let path = output.join(path);
+        let written = std::fs::read(&path)
+            .unwrap_or_else(|e| panic!("{} - {}, got {:?}", path.display(), e, self.files()));
+        assert_eq!(written, content, "content of {}", path.display());
+    }
+}
+
+fn collect_files(root: &Path, folder: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::MockSite;
+    use mangater_sdk::entity::Registerable;
+    use mangater_sdk::traits::Matcher;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// a plugin crawling manga-like chapters: one image and a caption per page, plus a "next" link
+    #[derive(Clone)]
+    struct ChapterDomain {
+        base_url: String,
+    }
+
+    impl Domain for ChapterDomain {
+        fn match_domain(&self, domain: String) -> Result<bool, SdkError> {
+            Ok(domain.starts_with(&self.base_url))
+        }
+
+        fn get_domain_key(&self) -> String {
+            "chapter".to_string()
+        }
+
+        fn get_domain_registerable(&self) -> Registerable {
+            Registerable {
+                configurator: None,
+                matcher: Arc::new(self.clone()),
+                storage: None,
+            }
+        }
+    }
+
+    impl Matcher for ChapterDomain {
+        fn match_patterns(&self) -> Vec<PatternMatchResult> {
+            [
+                ("img.page", PatternType::Resource),
+                ("p.caption", PatternType::Content),
+                ("a.next", PatternType::Pagination),
+            ]
+            .into_iter()
+            .map(|(pattern, pattern_type)| PatternMatchResult {
+                pattern: pattern.to_string(),
+                pattern_type,
+                resource_string: None,
+            })
+            .collect()
+        }
+    }
+
+    async fn chapter_site() -> MockSite {
+        let site = MockSite::start().await;
+        for index in 1..=2 {
+            let next = match index {
+                1 => "<a class=\"next\" href=\"/chapter/2\">next</a>",
+                _ => "",
+            };
+            site.page(
+                &format!("/chapter/{}", index),
+                format!(
+                    "<html><body><img class=\"page\" src=\"/img/{index}.png\"><p class=\"caption\">page {index}</p>{next}</body></html>"
+                ),
+            );
+            site.route(
+                &format!("/img/{}.png", index),
+                crate::MockResponse::asset(format!("png-{}", index).into_bytes(), "image/png"),
+            );
+        }
+        site
+    }
+
+    #[tokio::test]
+    async fn test_harness_run() -> Result<(), SdkError> {
+        let site = chapter_site().await;
+        let output = std::env::temp_dir().join(format!(
+            "mangater-testkit-harness-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let harness = ScrapHarness::new(Arc::new(ChapterDomain {
+            base_url: site.base_url().to_string(),
+        }))
+        .with_output_dir(&output);
+
+        let outcome = harness.run(&site.url("/chapter/1")).await?;
+        assert_eq!(outcome.report.pages_visited, 2);
+        outcome.assert_item_count(PatternType::Resource, 2);
+        outcome.assert_item_count(PatternType::Content, 2);
+        outcome.assert_resource(&site.url("/img/2.png"), b"png-2");
+        outcome.assert_content_contains("page 2");
+        assert_eq!(outcome.items[0].context.domain_key, "chapter");
+
+        assert_eq!(
+            outcome.files(),
+            vec![
+                PathBuf::from("chapter/1/0001_1.png"),
+                PathBuf::from("chapter/1/0002_content.txt"),
+                PathBuf::from("chapter/1/0003_2.png"),
+                PathBuf::from("chapter/1/0004_content.txt"),
+            ]
+        );
+        outcome.assert_file("chapter/1/0003_2.png", b"png-2");
+        assert_eq!(site.hits("/img/1.png"), 1);
+
+        std::fs::remove_dir_all(&output)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_harness_with_config() -> Result<(), SdkError> {
+        let site = chapter_site().await;
+        let harness = ScrapHarness::new(Arc::new(ChapterDomain {
+            base_url: site.base_url().to_string(),
+        }))
+        .with_config("{ core: { crawl: { max_pages: 1 } } }")?;
+
+        let outcome = harness.run(&site.url("/chapter/1")).await?;
+        assert_eq!(outcome.report.pages_visited, 1);
+        assert_eq!(outcome.results().len(), 2);
+        assert!(outcome.files().is_empty());
+        Ok(())
+    }
+}
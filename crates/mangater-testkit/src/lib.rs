@@ -0,0 +1,43 @@
+// mangater-testkit - the plugin test harness for Mangater
+// Copyright (C) 2026 Takara-Mono <quoeamaster@gmail.com>
+//
+// This file is dual-licensed under the terms of the MIT.
+//
+// You may choose either license at your option.
+// For a copy of the MIT license, see <https://opensource.org/licenses/MIT>.
+//
+// MIT License:
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Test harness for Mangater plugins ([`Domain`](mangater_sdk::traits::Domain) implementations).
+//!
+//! - [`MockSite`]: a local HTTP site serving canned pages and assets, with configurable latency,
+//!   status codes and headers.
+//! - [`FixtureSite`]: canned responses of real urls (e.g. `https://en.wikipedia.org/...`),
+//!   replayed through the fetcher's fixtures.
+//! - [`ScrapHarness`]: runs a scrap of a plugin through the engine and asserts on the collected
+//!   pattern results and stored files.
+
+mod fixture_site;
+mod harness;
+mod server;
+
+pub use fixture_site::FixtureSite;
+pub use harness::{CollectedItem, ScrapHarness, ScrapOutcome};
+pub use server::{MockResponse, MockSite, ReceivedRequest};
@@ -0,0 +1,373 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// largest request head (request line and headers) read by a [`MockSite`]
+const MAX_REQUEST_HEAD: usize = 64 * 1024;
+
+/// A canned response of a [`MockSite`] route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockResponse {
+    pub status: u16,
+    /// headers sent along with the body; `Content-Length` and `Connection` are always set
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// delay before the response is sent
+    pub latency: Duration,
+}
+
+impl MockResponse {
+    /// `200 OK` with the given body, no headers
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// `200 OK` html page (utf-8)
+    pub fn html(body: impl Into<String>) -> Self {
+        Self::ok(body.into()).with_header("Content-Type", "text/html; charset=utf-8")
+    }
+
+    /// `200 OK` binary asset (e.g. an image) of the given content type
+    pub fn asset(body: impl Into<Vec<u8>>, content_type: &str) -> Self {
+        Self::ok(body).with_header("Content-Type", content_type)
+    }
+
+    /// the given status with an empty body, e.g. `503` or a `304 Not Modified`
+    pub fn status(status: u16) -> Self {
+        Self::ok(Vec::new()).with_status(status)
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            self.body.len()
+        ));
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+/// A request received by a [`MockSite`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedRequest {
+    pub method: String,
+    /// the request target, e.g. `/wiki/Manga?action=raw`
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl ReceivedRequest {
+    /// value of the given header (case insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Default)]
+struct SiteState {
+    /// responses per request target; served in order, the last one repeats
+    routes: HashMap<String, VecDeque<MockResponse>>,
+    /// delay added to every response
+    latency: Duration,
+    requests: Vec<ReceivedRequest>,
+}
+
+/// A local HTTP site serving canned pages and assets, for plugin tests without network.
+///
+/// Routes are matched against the request target (path and query), then the path alone;
+/// anything else is a `404 Not Found` (e.g. `/robots.txt`, which allows every page). A route
+/// may be given several responses (e.g. a `503` then a `200`), served in order, the last one
+/// repeating. Every request received is kept for assertions.
+///
+/// The site stops when dropped.
+///
+/// # Example
+/// ```no_run
+/// use mangater_testkit::{MockResponse, MockSite};
+///
+/// # async fn run() {
+/// let site = MockSite::start().await;
+/// site.page("/chapter/1", "<html><body><img class=\"page\" src=\"/img/1.png\"></body></html>");
+/// site.route("/img/1.png", MockResponse::asset(b"png".to_vec(), "image/png"));
+/// let url = site.url("/chapter/1");
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MockSite {
+    base_url: String,
+    state: Arc<Mutex<SiteState>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockSite {
+    /// Starts serving on a free local port.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("a local port should be available");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(SiteState::default()));
+
+        let shared = Arc::clone(&state);
+        let handle = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(serve(socket, Arc::clone(&shared)));
+            }
+        });
+        Self {
+            base_url,
+            state,
+            handle,
+        }
+    }
+
+    /// base url of the site, e.g. `http://127.0.0.1:34567`
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// absolute url of the given path on the site
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Serves the html page at the given path (request target).
+    pub fn page(&self, path: &str, html: impl Into<String>) {
+        self.route(path, MockResponse::html(html));
+    }
+
+    /// Serves the response at the given path (request target); replaces the former responses.
+    pub fn route(&self, path: &str, response: MockResponse) {
+        self.routes(path, vec![response]);
+    }
+
+    /// Serves the responses at the given path in order, the last one repeating;
+    /// replaces the former responses.
+    pub fn routes(&self, path: &str, responses: Vec<MockResponse>) {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .insert(path.to_string(), responses.into());
+    }
+
+    /// Delays every response of the site, on top of the latency of the response itself.
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().unwrap().latency = latency;
+    }
+
+    /// every request received so far, in order
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// number of requests received for the given request target
+    pub fn hits(&self, target: &str) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|request| request.target == target)
+            .count()
+    }
+}
+
+impl Drop for MockSite {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve(mut socket: TcpStream, state: Arc<Mutex<SiteState>>) {
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+    let (response, latency) = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(request.clone());
+        let route = if state.routes.contains_key(&request.target) {
+            request.target.as_str()
+        } else {
+            request.target.split('?').next().unwrap_or_default()
+        };
+        let response = match state.routes.get_mut(route) {
+            Some(routes) if routes.len() > 1 => routes.pop_front().unwrap(),
+            Some(routes) => routes.front().cloned().unwrap_or(MockResponse::status(404)),
+            None => MockResponse::status(404),
+        };
+        let latency = state.latency + response.latency;
+        (response, latency)
+    };
+
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+    if let Err(e) = socket.write_all(&response.to_bytes()).await {
+        tracing::debug!("mock site failed to respond to {} - {}", request.target, e);
+    }
+    let _ = socket.shutdown().await;
+}
+
+/// reads the request head (request line and headers); the body, if any, is ignored
+async fn read_request(socket: &mut TcpStream) -> Option<ReceivedRequest> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 4096];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = socket.read(&mut buffer).await.ok()?;
+        if read == 0 || head.len() > MAX_REQUEST_HEAD {
+            return None;
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?.to_string();
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Some(ReceivedRequest {
+        method,
+        target,
+        headers,
+    })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mangater_sdk::entity::HttpConfig;
+    use mangater_sdk::util::http::HttpFetcher;
+    use mangater_sdk::SdkError;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_mock_site_routes() -> Result<(), SdkError> {
+        let site = MockSite::start().await;
+        site.page("/chapter/1", "<html>chapter 1</html>");
+        site.route(
+            "/img/1.png",
+            MockResponse::asset(vec![0x89, b'P', b'N', b'G'], "image/png")
+                .with_header("ETag", "\"v1\""),
+        );
+        site.route("/private", MockResponse::status(403));
+
+        let fetcher = HttpFetcher::new(&HttpConfig::default(), None)?;
+        let page = fetcher
+            .fetch_text(&site.url("/chapter/1?lang=en"), None)
+            .await?;
+        assert_eq!(page, "<html>chapter 1</html>");
+
+        let image = fetcher
+            .fetch_resource(&site.url("/img/1.png"), None)
+            .await?;
+        assert_eq!(image.bytes, vec![0x89, b'P', b'N', b'G']);
+        assert_eq!(image.metadata.content_type.as_deref(), Some("image/png"));
+        assert_eq!(image.metadata.etag.as_deref(), Some("\"v1\""));
+
+        assert!(matches!(
+            fetcher.fetch_bytes(&site.url("/private"), None).await,
+            Err(SdkError::Authentication)
+        ));
+        assert!(matches!(
+            fetcher.fetch_bytes(&site.url("/robots.txt"), None).await,
+            Err(SdkError::NotFound(_))
+        ));
+
+        let requests = site.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].target, "/chapter/1?lang=en");
+        assert!(requests[0].header("user-agent").is_some());
+        assert_eq!(site.hits("/img/1.png"), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mock_site_sequence_and_latency() -> Result<(), SdkError> {
+        let site = MockSite::start().await;
+        site.routes(
+            "/flaky",
+            vec![
+                MockResponse::status(503).with_header("Retry-After", "0"),
+                MockResponse::ok("recovered").with_latency(Duration::from_millis(100)),
+            ],
+        );
+
+        let mut config = HttpConfig::default();
+        config.retry.initial_backoff_ms = 10;
+        let fetcher = HttpFetcher::new(&config, None)?;
+
+        let started = Instant::now();
+        assert_eq!(
+            fetcher.fetch_bytes(&site.url("/flaky"), None).await?,
+            b"recovered"
+        );
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        // the last response repeats
+        assert_eq!(
+            fetcher.fetch_bytes(&site.url("/flaky"), None).await?,
+            b"recovered"
+        );
+        assert_eq!(site.hits("/flaky"), 3);
+
+        site.set_latency(Duration::from_millis(150));
+        let started = Instant::now();
+        fetcher.fetch_bytes(&site.url("/flaky"), None).await?;
+        assert!(started.elapsed() >= Duration::from_millis(250));
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+use mangater_sdk::traits::Config;
+use site_wikipedia::WikipediaInstance;
+
+// the cdylib can be dropped into `core.plugin_dir`; same sdk version and rustc as the application
+mangater_sdk::export_plugin!(|plugins| {
+    let mut wikipedia = WikipediaInstance::new();
+    wikipedia.load(plugins.clone())?;
+    Ok(wikipedia)
+});
@@ -24,6 +24,12 @@ pub struct WikipediaInstance {
     config: WikipediaConfig,
 }
 
+impl Default for WikipediaInstance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl WikipediaInstance {
     pub fn new() -> Self {
         Self {
@@ -41,6 +47,11 @@ impl Domain for WikipediaInstance {
         Ok(WIKI_REGEX.is_match(&domain))
     }
 
+    /// every language / mobile subdomain; `match_domain` still checks the scheme and path
+    fn host_patterns(&self) -> Vec<String> {
+        vec!["wikipedia.org".to_string(), "*.wikipedia.org".to_string()]
+    }
+
     // fn register_domain(
     //     &self,
     //     //registry: Box<dyn Registry>,
@@ -43,3 +43,12 @@ fn test_wikipedia_domain_match() {
         )
         .is_ok_and(|x| !x));
 }
+
+#[test]
+fn test_wikipedia_host_patterns() {
+    let wikipedia = WikipediaInstance::new();
+    assert_eq!(
+        wikipedia.host_patterns(),
+        vec!["wikipedia.org", "*.wikipedia.org"]
+    );
+}
@@ -0,0 +1,55 @@
+use mangater_sdk::entity::PatternType;
+use mangater_sdk::traits::Config;
+use mangater_sdk::SdkError;
+use mangater_testkit::{FixtureSite, ScrapHarness};
+use serde_json::json;
+use site_wikipedia::WikipediaInstance;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const PAGE_URL: &str = "https://en.wikipedia.org/wiki/Manga";
+const IMAGE_URL: &str = "https://upload.wikimedia.org/wikipedia/commons/manga.png";
+
+fn manga_site() -> FixtureSite {
+    FixtureSite::new()
+        .page(
+            PAGE_URL,
+            format!(
+                "<html><body><div id=\"mw-content-text\"><p>Manga are comics or graphic novels originating from Japan.</p><img src=\"{}\"></div></body></html>",
+                IMAGE_URL
+            ),
+        )
+        .asset(IMAGE_URL, vec![0x89, b'P', b'N', b'G'], "image/png")
+}
+
+#[tokio::test]
+async fn test_wikipedia_scrap_images() -> Result<(), SdkError> {
+    let site = manga_site();
+    let harness =
+        ScrapHarness::new(Arc::new(WikipediaInstance::new())).with_fixtures(site.fixtures().await?);
+
+    let outcome = harness.run(PAGE_URL).await?;
+    assert_eq!(outcome.report.pages_visited, 1);
+    outcome.assert_item_count(PatternType::Resource, 1);
+    outcome.assert_item_count(PatternType::Content, 0);
+    outcome.assert_resource(IMAGE_URL, &[0x89, b'P', b'N', b'G']);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wikipedia_scrap_content() -> Result<(), SdkError> {
+    let mut wikipedia = WikipediaInstance::new();
+    wikipedia.load(HashMap::from([(
+        "wikipedia".to_string(),
+        json!({ "need_content": true }),
+    )]))?;
+
+    let site = manga_site();
+    let harness = ScrapHarness::new(Arc::new(wikipedia)).with_fixtures(site.fixtures().await?);
+
+    let outcome = harness.run(PAGE_URL).await?;
+    outcome.assert_item_count(PatternType::Resource, 1);
+    outcome.assert_item_count(PatternType::Content, 1);
+    outcome.assert_content_contains("originating from Japan");
+    Ok(())
+}
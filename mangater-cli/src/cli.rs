@@ -1,5 +1,5 @@
 use clap::{Parser, Subcommand};
-use mangater_cli::entity::{ConfigMode, LogLevel, ScrapArgs};
+use mangater_cli::entity::{CatalogArgs, ConfigMode, LogLevel, ScrapArgs};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -35,4 +35,13 @@ pub enum Commands {
 
     #[command(about = "List all supported domains")]
     ListDomains,
+
+    #[command(about = "Show which plugin would scrap the provided URL, and why")]
+    Resolve {
+        /// URL to resolve
+        url: String,
+    },
+
+    #[command(about = "Query the catalog of everything scraped (`core.storage.catalog`)")]
+    Catalog(CatalogArgs),
 }
@@ -1,5 +1,9 @@
+mod cmd_catalog;
 mod cmd_list_domains;
+mod cmd_resolve;
 mod cmd_scrap;
 
+pub use cmd_catalog::{catalog, catalog_find, catalog_list, catalog_stats};
 pub use cmd_list_domains::list_domains;
+pub use cmd_resolve::resolve;
 pub use cmd_scrap::scrap;
@@ -0,0 +1,68 @@
+use mangater_sdk::storage::{Catalog, CatalogEntry, CatalogStats};
+
+use std::sync::Arc;
+
+use crate::entity::{CatalogArgs, CatalogCommand, ConfigMode};
+use crate::util::engine::build_engine;
+
+pub fn catalog(
+    config_mode: ConfigMode,
+    config_file: Option<String>,
+    args: CatalogArgs,
+) -> anyhow::Result<()> {
+    let catalog = match args.database {
+        Some(database) => Arc::new(Catalog::open(database)?),
+        None => build_engine(config_mode, config_file)?
+            .catalog()
+            .ok_or_else(|| {
+                anyhow::anyhow!("no catalog configured; set `core.storage.catalog` or --database")
+            })?,
+    };
+
+    match args.command {
+        CatalogCommand::List { domain } => catalog_list(&catalog, &domain).map(|_| ()),
+        CatalogCommand::Find { url } => catalog_find(&catalog, &url).map(|_| ()),
+        CatalogCommand::Stats => catalog_stats(&catalog).map(|_| ()),
+    }
+}
+
+pub fn catalog_list(catalog: &Catalog, domain_key: &str) -> anyhow::Result<Vec<CatalogEntry>> {
+    let entries = catalog.list_by_domain(domain_key)?;
+    println!("Item(s) of {}, count: {}", domain_key, entries.len());
+    print_entries(&entries);
+    Ok(entries)
+}
+
+pub fn catalog_find(catalog: &Catalog, url: &str) -> anyhow::Result<Vec<CatalogEntry>> {
+    let entries = catalog.find_by_url(url)?;
+    println!("Item(s) of {}, count: {}", url, entries.len());
+    print_entries(&entries);
+    Ok(entries)
+}
+
+pub fn catalog_stats(catalog: &Catalog) -> anyhow::Result<CatalogStats> {
+    let stats = catalog.stats()?;
+    println!("Items: {}, bytes: {}", stats.items, stats.bytes);
+    for domain in &stats.domains {
+        println!(
+            "- {}: items: {}, pages: {}, bytes: {}, last: {}",
+            domain.domain_key, domain.items, domain.pages, domain.bytes, domain.last_persisted_at
+        );
+    }
+    Ok(stats)
+}
+
+fn print_entries(entries: &[CatalogEntry]) {
+    for entry in entries {
+        println!(
+            "- [{}] {} {} ({}, {} bytes, sha256 {}) -> {}",
+            entry.persisted_at,
+            entry.pattern_type,
+            entry.source_url,
+            entry.mime_type,
+            entry.byte_size,
+            entry.content_hash,
+            entry.local_path.as_deref().unwrap_or("-")
+        );
+    }
+}
@@ -6,7 +6,7 @@ pub fn list_domains(
     config_file: Option<String>,
 ) -> anyhow::Result<Vec<String>> {
     // create engine and run list...
-    let mut engine = build_engine(config_mode, config_file);
+    let mut engine = build_engine(config_mode, config_file)?;
     let domains = engine.registry().list_registered_domains();
 
     println!("Registered domain(s), count: {}", domains.len());
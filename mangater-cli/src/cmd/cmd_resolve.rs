@@ -0,0 +1,59 @@
+use mangater_sdk::entity::DomainCandidate;
+
+use crate::entity::ConfigMode;
+use crate::util::engine::build_engine;
+
+/// Prints every registered plugin in resolution order (highest priority first, then the
+/// earliest registered) with its answer for the url; the first one matching wins.
+///
+/// Plugins declaring host patterns are skipped if the url's host misses them; the others are
+/// asked through `match_domain`.
+pub fn resolve(
+    config_mode: ConfigMode,
+    config_file: Option<String>,
+    url: &str,
+) -> anyhow::Result<Vec<DomainCandidate>> {
+    let mut engine = build_engine(config_mode, config_file)?;
+    let candidates = engine.registry().resolve_candidates(url);
+
+    let winner = candidates.iter().find(|candidate| candidate.is_match());
+    match winner {
+        Some(winner) => println!(
+            "{} would be scraped by: {} (priority {})",
+            url, winner.key, winner.priority
+        ),
+        None => println!("{} is not supported by any plugin", url),
+    }
+    println!(
+        "Candidate(s) in resolution order, count: {}",
+        candidates.len()
+    );
+    for (index, candidate) in candidates.iter().enumerate() {
+        let verdict = match (&candidate.matched, candidate.host_match) {
+            (_, Some(false)) => "skipped".to_string(),
+            (Ok(true), _) if winner.is_some_and(|winner| winner.key == candidate.key) => {
+                "matched - wins".to_string()
+            }
+            (Ok(true), _) => format!(
+                "matched - shadowed by {}",
+                winner.map(|winner| winner.key.as_str()).unwrap_or_default()
+            ),
+            (Ok(false), _) => "not matched".to_string(),
+            (Err(e), _) => format!("failed to match - {}", e),
+        };
+        let hosts = match candidate.host_match {
+            Some(true) => "host matched",
+            Some(false) => "host not matched",
+            None => "no host patterns",
+        };
+        println!(
+            "{}. {} (priority {}, {}): {}",
+            index + 1,
+            candidate.key,
+            candidate.priority,
+            hosts,
+            verdict
+        );
+    }
+    Ok(candidates)
+}
@@ -1,8 +1,55 @@
+use mangater_core::orchestration::ScrapReport;
+use mangater_sdk::entity::CacheMode;
 use tracing::info;
 
-use crate::entity::ScrapArgs;
+use crate::entity::{ConfigMode, ScrapArgs};
+use crate::util::engine::build_engine;
 
-pub fn scrap(args: ScrapArgs) -> anyhow::Result<()> {
+/// Scraps the urls one after the other with the same engine (e.g. for the `epub` storage to
+/// bundle them into one book); the batch stops on the first failing url.
+pub fn scrap(
+    config_mode: ConfigMode,
+    config_file: Option<String>,
+    args: ScrapArgs,
+) -> anyhow::Result<Vec<ScrapReport>> {
     info!("* Scraping URL: {:?}, Output: {:?}", args.url, args.output);
-    Ok(())
+
+    let mut engine = build_engine(config_mode, config_file)?;
+    // plugins without their own storage have the results written under the output directory
+    if let Some(output) = args.output {
+        engine.set_output_dir(output)?;
+    }
+    // the HTTP cache (`core.cache`) is enabled with the default settings if not configured
+    if args.offline {
+        engine.set_cache_mode(CacheMode::Offline)?;
+    } else if args.refresh {
+        engine.set_cache_mode(CacheMode::Refresh)?;
+    }
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let mut reports = Vec::with_capacity(args.url.len());
+    for url in args.url {
+        let report = runtime.block_on(engine.run_scrap_workflow(url.clone()))?;
+
+        println!(
+            "Scrap done: {}, pages: {}, found: {}, downloaded: {}, failed: {}, bytes: {}",
+            url,
+            report.pages_visited,
+            report.items_found,
+            report.items_downloaded,
+            report.items_failed,
+            report.bytes_downloaded
+        );
+        for page in &report.pages {
+            info!(
+                "* page: {}, found: {}, downloaded: {}, failed: {}",
+                page.url, page.items_found, page.items_downloaded, page.items_failed
+            );
+        }
+        if let Some(stop_reason) = report.stop_reason {
+            info!("* pagination stopped: {:?}", stop_reason);
+        }
+        reports.push(report);
+    }
+    Ok(reports)
 }
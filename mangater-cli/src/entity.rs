@@ -1,3 +1,3 @@
 mod model;
 
-pub use model::{ConfigMode, LogLevel, ScrapArgs};
+pub use model::{CatalogArgs, CatalogCommand, ConfigMode, LogLevel, ScrapArgs, ScrapExitCode};
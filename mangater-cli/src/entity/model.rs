@@ -1,4 +1,5 @@
 use clap::ValueEnum;
+use mangater_sdk::SdkError;
 
 // #[derive(clap::Args, Clone, Debug)]
 // pub struct GlobalArgs {
@@ -13,13 +14,84 @@ use clap::ValueEnum;
 
 #[derive(clap::Args, Clone, Debug)]
 pub struct ScrapArgs {
-    /// URL to scrape (mandatory)
-    #[arg(short, long)]
-    pub url: String,
+    /// URL to scrape (mandatory); repeat it to scrape a batch of urls, one after the other
+    #[arg(short, long, required = true)]
+    pub url: Vec<String>,
 
-    /// Output file path
+    /// Output directory path (defaults to `core.storage.output` in config, i.e. `output`)
     #[arg(short, long)]
     pub output: Option<String>,
+
+    /// Serve every request from the HTTP cache only, never hitting the network
+    #[arg(long, conflicts_with = "refresh")]
+    pub offline: bool,
+
+    /// Download everything again, replacing the responses in the HTTP cache
+    #[arg(long)]
+    pub refresh: bool,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct CatalogArgs {
+    /// Catalog database path (defaults to `core.storage.catalog` in config)
+    #[arg(short, long)]
+    pub database: Option<String>,
+
+    #[command(subcommand)]
+    pub command: CatalogCommand,
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum CatalogCommand {
+    #[command(about = "List the items collected for a domain key (plugin)")]
+    List {
+        /// domain key, e.g. `wikipedia`
+        #[arg(short, long)]
+        domain: String,
+    },
+
+    #[command(about = "Find the items collected from a url (the item's own url or its page's)")]
+    Find {
+        #[arg(short, long)]
+        url: String,
+    },
+
+    #[command(about = "Show the figures of the catalog, per domain key")]
+    Stats,
+}
+
+/// Exit codes of the `scrap` command; distinct per failure reason so shell pipelines can branch on them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrapExitCode {
+    /// any failure not covered by the other codes
+    Other = 1,
+    /// no registered plugin supports the url
+    Unsupported = 3,
+    /// network-level failure (HTTP, DNS, timeout, rate limited, etc.)
+    Network = 4,
+    /// failure on persisting the scraped content
+    Storage = 5,
+    /// the url is disallowed by the site's robots.txt
+    Blocked = 6,
+}
+
+impl ScrapExitCode {
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<SdkError>() {
+            Some(SdkError::Unsupported(_)) => ScrapExitCode::Unsupported,
+            Some(SdkError::Network(_))
+            | Some(SdkError::RateLimited)
+            | Some(SdkError::Authentication)
+            | Some(SdkError::NotFound(_)) => ScrapExitCode::Network,
+            Some(SdkError::Storage(_)) => ScrapExitCode::Storage,
+            Some(SdkError::RobotsDisallowed(_)) => ScrapExitCode::Blocked,
+            _ => ScrapExitCode::Other,
+        }
+    }
+
+    pub fn code(&self) -> i32 {
+        *self as i32
+    }
 }
 
 #[derive(Clone, ValueEnum, Debug)]
@@ -39,14 +111,15 @@ pub enum LogLevel {
     Error,
 }
 
-impl LogLevel {
-    pub fn to_string(&self) -> String {
-        match self {
-            LogLevel::Trace => String::from("trace"),
-            LogLevel::Debug => String::from("debug"),
-            LogLevel::Info => String::from("info"),
-            LogLevel::Warn => String::from("warn"),
-            LogLevel::Error => String::from("error"),
-        }
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        };
+        write!(f, "{}", level)
     }
 }
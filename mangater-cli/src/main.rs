@@ -4,9 +4,9 @@ use clap::Parser;
 
 use crate::cli::Cli;
 use mangater_cli::cmd;
-use mangater_cli::entity::LogLevel;
+use mangater_cli::entity::{LogLevel, ScrapExitCode};
 
-use tracing::debug;
+use tracing::{debug, error};
 use tracing_subscriber::EnvFilter;
 
 fn init_tracing(log_level: &LogLevel) {
@@ -36,7 +36,20 @@ fn main() -> anyhow::Result<()> {
 
     // match the sub-command and execute the corresponding code logics
     match cli.command {
-        cli::Commands::Scrap(scrap_args) => cmd::scrap(scrap_args)?,
+        cli::Commands::Scrap(scrap_args) => {
+            if let Err(e) = cmd::scrap(cli.config_mode, cli.config, scrap_args) {
+                error!("scrap failed: {:?}", e);
+                std::process::exit(ScrapExitCode::from_error(&e).code());
+            }
+        }
+        cli::Commands::Catalog(catalog_args) => {
+            cmd::catalog(cli.config_mode, cli.config, catalog_args)?;
+            return Ok(());
+        }
+        cli::Commands::Resolve { url } => {
+            cmd::resolve(cli.config_mode, cli.config, &url)?;
+            return Ok(());
+        }
         cli::Commands::ListDomains => {
             match cmd::list_domains(cli.config_mode, cli.config) {
                 Ok(_) => {}
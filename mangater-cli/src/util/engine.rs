@@ -1,43 +1,99 @@
+use anyhow::Context;
 use mangater_core::orchestration::Engine;
 
 use std::sync::Arc;
 
 use crate::entity::ConfigMode;
 
+/// Builds the engine out of the config file and registers every plugin: the compiled-in ones,
+/// the declarative sites, the dynamic and the WebAssembly plugins.
+///
+/// # Errors
+///
+/// Fails if the config file or any of the plugins cannot be loaded; the error names the file
+/// (or folder) at fault.
 pub fn build_engine(
     config_mode: ConfigMode,
     config_file: Option<String>,
-) -> mangater_core::orchestration::Engine {
+) -> anyhow::Result<mangater_core::orchestration::Engine> {
     let mut engine = Engine::new();
 
     // load config from json5 file
     let config_file_path = config_file.unwrap_or("config.json5".to_string());
     let app_config = match config_mode {
-        ConfigMode::Json5 => engine
-            .config_load_from_json5_file(config_file_path)
-            .unwrap(),
-        ConfigMode::Json => engine.config_load_from_json_file(config_file_path).unwrap(),
-    };
+        ConfigMode::Json5 => engine.config_load_from_json5_file(config_file_path.clone()),
+        ConfigMode::Json => engine.config_load_from_json_file(config_file_path.clone()),
+    }
+    .with_context(|| format!("failed to load the config {}", config_file_path))?;
     tracing::debug!("overall app config: {:?}", app_config);
+    let site_dir = app_config.core.site_dir.clone();
+    let plugin_dir = app_config.core.plugin_dir.clone().unwrap_or_default();
+    #[cfg(feature = "wasm")]
+    let wasm_dir = app_config
+        .core
+        .wasm
+        .as_ref()
+        .and_then(|wasm| wasm.plugin_dir.clone())
+        .unwrap_or_default();
 
     // *** wikipedia plugin registration ***
     #[cfg(feature = "wikipedia")]
     {
-        use mangater_sdk::traits::{Config, Domain};
+        use mangater_sdk::traits::{Config, Domain, DEFAULT_PRIORITY};
         use site_wikipedia::WikipediaInstance;
 
         let mut wikipedia = WikipediaInstance::new();
         // run config pre-load
         // [lesson] only the plugin that requires a custom config section would need to implement the Config trait
-        wikipedia.load(app_config.plugins.clone()).unwrap();
+        wikipedia
+            .load(app_config.plugins.clone())
+            .with_context(|| format!("failed to configure wikipedia ({})", config_file_path))?;
+        // the winner among plugins matching the same url (`plugins.wikipedia.core.priority`)
+        let priority = app_config
+            .plugin_overrides(&wikipedia.get_domain_key())
+            .with_context(|| format!("failed to configure wikipedia ({})", config_file_path))?
+            .priority
+            .unwrap_or(DEFAULT_PRIORITY);
 
         // register the wikipedia domain / plugin to the engine's registry
-        engine.registry().add_to_registry(
+        engine.registry().add_to_registry_with_priority(
             Some(wikipedia.get_domain_key()),
             Arc::new(wikipedia.clone()),
+            priority,
         );
     }
     //engine.registry().add_to_registry(None, Box::new(wikipedia::Wikipedia::new()));
 
-    engine
+    // *** declarative sites (`plugins.<key>.url_regex` sections and `core.site_dir` files) ***
+    let declarative_sites = engine
+        .load_declarative_sites()
+        .with_context(|| match &site_dir {
+            Some(site_dir) => format!(
+                "failed to load the declarative sites of {} and {}",
+                config_file_path, site_dir
+            ),
+            None => format!(
+                "failed to load the declarative sites of {}",
+                config_file_path
+            ),
+        })?;
+    tracing::debug!("declarative site(s): {:?}", declarative_sites);
+
+    // *** dynamic plugins (`core.plugin_dir`); replace the compiled-in ones of the same key ***
+    // (no stable ABI: only plugins built with the same sdk and rustc as this binary load)
+    let dynamic_plugins = engine
+        .load_dynamic_plugins()
+        .with_context(|| format!("failed to load the dynamic plugins of {}", plugin_dir))?;
+    tracing::debug!("dynamic plugin(s): {:?}", dynamic_plugins);
+
+    // *** WebAssembly plugins (`core.wasm.plugin_dir`), sandboxed; same as the dynamic ones ***
+    #[cfg(feature = "wasm")]
+    {
+        let wasm_plugins = engine
+            .load_wasm_plugins()
+            .with_context(|| format!("failed to load the wasm plugins of {}", wasm_dir))?;
+        tracing::debug!("wasm plugin(s): {:?}", wasm_plugins);
+    }
+
+    Ok(engine)
 }
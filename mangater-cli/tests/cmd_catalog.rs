@@ -0,0 +1,59 @@
+use mangater_cli::cmd;
+use mangater_cli::entity::{CatalogArgs, CatalogCommand, ConfigMode};
+use mangater_sdk::storage::{Catalog, CatalogEntry};
+
+fn entry(domain_key: &str, source_url: &str, byte_size: u64) -> CatalogEntry {
+    CatalogEntry {
+        source_url: source_url.to_string(),
+        page_url: format!("https://{}.example.com/page", domain_key),
+        domain_key: domain_key.to_string(),
+        pattern: "img".to_string(),
+        pattern_type: "Resource".to_string(),
+        content_hash: "00".repeat(32),
+        byte_size,
+        mime_type: "image/png".to_string(),
+        local_path: None,
+        persisted_at: "2026-10-18T10:00:00Z".to_string(),
+    }
+}
+
+#[test]
+fn test_cmd_catalog_queries() {
+    let catalog = Catalog::open_in_memory().unwrap();
+    catalog
+        .record(&entry("wikipedia", "https://upload.example.org/a.png", 10))
+        .unwrap();
+    catalog
+        .record(&entry("wikipedia", "https://upload.example.org/b.png", 20))
+        .unwrap();
+    catalog
+        .record(&entry("manga", "https://cdn.example.com/1.png", 5))
+        .unwrap();
+
+    let listed = cmd::catalog_list(&catalog, "wikipedia").unwrap();
+    assert_eq!(listed.len(), 2);
+
+    let found = cmd::catalog_find(&catalog, "https://cdn.example.com/1.png").unwrap();
+    assert_eq!(
+        found,
+        vec![entry("manga", "https://cdn.example.com/1.png", 5)]
+    );
+
+    let stats = cmd::catalog_stats(&catalog).unwrap();
+    assert_eq!(stats.items, 3);
+    assert_eq!(stats.bytes, 35);
+    assert_eq!(stats.domains.len(), 2);
+}
+
+#[test]
+fn test_cmd_catalog_not_configured() {
+    let result = cmd::catalog(
+        ConfigMode::Json5,
+        Some("testdata/config.json5".to_string()),
+        CatalogArgs {
+            database: None,
+            command: CatalogCommand::Stats,
+        },
+    );
+    assert!(result.is_err());
+}
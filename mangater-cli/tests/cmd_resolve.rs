@@ -0,0 +1,37 @@
+use mangater_cli::cmd;
+use mangater_cli::entity::ConfigMode;
+
+#[cfg(feature = "wikipedia")]
+#[test]
+fn test_cmd_resolve() {
+    let candidates = cmd::resolve(
+        ConfigMode::Json5,
+        Some("testdata/config.json5".to_string()),
+        "https://en.wikipedia.org/wiki/Manga",
+    )
+    .unwrap();
+    let winner = candidates.iter().find(|c| c.is_match()).unwrap();
+    assert_eq!(winner.key, "wikipedia");
+    assert_eq!(winner.priority, 0);
+
+    let candidates = cmd::resolve(
+        ConfigMode::Json5,
+        Some("testdata/config.json5".to_string()),
+        "https://example.com/",
+    )
+    .unwrap();
+    assert!(!candidates.is_empty());
+    assert!(candidates.iter().all(|c| !c.is_match()));
+
+    // the host matches the patterns of wikipedia, the scheme does not match the plugin
+    let candidates = cmd::resolve(
+        ConfigMode::Json5,
+        Some("testdata/config.json5".to_string()),
+        "http://en.wikipedia.org/wiki/Manga",
+    )
+    .unwrap();
+    let wikipedia = candidates.iter().find(|c| c.key == "wikipedia").unwrap();
+    assert_eq!(wikipedia.host_match, Some(true));
+    assert!(!wikipedia.is_match());
+    assert!(candidates.iter().all(|c| !c.is_match()));
+}
@@ -0,0 +1,45 @@
+use mangater_cli::cmd;
+use mangater_cli::entity::{ConfigMode, ScrapArgs, ScrapExitCode};
+use mangater_sdk::SdkError;
+
+#[test]
+fn test_cmd_scrap_unsupported_url() {
+    let result = cmd::scrap(
+        ConfigMode::Json5,
+        Some("testdata/config.json5".to_string()),
+        ScrapArgs {
+            url: vec!["https://unsupported.example.com/chapter/1".to_string()],
+            output: None,
+            offline: false,
+            refresh: false,
+        },
+    );
+    let err = result.expect_err("Expected the url to be unsupported");
+    assert_eq!(ScrapExitCode::from_error(&err), ScrapExitCode::Unsupported);
+    assert_eq!(ScrapExitCode::from_error(&err).code(), 3);
+}
+
+#[test]
+fn test_scrap_exit_code_mapping() {
+    let network: anyhow::Error = SdkError::Network("dns".to_string()).into();
+    assert_eq!(ScrapExitCode::from_error(&network), ScrapExitCode::Network);
+
+    let rate_limited: anyhow::Error = SdkError::RateLimited.into();
+    assert_eq!(
+        ScrapExitCode::from_error(&rate_limited),
+        ScrapExitCode::Network
+    );
+
+    let storage: anyhow::Error = SdkError::Storage(std::io::Error::other("disk full")).into();
+    assert_eq!(ScrapExitCode::from_error(&storage), ScrapExitCode::Storage);
+
+    let blocked: anyhow::Error =
+        SdkError::RobotsDisallowed("https://example.com/private".to_string()).into();
+    assert_eq!(ScrapExitCode::from_error(&blocked).code(), 6);
+
+    let parse: anyhow::Error = SdkError::Parse("selector".to_string()).into();
+    assert_eq!(ScrapExitCode::from_error(&parse), ScrapExitCode::Other);
+
+    let other = anyhow::anyhow!("runtime failure");
+    assert_eq!(ScrapExitCode::from_error(&other), ScrapExitCode::Other);
+}
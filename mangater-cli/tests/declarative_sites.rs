@@ -0,0 +1,120 @@
+use mangater_cli::cmd;
+use mangater_cli::entity::ConfigMode;
+use mangater_cli::util::engine::build_engine;
+use mangater_sdk::entity::PatternType;
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!(
+        "mangater-cli-sites-{}-{}-{}",
+        name,
+        std::process::id(),
+        nanos
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// a config declaring `comics` in its plugins section and `manga` in a site file
+fn config_with_sites(dir: &std::path::Path) -> String {
+    let sites = dir.join("sites");
+    std::fs::create_dir_all(&sites).unwrap();
+    std::fs::write(
+        sites.join("manga.json5"),
+        r#"{
+            domain_key: "manga",
+            url_regex: "^https://manga\\.example\\.com/chapter/",
+            patterns: [
+                { pattern: "img.page", pattern_type: "resource" },
+                { pattern: "a.next", pattern_type: "pagination" },
+            ],
+        }"#,
+    )
+    .unwrap();
+
+    let config = dir.join("config.json5");
+    std::fs::write(
+        &config,
+        format!(
+            r#"{{
+                core: {{ site_dir: {:?} }},
+                plugins: {{
+                    comics: {{
+                        url_regex: "^https://comics\\.example\\.com/",
+                        host_patterns: ["comics.example.com"],
+                        patterns: [{{ pattern: ".strip img", pattern_type: "resource" }}],
+                        core: {{ priority: 4 }},
+                    }},
+                }},
+            }}"#,
+            sites.display().to_string()
+        ),
+    )
+    .unwrap();
+    config.display().to_string()
+}
+
+#[test]
+fn test_build_engine_registers_declarative_sites() {
+    let dir = temp_dir("build");
+    let config = config_with_sites(&dir);
+
+    let mut engine = build_engine(ConfigMode::Json5, Some(config.clone())).unwrap();
+    let domains = engine.registry().list_registered_domains();
+    assert!(domains.contains(&"comics".to_string()));
+    assert!(domains.contains(&"manga".to_string()));
+
+    let candidates = cmd::resolve(
+        ConfigMode::Json5,
+        Some(config.clone()),
+        "https://comics.example.com/strip/1",
+    )
+    .unwrap();
+    let winner = candidates.iter().find(|c| c.is_match()).unwrap();
+    assert_eq!(winner.key, "comics");
+    assert_eq!(winner.priority, 4);
+
+    let candidates = cmd::resolve(
+        ConfigMode::Json5,
+        Some(config),
+        "https://manga.example.com/chapter/1",
+    )
+    .unwrap();
+    let winner = candidates.iter().find(|c| c.is_match()).unwrap();
+    assert_eq!(winner.key, "manga");
+    let pattern_types: Vec<PatternType> = winner
+        .domain
+        .get_domain_registerable()
+        .matcher
+        .match_patterns()
+        .into_iter()
+        .map(|p| p.pattern_type)
+        .collect();
+    assert_eq!(
+        pattern_types,
+        vec![PatternType::Resource, PatternType::Pagination]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_build_engine_reports_broken_site_file() {
+    let dir = temp_dir("broken");
+    let config = config_with_sites(&dir);
+    let broken = dir.join("sites").join("broken.json5");
+    std::fs::write(&broken, "{ domain_key: \"broken\" }").unwrap();
+
+    let error = build_engine(ConfigMode::Json5, Some(config.clone()))
+        .err()
+        .expect("a broken site file fails the engine");
+    assert!(error.to_string().contains(&config));
+    assert!(format!("{:#}", error).contains(&broken.display().to_string()));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -5,11 +5,12 @@ use mangater_cli::util::engine::build_engine;
 #[cfg(feature = "wikipedia")]
 #[test]
 fn test_engine_registry_list_domains() {
-    let mut engine = build_engine(ConfigMode::Json5, Some("testdata/config.json5".to_string()));
+    let mut engine =
+        build_engine(ConfigMode::Json5, Some("testdata/config.json5".to_string())).unwrap();
     let domains = engine.registry().list_registered_domains();
 
     assert!(!domains.is_empty());
-    assert_eq!(domains.contains(&"wikipedia".to_string()), true);
+    assert!(domains.contains(&"wikipedia".to_string()));
 }
 
 #[cfg(feature = "wikipedia")]
@@ -20,7 +21,18 @@ fn test_cmd_list_domains() {
 
     let domains = result.unwrap();
     assert!(!domains.is_empty());
-    assert_eq!(domains.contains(&"wikipedia".to_string()), true);
+    assert!(domains.contains(&"wikipedia".to_string()));
+}
+
+#[test]
+fn test_build_engine_missing_config() {
+    let error = build_engine(
+        ConfigMode::Json5,
+        Some("testdata/missing.json5".to_string()),
+    )
+    .err()
+    .expect("a missing config fails the engine");
+    assert!(error.to_string().contains("testdata/missing.json5"));
 }
 
 // this test depends on how Cargo.toml was configured
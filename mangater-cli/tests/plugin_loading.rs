@@ -0,0 +1,126 @@
+use mangater_cli::entity::ConfigMode;
+use mangater_cli::util::engine::build_engine;
+use mangater_core::orchestration::Engine;
+use mangater_sdk::entity::PatternType;
+use mangater_sdk::SdkError;
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// the wikipedia cdylib cargo builds next to this test (site-wikipedia-plugin)
+fn wikipedia_library() -> PathBuf {
+    std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join(format!(
+            "{}site_wikipedia_plugin{}",
+            std::env::consts::DLL_PREFIX,
+            std::env::consts::DLL_SUFFIX
+        ))
+}
+
+fn plugin_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!(
+        "mangater-cli-plugins-{}-{}-{}",
+        name,
+        std::process::id(),
+        nanos
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn engine_with_plugin_dir(dir: &std::path::Path, plugins: &str) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .config_load_from_json5_str(&format!(
+            "{{ core: {{ plugin_dir: {:?} }}, plugins: {} }}",
+            dir.display().to_string(),
+            plugins
+        ))
+        .unwrap();
+    engine
+}
+
+#[test]
+fn test_load_dynamic_plugins() {
+    let dir = plugin_dir("wikipedia");
+    std::fs::copy(
+        wikipedia_library(),
+        dir.join(wikipedia_library().file_name().unwrap()),
+    )
+    .unwrap();
+
+    let mut engine = engine_with_plugin_dir(
+        &dir,
+        "{ wikipedia: { need_content: true, core: { priority: 3 } } }",
+    );
+    assert_eq!(engine.load_dynamic_plugins().unwrap(), vec!["wikipedia"]);
+
+    let candidates = engine
+        .registry()
+        .resolve_candidates("https://en.wikipedia.org/wiki/Manga");
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].key, "wikipedia");
+    assert_eq!(candidates[0].priority, 3);
+    assert!(candidates[0].is_match());
+
+    // the plugin got its config section
+    let patterns = candidates[0]
+        .domain
+        .get_domain_registerable()
+        .matcher
+        .match_patterns();
+    let pattern_types: Vec<PatternType> = patterns.into_iter().map(|p| p.pattern_type).collect();
+    assert_eq!(
+        pattern_types,
+        vec![PatternType::Resource, PatternType::Content]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_load_dynamic_plugins_not_a_plugin() {
+    let dir = plugin_dir("broken");
+    std::fs::write(
+        dir.join(format!("broken.{}", std::env::consts::DLL_EXTENSION)),
+        "not a library",
+    )
+    .unwrap();
+
+    let mut engine = engine_with_plugin_dir(&dir, "{}");
+    match engine.load_dynamic_plugins() {
+        Err(SdkError::Plugin(msg)) => assert!(msg.contains("broken")),
+        other => panic!("Expected SdkError::Plugin, got {:?}", other),
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_build_engine_reports_broken_plugin() {
+    let dir = plugin_dir("build");
+    let library = dir.join(format!("broken.{}", std::env::consts::DLL_EXTENSION));
+    std::fs::write(&library, "not a library").unwrap();
+    let config = dir.join("config.json5");
+    std::fs::write(
+        &config,
+        format!(
+            "{{ core: {{ plugin_dir: {:?} }}, plugins: {{}} }}",
+            dir.display().to_string()
+        ),
+    )
+    .unwrap();
+
+    let error = build_engine(ConfigMode::Json5, Some(config.display().to_string()))
+        .err()
+        .expect("a broken plugin fails the engine");
+    assert!(error.to_string().contains(&dir.display().to_string()));
+    assert!(format!("{:#}", error).contains(&library.display().to_string()));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
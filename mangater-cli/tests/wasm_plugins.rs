@@ -0,0 +1,111 @@
+#![cfg(feature = "wasm")]
+
+use mangater_cli::entity::ConfigMode;
+use mangater_cli::util::engine::build_engine;
+use mangater_core::orchestration::Engine;
+use mangater_sdk::SdkError;
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// the hand-written test component of mangater-core (text format, loaded as a `.wasm` file)
+fn echo_plugin() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../crates/mangater-core/testing/wasm/echo-plugin.wat")
+}
+
+fn plugin_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!(
+        "mangater-cli-wasm-plugins-{}-{}-{}",
+        name,
+        std::process::id(),
+        nanos
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn engine_with_wasm_dir(dir: &Path, plugins: &str) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .config_load_from_json5_str(&format!(
+            "{{ core: {{ wasm: {{ plugin_dir: {:?} }} }}, plugins: {} }}",
+            dir.display().to_string(),
+            plugins
+        ))
+        .unwrap();
+    engine
+}
+
+#[test]
+fn test_load_wasm_plugins() {
+    let dir = plugin_dir("echo");
+    std::fs::copy(echo_plugin(), dir.join("echo.wasm")).unwrap();
+    // not a `.wasm` file: ignored
+    std::fs::write(dir.join("README.md"), "not a plugin").unwrap();
+
+    let mut engine = engine_with_wasm_dir(&dir, "{ 'wasm-echo': { core: { priority: 5 } } }");
+    assert_eq!(engine.load_wasm_plugins().unwrap(), vec!["wasm-echo"]);
+
+    let candidates = engine
+        .registry()
+        .resolve_candidates("https://echo.example.com/chapter/1");
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].key, "wasm-echo");
+    assert_eq!(candidates[0].priority, 5);
+    assert_eq!(candidates[0].host_match, Some(true));
+    assert!(candidates[0].is_match());
+
+    let candidates = engine
+        .registry()
+        .resolve_candidates("https://en.wikipedia.org/wiki/Manga");
+    assert!(!candidates[0].is_match());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_load_wasm_plugins_not_a_plugin() {
+    let dir = plugin_dir("broken");
+    std::fs::write(dir.join("broken.wasm"), "not a component").unwrap();
+
+    let mut engine = engine_with_wasm_dir(&dir, "{}");
+    match engine.load_wasm_plugins() {
+        Err(SdkError::Plugin(msg)) => assert!(msg.contains("broken.wasm")),
+        other => panic!("Expected SdkError::Plugin, got {:?}", other),
+    }
+
+    // no folder configured: nothing loaded
+    let mut engine = Engine::new();
+    engine.config_load_from_json5_str("{ core: {} }").unwrap();
+    assert!(engine.load_wasm_plugins().unwrap().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_build_engine_reports_broken_wasm_plugin() {
+    let dir = plugin_dir("build");
+    let component = dir.join("broken.wasm");
+    std::fs::write(&component, "not a component").unwrap();
+    let config = dir.join("config.json5");
+    std::fs::write(
+        &config,
+        format!(
+            "{{ core: {{ wasm: {{ plugin_dir: {:?} }} }}, plugins: {{}} }}",
+            dir.display().to_string()
+        ),
+    )
+    .unwrap();
+
+    let error = build_engine(ConfigMode::Json5, Some(config.display().to_string()))
+        .err()
+        .expect("a broken wasm plugin fails the engine");
+    assert!(error.to_string().contains(&dir.display().to_string()));
+    assert!(format!("{:#}", error).contains(&component.display().to_string()));
+    std::fs::remove_dir_all(&dir).unwrap();
+}